@@ -124,7 +124,7 @@ export const updateUserSettings = (settings: Settings) => invokeWithFeedback<voi
 
 // --- Chat, Creation & Agent ---
 export const createConversation = (sessionType: 'chat' | 'creation', title?: string) => invokeWithFeedback<Conversation>('create_conversation', { sessionType, title });
-export const processChatMessage = (userMessage: ChatMessage, aiMessageId: string, apiConfig: ApiConfig) => invokeWithFeedback<void>('process_chat_message', { userMessage, aiMessageId, apiConfig });
+export const processChatMessage = (userMessage: ChatMessage, aiMessageId: string, apiConfig: ApiConfig, templateVariables?: Record<string, string>) => invokeWithFeedback<void>('process_chat_message', { userMessage, aiMessageId, apiConfig, templateVariables });
 export const processAgenticInstruction = (conversationId: string, instruction: string, apiConfig: ApiConfig, mode: 'plan' | 'explore' | 'write' | 'research' | 'debate', knowledgeBaseSelection: string) => invokeWithFeedback<{ task_id: string }>('process_agentic_instruction', { conversationId, instruction, apiConfig, mode, knowledgeBaseSelection });
 export const stopChatGeneration = (messageId: string) => invokeWithFeedback<void>('stop_chat_generation', { messageId });
 export const stopAgentTask = (taskId: string) => invokeWithFeedback<void>('stop_agent_task', { taskId });
@@ -203,6 +203,7 @@ export const onCopilotContextUpdate = (handler: (payload: OverlayContext) => voi
 export const onCopilotShown = (handler: () => void): Promise<UnlistenFn> => listen('copilot-shown', () => handler());
 export const onChatMessageChunk = (handler: (payload: { messageId: string; chunk: string }) => void): Promise<UnlistenFn> => listen('stream-chunk', (event) => handler(event.payload as any));
 export const onChatMessageEnd = (handler: (payload: { messageId: string; finalMessage: any }) => void): Promise<UnlistenFn> => listen('stream-end', (event) => handler(event.payload as any));
+export const onChatMessageRetry = (handler: (payload: { messageId: string; model: string; reason: string }) => void): Promise<UnlistenFn> => listen('stream-retry', (event) => handler(event.payload as any));
 export const onIndexingProgress = (handler: (payload: { file: string; progress: number }) => void): Promise<UnlistenFn> => listen('indexing-progress', (event) => handler(event.payload as any));
 export const onConversationCreated = (handler: (payload: Conversation) => void): Promise<UnlistenFn> => listen('conversation-created', (event) => handler(event.payload as Conversation));
 export const onConversationTitleUpdated = (handler: (payload: Conversation) => void): Promise<UnlistenFn> => listen('conversation-title-updated', (event) => handler(event.payload as Conversation));
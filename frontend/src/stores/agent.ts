@@ -132,7 +132,7 @@ export const useAgentStore = defineStore('agent', () => {
             const chatStore = useChatStore();
             const message = chatStore.findMessageById(placeholderMessage.id);
             if (message) {
-                message.error = 'Could not initiate agent task on the backend. Please check logs.';
+                message.error = { kind: 'backend_unavailable', message: 'Could not initiate agent task on the backend. Please check logs.', retryable: true, partial: false };
                 message.model = 'ai';
                 message.content = [{ type: 'text', text: 'Agent task failed to start.' }];
             }
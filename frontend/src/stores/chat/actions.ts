@@ -29,6 +29,7 @@ interface SendMessagePayload {
   conversationIdOverride?: string;
   modelOverride?: string;
   kbSelectionOverride?: string;
+  templateVariables?: Record<string, string>; // e.g. `selection` for the copilot's captured text
 }
 
 export function useChatActions(state: ToRefs<ReturnType_useChatState>) {
@@ -278,7 +279,7 @@ export function useChatActions(state: ToRefs<ReturnType_useChatState>) {
   }
 
   async function sendMessage(payload: SendMessagePayload) {
-    const { content, conversationIdOverride, modelOverride, kbSelectionOverride } = payload;
+    const { content, conversationIdOverride, modelOverride, kbSelectionOverride, templateVariables } = payload;
     const conversationId = conversationIdOverride || currentConversationId.value;
 
     if (!conversationId) return;
@@ -342,7 +343,7 @@ export function useChatActions(state: ToRefs<ReturnType_useChatState>) {
     apiConfigToSend.knowledgeBase = settingsStore.settings.knowledgeBase;
     apiConfigToSend.execution = settingsStore.settings.execution;
 
-    processChatMessage(userMessage, aiMessageId, apiConfigToSend);
+    processChatMessage(userMessage, aiMessageId, apiConfigToSend, templateVariables);
   }
 
   async function stopGeneration() {
@@ -5,6 +5,7 @@ import {
     onConversationTitleUpdated,
     onChatMessageChunk,
     onChatMessageEnd,
+    onChatMessageRetry,
     onCodeExecutionOutput,
     onCodeExecutionComplete,
 } from '../../lib/api';
@@ -27,6 +28,7 @@ export function setupChatListeners(
   let unlistenTitleUpdated: UnlistenFn | null = null;
   let unlistenStreamChunk: UnlistenFn | null = null;
   let unlistenStreamEnd: UnlistenFn | null = null;
+  let unlistenStreamRetry: UnlistenFn | null = null;
   let unlistenCodeOutput: UnlistenFn | null = null;
   let unlistenCodeComplete: UnlistenFn | null = null;
 
@@ -132,6 +134,16 @@ export function setupChatListeners(
         streamingMessageId.value = null;
     });
 
+    // The reply failed and is requested again, possibly from another model: what streamed so far is dropped.
+    unlistenStreamRetry = await onChatMessageRetry(({ messageId, model }) => {
+        const message = findMessageById(messageId);
+        if (!message) return;
+        message.content = message.content.filter(part => part.type !== 'text');
+        message.model = model;
+        delete message.thinkingProcess;
+        delete message.isThinking;
+    });
+
     unlistenCodeOutput = await onCodeExecutionOutput(({ executionId, chunk }) => {
         const message = findMessageById(executionId);
         if (message) {
@@ -154,6 +166,7 @@ export function setupChatListeners(
     unlistenTitleUpdated?.();
     unlistenStreamChunk?.();
     unlistenStreamEnd?.();
+    unlistenStreamRetry?.();
     unlistenCodeOutput?.();
     unlistenCodeComplete?.();
   });
@@ -8,6 +8,25 @@ export interface ChatMessageAttachment {
   url: string;
 }
 
+export type ChatErrorKind =
+  | 'auth'
+  | 'rate_limit'
+  | 'context_length'
+  | 'network'
+  | 'provider_client'
+  | 'provider_server'
+  | 'backend_unavailable'
+  | 'config'
+  | 'unknown';
+
+export interface ChatError {
+  kind: ChatErrorKind;
+  message: string;
+  status?: number; // HTTP status from the provider, if there was one
+  retryable: boolean;
+  partial: boolean; // some text arrived before the failure
+}
+
 export interface ChatMessage {
   id: string;
   conversationId: string;
@@ -15,7 +34,7 @@ export interface ChatMessage {
   content: ChatMessageContentPart[];
   timestamp: number;
   sources?: KnowledgeSource[];
-  error?: string;
+  error?: ChatError;
   suggestions?: string[];
   model?: string; // e.g., "provider-id::model-name" or "agent" or "agent-starting"
   knowledgeBaseSelection?: string; // 'none', 'all', or a specific path
@@ -343,7 +362,17 @@ export interface FileNode {
   fileType?: 'text' | 'pdf' | 'doc' | 'ppt';
 }
 
-export type ToolParameterType = 'text' | 'textarea';
+export type ToolParameterType =
+  | 'text'
+  | 'textarea'
+  | 'number'
+  | 'boolean'
+  | 'select'
+  | 'file'
+  | 'directory'
+  | 'date' // "YYYY-MM-DD"
+  | 'secret' // masked in the execution log
+  | 'json';
 
 export interface ToolParameter {
   name: string;
@@ -351,6 +380,7 @@ export interface ToolParameter {
   paramType: ToolParameterType;
   defaultValue: string;
   required: boolean;
+  options?: string[]; // allowed values for 'select'
 }
 
 export type ToolInputSource = 'user_input' | 'clipboard' | 'chat_selection';
@@ -0,0 +1,45 @@
+// src-tauri/src/commands/api_server.rs
+use crate::{
+    database::queries,
+    error::Result,
+    services::{self, api_server::ApiServerStatus},
+    state::AppState,
+};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn get_api_server_status() -> Result<ApiServerStatus> {
+    Ok(services::api_server::status().await)
+}
+
+#[tauri::command]
+pub async fn start_api_server(app: AppHandle, state: State<'_, AppState>) -> Result<ApiServerStatus> {
+    {
+        let conn = state.db.lock().unwrap();
+        let mut settings = queries::get_settings(&conn)?;
+        settings.api_server.enabled = true;
+        queries::save_settings(&conn, &settings)?;
+    }
+    services::api_server::start(app, state.inner().clone()).await
+}
+
+#[tauri::command]
+pub async fn stop_api_server(state: State<'_, AppState>) -> Result<()> {
+    {
+        let conn = state.db.lock().unwrap();
+        let mut settings = queries::get_settings(&conn)?;
+        settings.api_server.enabled = false;
+        queries::save_settings(&conn, &settings)?;
+    }
+    services::api_server::stop().await
+}
+
+#[tauri::command]
+pub async fn regenerate_api_server_key(state: State<'_, AppState>) -> Result<String> {
+    let conn = state.db.lock().unwrap();
+    let mut settings = queries::get_settings(&conn)?;
+    settings.api_server.api_key = services::api_server::generate_api_key();
+    queries::save_settings(&conn, &settings)?;
+    log::info!("Regenerated local API server key.");
+    Ok(settings.api_server.api_key)
+}
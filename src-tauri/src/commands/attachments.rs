@@ -0,0 +1,35 @@
+// src-tauri/src/commands/attachments.rs
+use crate::{
+    database::models,
+    error::{AppError, Result},
+    services::attachments,
+    state::AppState,
+};
+use base64::{engine::general_purpose, Engine as _};
+use std::path::Path;
+use tauri::State;
+
+/// Copies a file into the attachment store. Use the returned attachment as a `file` or `audio`
+/// content part, or its `path` as the URL of an `image_url` part.
+#[tauri::command]
+pub async fn add_attachment(state: State<'_, AppState>, path: String) -> Result<models::Attachment> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || attachments::store_file(&state, Path::new(&path))).await?
+}
+
+/// Stores base64 `data` that isn't on disk yet, e.g. a voice recording or a pasted file.
+#[tauri::command]
+pub async fn add_attachment_data(state: State<'_, AppState>, name: String, data: String) -> Result<models::Attachment> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let bytes = general_purpose::STANDARD.decode(data.trim())
+            .map_err(|e| AppError::Parse(format!("Invalid attachment data: {}", e)))?;
+        attachments::store(&state, &name, &bytes)
+    }).await?
+}
+
+/// Transcribes an audio attachment ahead of sending, so the user can check the text.
+#[tauri::command]
+pub async fn transcribe_attachment(state: State<'_, AppState>, attachment: models::Attachment) -> Result<String> {
+    attachments::transcribe(&state, &attachment).await
+}
@@ -1,9 +1,10 @@
 use crate::{
-    database::queries,
+    database::{models, queries},
     error::Result,
+    services,
     state::AppState,
 };
-use std::{fs, io::Write};
+use std::{fs, io::Write, path::Path};
 use tauri::State;
 
 #[tauri::command]
@@ -58,4 +59,40 @@ pub async fn import_data(state: State<'_, AppState>, file_path: String) -> Resul
 
     log::info!("Data import request processed by backend successfully.");
     Ok(())
-}
\ No newline at end of file
+}
+/// Exports one conversation as "markdown", "html", "json" or "pdf".
+#[tauri::command]
+pub async fn export_conversation(
+    state: State<'_, AppState>,
+    conversation_id: String,
+    format: String,
+    file_path: String,
+    options: Option<models::ConversationExportOptions>,
+) -> Result<()> {
+    services::export::export_conversation(&state, &conversation_id, &format, Path::new(&file_path), &options.unwrap_or_default()).await
+}
+
+/// Exports all conversations into a directory, or a zip archive when `path` ends in `.zip`.
+#[tauri::command]
+pub async fn export_all_conversations(
+    state: State<'_, AppState>,
+    format: String,
+    path: String,
+    options: Option<models::ConversationExportOptions>,
+) -> Result<usize> {
+    services::export::export_all_conversations(&state, &format, Path::new(&path), &options.unwrap_or_default()).await
+}
+
+/// Imports a ChatGPT, Claude or OpenAI JSONL export. With `dry_run`, only reports what would be imported.
+#[tauri::command]
+pub async fn import_chat_history(
+    state: State<'_, AppState>,
+    path: String,
+    format: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<models::ImportStats> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        services::importers::import_history(&state, Path::new(&path), format.as_deref(), dry_run.unwrap_or(false))
+    }).await?
+}
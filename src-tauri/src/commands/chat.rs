@@ -2,11 +2,16 @@
 use crate::{
     database::{models, queries},
     error::{AppError, Result},
-    services,
+    services::{
+        self,
+        chat::context,
+        usage::{CallUsage, UsageCall},
+    },
     state::AppState,
 };
 use futures_util::StreamExt;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::fs;
 use tauri::{AppHandle, Manager, State};
 use uuid::Uuid;
@@ -40,6 +45,8 @@ pub fn create_conversation(
         created_at: chrono::Utc::now().timestamp_millis(),
         session_type: session_type.clone(),
         artifacts: vec![],
+        active_leaf_id: None,
+        persona_id: None,
     };
 
     if session_type == "creation" {
@@ -79,63 +86,83 @@ pub async fn generate_artifact(
         "provider": provider,
     });
 
-    let response = state.http_client.post(url).json(&request_body).send().await?;
+    let artifact_id = Uuid::new_v4().to_string();
+    let kind = match creation_type.as_str() {
+        "image" => "image",
+        "audio" => "speech",
+        "video" => "video",
+        _ => "chat",
+    };
+    let call = UsageCall::start(&provider, &model_name, kind, "creation");
+    let generated: Result<(String, String, Option<String>)> = async {
+        let response = state.http_client.post(url).json(&request_body).send().await?;
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown backend error".to_string());
-        return Err(AppError::ApiClient(format!("Backend generation failed: {}", error_text)));
-    }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown backend error".to_string());
+            return Err(AppError::ApiClient(format!("Backend generation failed: {}", error_text)));
+        }
 
-    let artifact_id = Uuid::new_v4().to_string();
-    let final_file_path;
-    let mut final_prompt = prompt.clone();
-    let content_type_header;
-
-    let response_content_type = response.headers()
-        .get("content-type")
-        .and_then(|value| value.to_str().ok());
-
-    if response_content_type.map_or(false, |ct| ct.contains("application/json")) {
-        let json_body: Value = response.json().await?;
-        final_file_path = json_body["url"].as_str().unwrap_or_default().to_string();
-        final_prompt = json_body["prompt"].as_str().unwrap_or(&prompt).to_string();
-        content_type_header = json_body["content_type"].as_str().map(|s| s.to_string());
-    } else if response_content_type.map_or(false, |ct| ct.contains("text/event-stream")) {
-        // Handle SSE for video
-        let mut stream = response.bytes_stream();
-        let mut final_payload: Option<Value> = None;
-
-        while let Some(item) = stream.next().await {
-            let chunk = item?;
-            let lines = String::from_utf8_lossy(&chunk);
-            for line in lines.lines() {
-                if line.starts_with("data:") {
-                    let data_str = line.strip_prefix("data:").unwrap().trim();
-                    if let Ok(data_json) = serde_json::from_str::<Value>(data_str) {
-                        if data_json.get("status").and_then(Value::as_str) == Some("SUCCESS") {
-                            final_payload = Some(data_json.clone());
+        let final_file_path;
+        let mut final_prompt = prompt.clone();
+        let content_type_header;
+
+        let response_content_type = response.headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok());
+
+        if response_content_type.map_or(false, |ct| ct.contains("application/json")) {
+            let json_body: Value = response.json().await?;
+            final_file_path = json_body["url"].as_str().unwrap_or_default().to_string();
+            final_prompt = json_body["prompt"].as_str().unwrap_or(&prompt).to_string();
+            content_type_header = json_body["content_type"].as_str().map(|s| s.to_string());
+        } else if response_content_type.map_or(false, |ct| ct.contains("text/event-stream")) {
+            // Handle SSE for video
+            let mut stream = response.bytes_stream();
+            let mut final_payload: Option<Value> = None;
+
+            while let Some(item) = stream.next().await {
+                let chunk = item?;
+                let lines = String::from_utf8_lossy(&chunk);
+                for line in lines.lines() {
+                    if line.starts_with("data:") {
+                        let data_str = line.strip_prefix("data:").unwrap().trim();
+                        if let Ok(data_json) = serde_json::from_str::<Value>(data_str) {
+                            if data_json.get("status").and_then(Value::as_str) == Some("SUCCESS") {
+                                final_payload = Some(data_json.clone());
+                            }
+                            app_handle.emit_all("artifact-progress", data_json)?;
                         }
-                        app_handle.emit_all("artifact-progress", data_json)?;
                     }
                 }
             }
+
+            let final_data = final_payload.ok_or_else(|| AppError::Internal("Video stream ended without a success payload.".to_string()))?;
+            final_file_path = final_data["url"].as_str().unwrap_or_default().to_string();
+            final_prompt = final_data["prompt"].as_str().unwrap_or(&prompt).to_string();
+            content_type_header = final_data["content_type"].as_str().map(|s| s.to_string());
+        } else {
+            // Handle direct binary stream (e.g., audio)
+            content_type_header = response_content_type.map(|s| s.to_string());
+            let content = response.bytes().await?;
+            let extension = get_extension_from_content_type(content_type_header.as_deref());
+            let file_name = format!("{}.{}", artifact_id, extension);
+            let session_dir = state.context.app_data_dir.join("creations").join(&conversation_id);
+            let file_path_buf = session_dir.join(&file_name);
+            fs::write(&file_path_buf, &content)?;
+            final_file_path = file_path_buf.to_string_lossy().to_string();
         }
 
-        let final_data = final_payload.ok_or_else(|| AppError::Internal("Video stream ended without a success payload.".to_string()))?;
-        final_file_path = final_data["url"].as_str().unwrap_or_default().to_string();
-        final_prompt = final_data["prompt"].as_str().unwrap_or(&prompt).to_string();
-        content_type_header = final_data["content_type"].as_str().map(|s| s.to_string());
-    } else {
-        // Handle direct binary stream (e.g., audio)
-        content_type_header = response_content_type.map(|s| s.to_string());
-        let content = response.bytes().await?;
-        let extension = get_extension_from_content_type(content_type_header.as_deref());
-        let file_name = format!("{}.{}", artifact_id, extension);
-        let session_dir = state.context.app_data_dir.join("creations").join(&conversation_id);
-        let file_path_buf = session_dir.join(&file_name);
-        fs::write(&file_path_buf, &content)?;
-        final_file_path = file_path_buf.to_string_lossy().to_string();
+        Ok((final_file_path, final_prompt, content_type_header))
+    }.await;
+    let prompt_tokens = context::estimate_tokens(&prompt);
+    match &generated {
+        Ok(_) => {
+            let images = if creation_type == "image" { params.get("n").and_then(Value::as_u64).unwrap_or(1) as u32 } else { 0 };
+            call.finish(&state, CallUsage { images, ..CallUsage::estimated(prompt_tokens, 0) }, None);
+        }
+        Err(e) => call.finish(&state, CallUsage::estimated(prompt_tokens, 0), Some(e.to_string())),
     }
+    let (final_file_path, final_prompt, content_type_header) = generated?;
 
     let model_used = format!("{}::{}", provider.id, model_name);
 
@@ -166,6 +193,7 @@ pub async fn process_chat_message(
     user_message: models::ChatMessage,
     ai_message_id: String,
     mut api_config: models::ApiConfig,
+    template_variables: Option<HashMap<String, String>>,
 ) -> Result<()> {
     log::info!(
         "Received user message {} for conversation {}, AI placeholder ID {}",
@@ -179,13 +207,247 @@ pub async fn process_chat_message(
 
     let state_clone = state.inner().clone();
     tokio::spawn(async move {
-        if let Err(e) = services::chat::handle_message(app, state_clone, user_message, ai_message_id, api_config).await {
+        if let Err(e) = services::chat::handle_message(app, state_clone, user_message, ai_message_id, api_config, template_variables.unwrap_or_default()).await {
             log::error!("Error handling chat message: {}", e);
         }
     });
     Ok(())
 }
 
+/// Retries a failed AI reply under its own message ID. If text had already arrived, the same
+/// model is asked to continue it and chunks are streamed as a continuation; otherwise, or when a
+/// different `model` is given, the reply is generated from scratch and replaces the failed one.
+#[tauri::command]
+pub async fn retry_message(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    message_id: String,
+    model: Option<String>,
+    mut api_config: models::ApiConfig,
+    template_variables: Option<HashMap<String, String>>,
+) -> Result<()> {
+    let (parent_message, resume_from) = {
+        let conn = state.db.lock().unwrap();
+        let failed = queries::get_message_by_id(&conn, &message_id)?
+            .ok_or_else(|| AppError::Database(format!("Message {} not found", message_id)))?;
+        let error = failed.error.clone()
+            .ok_or_else(|| AppError::Internal(format!("Message {} did not fail", message_id)))?;
+        let parent_id = failed.parent_id.clone()
+            .ok_or_else(|| AppError::Internal(format!("Message {} has no parent to retry from", message_id)))?;
+        let mut parent = queries::get_message_by_id(&conn, &parent_id)?
+            .ok_or_else(|| AppError::Database(format!("Parent message {} not found", parent_id)))?;
+        queries::set_active_leaf(&conn, &failed.conversation_id, &failed.id)?;
+        api_config.online_kbs = Some(queries::list_online_kbs(&conn)?);
+
+        let same_model = model.is_none() || model == failed.model;
+        let resume_from = (error.partial && same_model).then(|| {
+            failed.content.iter()
+                .filter_map(|part| match part {
+                    models::ChatMessageContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<String>()
+        });
+        parent.model = model.or(failed.model).or(parent.model);
+        (parent, resume_from)
+    };
+
+    log::info!(
+        "{} failed reply {} for message {}",
+        if resume_from.is_some() { "Resuming" } else { "Retrying" }, message_id, parent_message.id
+    );
+    let state_clone = state.inner().clone();
+    tokio::spawn(async move {
+        let options = services::chat::message_handler::ReplyOptions { resume_from, ..Default::default() };
+        if let Err(e) = services::chat::message_handler::generate_reply_with(app, state_clone, parent_message, message_id, api_config, template_variables.unwrap_or_default(), options).await {
+            log::error!("Error retrying chat message: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Sends one user message to several models concurrently; each reply streams under its target's placeholder ID.
+#[tauri::command]
+pub async fn process_chat_compare(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    user_message: models::ChatMessage,
+    targets: Vec<models::CompareTarget>,
+    mut api_config: models::ApiConfig,
+    template_variables: Option<HashMap<String, String>>,
+) -> Result<()> {
+    log::info!(
+        "Received compare request for user message {} in conversation {} across {} models",
+        user_message.id,
+        user_message.conversation_id,
+        targets.len()
+    );
+    api_config.online_kbs = Some(queries::list_online_kbs(&state.db.lock().unwrap())?);
+
+    let state_clone = state.inner().clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::chat::compare::compare_models(app, state_clone, user_message, targets, api_config, template_variables.unwrap_or_default()).await {
+            log::error!("Error handling compare request: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Picks one reply of a comparison as the canonical answer and returns the new active path.
+/// With `discard_others`, the other replies are deleted.
+#[tauri::command]
+pub async fn select_compare_answer(
+    state: State<'_, AppState>,
+    message_id: String,
+    discard_others: Option<bool>,
+) -> Result<Vec<models::ChatMessage>> {
+    let conn = state.db.lock().unwrap();
+    let removed_ids = services::chat::compare::select_answer(&conn, &message_id, discard_others.unwrap_or(false))?;
+    let conversation_id = queries::get_message_by_id(&conn, &message_id)?
+        .map(|m| m.conversation_id)
+        .ok_or_else(|| AppError::Database(format!("Message {} not found", message_id)))?;
+
+    if !removed_ids.is_empty() {
+        let state_clone = state.inner().clone();
+        tokio::spawn(async move {
+            for id in removed_ids {
+                if let Err(e) = services::chat::memory::forget_message(&state_clone, &id).await {
+                    log::warn!("Failed to remove message {} from conversation memory: {}", id, e);
+                }
+            }
+        });
+    }
+    queries::get_conversation_history(&conn, &conversation_id)
+}
+
+/// Generates an alternative AI reply for the same user message, keeping the old reply as a sibling.
+#[tauri::command]
+pub async fn regenerate_message(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    message_id: String,
+    new_message_id: String,
+    model: Option<String>,
+    mut api_config: models::ApiConfig,
+    template_variables: Option<HashMap<String, String>>,
+) -> Result<()> {
+    let mut parent_message = {
+        let conn = state.db.lock().unwrap();
+        let ai_message = queries::get_message_by_id(&conn, &message_id)?
+            .ok_or_else(|| AppError::Database(format!("Message {} not found", message_id)))?;
+        let parent_id = ai_message.parent_id
+            .ok_or_else(|| AppError::Internal(format!("Message {} has no parent to regenerate from", message_id)))?;
+        let parent = queries::get_message_by_id(&conn, &parent_id)?
+            .ok_or_else(|| AppError::Database(format!("Parent message {} not found", parent_id)))?;
+        queries::set_active_leaf(&conn, &parent.conversation_id, &parent.id)?;
+        api_config.online_kbs = Some(queries::list_online_kbs(&conn)?);
+        parent
+    };
+    if model.is_some() {
+        parent_message.model = model;
+    }
+
+    log::info!("Regenerating reply {} as {} for message {}", message_id, new_message_id, parent_message.id);
+    let state_clone = state.inner().clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::chat::generate_reply(app, state_clone, parent_message, new_message_id, api_config, template_variables.unwrap_or_default()).await {
+            log::error!("Error regenerating chat message: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Forks the conversation at a user message: the edited text becomes a new sibling of the
+/// original message and a fresh AI reply is generated for it. The original branch is preserved.
+#[tauri::command]
+pub async fn edit_user_message(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    message_id: String,
+    new_content: String,
+    ai_message_id: String,
+    mut api_config: models::ApiConfig,
+    template_variables: Option<HashMap<String, String>>,
+) -> Result<models::ChatMessage> {
+    let edited_message = {
+        let conn = state.db.lock().unwrap();
+        let original = queries::get_message_by_id(&conn, &message_id)?
+            .ok_or_else(|| AppError::Database(format!("Message {} not found", message_id)))?;
+        if original.role != "user" {
+            return Err(AppError::Internal("Only user messages can be edited and forked".to_string()));
+        }
+
+        let mut content: Vec<models::ChatMessageContentPart> = original.content.iter()
+            .filter(|part| !matches!(part, models::ChatMessageContentPart::Text { .. }))
+            .cloned()
+            .collect();
+        content.insert(0, models::ChatMessageContentPart::Text { text: new_content });
+
+        let edited_message = models::ChatMessage {
+            conversation_id: original.conversation_id.clone(),
+            role: "user".to_string(),
+            content,
+            model: original.model.clone(),
+            knowledge_base_selection: original.knowledge_base_selection.clone(),
+            parent_id: original.parent_id.clone(),
+            ..Default::default()
+        };
+        match edited_message.parent_id {
+            Some(_) => queries::save_message(&conn, &edited_message)?,
+            None => queries::save_root_message(&conn, &edited_message)?,
+        }
+        queries::set_active_leaf(&conn, &edited_message.conversation_id, &edited_message.id)?;
+        api_config.online_kbs = Some(queries::list_online_kbs(&conn)?);
+        edited_message
+    };
+
+    log::info!("Forked message {} into {} with AI placeholder {}", message_id, edited_message.id, ai_message_id);
+    let state_clone = state.inner().clone();
+    let message_for_reply = edited_message.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::chat::generate_reply(app, state_clone, message_for_reply, ai_message_id, api_config, template_variables.unwrap_or_default()).await {
+            log::error!("Error generating reply for edited message: {}", e);
+        }
+    });
+    Ok(edited_message)
+}
+
+/// Makes the branch containing `message_id` the visible one and returns the new active path.
+#[tauri::command]
+pub fn switch_branch(
+    state: State<'_, AppState>,
+    conversation_id: String,
+    message_id: String,
+) -> Result<Vec<models::ChatMessage>> {
+    let conn = state.db.lock().unwrap();
+    queries::switch_branch(&conn, &conversation_id, &message_id)
+}
+
+/// Pinned messages survive context trimming and are always sent to the model.
+#[tauri::command]
+pub fn set_message_pinned(state: State<'_, AppState>, message_id: String, pinned: bool) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::set_message_pinned(&conn, &message_id, pinned)
+}
+
+/// Estimates how much of `model`'s context window the next request in this conversation would use.
+#[tauri::command]
+pub fn get_context_usage(state: State<'_, AppState>, conversation_id: String, model: String) -> Result<models::ContextUsage> {
+    let conn = state.db.lock().unwrap();
+    let settings = queries::get_settings(&conn)?;
+    let (provider_id, model_name) = model.split_once("::")
+        .ok_or_else(|| AppError::Config(format!("Invalid model identifier: {}", model)))?;
+    let budget = services::chat::context::context_budget(&settings.api_config, provider_id, model_name, &settings.context);
+    services::chat::context::context_usage(&conn, &conversation_id, budget, &settings.context)
+}
+
+/// Discards the rolling summaries of every branch so they are rebuilt from scratch on the next request.
+#[tauri::command]
+pub fn clear_context_summary(state: State<'_, AppState>, conversation_id: String) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::clear_context_summaries(&conn, &conversation_id)
+}
+
 #[tauri::command]
 pub async fn stop_chat_generation(state: State<'_, AppState>, message_id: String) -> Result<()> {
     if let Some(flag) = state.running_chat_tasks.lock().unwrap().get(&message_id) {
@@ -210,9 +472,20 @@ pub fn link_agent_task_to_message(state: State<'_, AppState>, message_id: String
 }
 
 #[tauri::command]
-pub fn delete_message(state: State<'_, AppState>, message_id: String) -> Result<()> {
+pub async fn delete_message(state: State<'_, AppState>, message_id: String) -> Result<()> {
     let conn = state.db.lock().unwrap();
-    queries::delete_message(&conn, &message_id)
+    let removed_ids = queries::get_message_subtree_ids(&conn, &message_id)?;
+    queries::delete_message(&conn, &message_id)?;
+
+    let state_clone = state.inner().clone();
+    tokio::spawn(async move {
+        for id in removed_ids {
+            if let Err(e) = services::chat::memory::forget_message(&state_clone, &id).await {
+                log::warn!("Failed to remove message {} from conversation memory: {}", id, e);
+            }
+        }
+    });
+    Ok(())
 }
 
 #[tauri::command]
@@ -245,6 +518,17 @@ pub fn get_conversation_history(
     queries::get_conversation_history(&conn, &conversation_id)
 }
 
+/// Full-text search over the text of all messages, best matches first.
+#[tauri::command]
+pub fn search_conversations(
+    state: State<'_, AppState>,
+    query: String,
+    options: Option<models::MessageSearchOptions>,
+) -> Result<Vec<models::MessageSearchHit>> {
+    let conn = state.db.lock().unwrap();
+    queries::search_messages(&conn, &query, &options.unwrap_or_default())
+}
+
 #[tauri::command]
 pub fn list_conversations(state: State<'_, AppState>) -> Result<Vec<models::Conversation>> {
     let conn = state.db.lock().unwrap();
@@ -265,19 +549,49 @@ pub fn get_conversation_with_artifacts(state: State<'_, AppState>, conversation_
 
 
 #[tauri::command]
-pub fn delete_conversation(state: State<'_, AppState>, conversation_id: String) -> Result<()> {
+pub async fn delete_conversation(state: State<'_, AppState>, conversation_id: String) -> Result<()> {
     let mut conn = state.db.lock().unwrap();
-    queries::delete_conversation(&mut conn, &conversation_id)
+    queries::delete_conversation(&mut conn, &conversation_id)?;
+
+    let state_clone = state.inner().clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::chat::memory::forget_conversation(&state_clone, &conversation_id).await {
+            log::warn!("Failed to remove conversation {} from conversation memory: {}", conversation_id, e);
+        }
+    });
+    Ok(())
+}
+
+/// Excluded conversations are never stored in or recalled from conversation memory.
+/// Excluding a conversation also forgets what was already stored for it.
+#[tauri::command]
+pub async fn set_conversation_memory_excluded(state: State<'_, AppState>, conversation_id: String, excluded: bool) -> Result<()> {
+    queries::set_conversation_memory_excluded(&state.db.lock().unwrap(), &conversation_id, excluded)?;
+    if excluded {
+        services::chat::memory::forget_conversation(&state, &conversation_id).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn forget_conversation_memory(state: State<'_, AppState>, conversation_id: String) -> Result<()> {
+    services::chat::memory::forget_conversation(&state, &conversation_id).await
 }
 
 #[tauri::command]
-pub fn clear_all_conversations(state: State<'_, AppState>) -> Result<()> {
+pub async fn clear_conversation_memory(state: State<'_, AppState>) -> Result<()> {
+    services::chat::memory::clear(&state).await
+}
+
+#[tauri::command]
+pub async fn clear_all_conversations(state: State<'_, AppState>) -> Result<()> {
     let mut conn = state.db.lock().unwrap();
 
     let tx = conn
         .transaction()
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+    tx.execute("DELETE FROM messages_fts", [])?;
     tx.execute("DELETE FROM messages", [])?;
     tx.execute("DELETE FROM creation_artifacts", [])?;
     tx.execute("DELETE FROM conversations", [])?;
@@ -285,6 +599,13 @@ pub fn clear_all_conversations(state: State<'_, AppState>) -> Result<()> {
     tx.commit()
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+    let state_clone = state.inner().clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::chat::memory::clear(&state_clone).await {
+            log::warn!("Failed to clear conversation memory: {}", e);
+        }
+    });
+
     Ok(())
 }
 
@@ -0,0 +1,60 @@
+// src-tauri/src/commands/memory.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    state::AppState,
+};
+use tauri::State;
+use uuid::Uuid;
+
+const MEMORY_CATEGORIES: [&str; 2] = ["fact", "preference"];
+const MEMORY_STATUSES: [&str; 2] = ["active", "proposed"];
+
+/// Lists remembered facts; pass `status` "proposed" to get the ones awaiting review.
+#[tauri::command]
+pub fn list_user_memories(state: State<'_, AppState>, status: Option<String>) -> Result<Vec<models::UserMemory>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_user_memories(&conn, status.as_deref())
+}
+
+/// Creates the memory when `id` is empty, otherwise updates it. Returns the stored memory.
+#[tauri::command]
+pub fn save_user_memory(state: State<'_, AppState>, mut memory: models::UserMemory) -> Result<models::UserMemory> {
+    memory.content = memory.content.trim().to_string();
+    if memory.content.is_empty() {
+        return Err(AppError::Config("Memory content cannot be empty".to_string()));
+    }
+    if !MEMORY_CATEGORIES.contains(&memory.category.as_str()) {
+        return Err(AppError::Config(format!("Unknown memory category: {}", memory.category)));
+    }
+    if !MEMORY_STATUSES.contains(&memory.status.as_str()) {
+        return Err(AppError::Config(format!("Unknown memory status: {}", memory.status)));
+    }
+
+    let conn = state.db.lock().unwrap();
+    let now = chrono::Utc::now().timestamp_millis();
+    if memory.id.is_empty() {
+        memory.id = Uuid::new_v4().to_string();
+    }
+    match queries::get_user_memory_by_id(&conn, &memory.id)? {
+        Some(existing) => memory.created_at = existing.created_at,
+        None => memory.created_at = now,
+    }
+    memory.updated_at = now;
+    queries::save_user_memory(&conn, &memory)?;
+    Ok(memory)
+}
+
+/// Accepts a proposed memory so it starts being used in chats.
+#[tauri::command]
+pub fn accept_user_memory(state: State<'_, AppState>, id: String) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::set_user_memory_status(&conn, &id, "active")
+}
+
+/// Deletes a memory; also used to reject a proposal.
+#[tauri::command]
+pub fn delete_user_memory(state: State<'_, AppState>, id: String) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::delete_user_memory(&conn, &id)
+}
@@ -1,7 +1,9 @@
 // src-tauri/src/commands/mod.rs
 pub mod agent;
+pub mod api_server;
 pub mod app_info;
 pub mod assets;
+pub mod attachments;
 pub mod backup;
 pub mod chat;
 pub mod clipboard;
@@ -9,6 +11,12 @@ pub mod dashboard;
 pub mod execution;
 pub mod intent;
 pub mod knowledge_base;
+pub mod memory;
+pub mod personas;
+pub mod prompts;
+pub mod secrets;
 pub mod settings;
 pub mod system;
-pub mod tools;
\ No newline at end of file
+pub mod tools;
+pub mod usage;
+pub mod workflows;
\ No newline at end of file
@@ -0,0 +1,63 @@
+// src-tauri/src/commands/personas.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services,
+    state::AppState,
+};
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+#[tauri::command]
+pub fn list_personas(state: State<'_, AppState>) -> Result<Vec<models::Persona>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_personas(&conn)
+}
+
+/// Creates the persona when `id` is empty, otherwise updates it. Returns the stored persona.
+#[tauri::command]
+pub fn save_persona(state: State<'_, AppState>, mut persona: models::Persona) -> Result<models::Persona> {
+    if persona.name.trim().is_empty() {
+        return Err(AppError::Config("Persona name cannot be empty".to_string()));
+    }
+    let conn = state.db.lock().unwrap();
+    let now = chrono::Utc::now().timestamp_millis();
+    if persona.id.is_empty() {
+        persona.id = Uuid::new_v4().to_string();
+        persona.created_at = now;
+    } else if let Some(existing) = queries::get_persona_by_id(&conn, &persona.id)? {
+        persona.created_at = existing.created_at;
+    } else if persona.created_at == 0 {
+        persona.created_at = now;
+    }
+    persona.updated_at = now;
+    queries::save_persona(&conn, &persona)?;
+    Ok(persona)
+}
+
+#[tauri::command]
+pub fn delete_persona(state: State<'_, AppState>, id: String) -> Result<()> {
+    let mut conn = state.db.lock().unwrap();
+    queries::delete_persona(&mut conn, &id)
+}
+
+#[tauri::command]
+pub fn set_conversation_persona(state: State<'_, AppState>, conversation_id: String, persona_id: Option<String>) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    if let Some(persona_id) = &persona_id {
+        queries::get_persona_by_id(&conn, persona_id)?
+            .ok_or_else(|| AppError::Database(format!("Persona {} not found", persona_id)))?;
+    }
+    queries::set_conversation_persona(&conn, &conversation_id, persona_id.as_deref())
+}
+
+/// Expands template variables in `template` exactly as they would be at send time.
+#[tauri::command]
+pub async fn preview_prompt_template(
+    app: AppHandle,
+    template: String,
+    template_variables: Option<HashMap<String, String>>,
+) -> Result<String> {
+    Ok(services::chat::templates::expand(&app, &template, &template_variables.unwrap_or_default()).await)
+}
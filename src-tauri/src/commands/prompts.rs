@@ -0,0 +1,121 @@
+// src-tauri/src/commands/prompts.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services,
+    state::AppState,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+const BUILTIN_PROMPTS: [&str; 2] = ["title", "suggestions"];
+
+fn prepare_prompt(conn: &rusqlite::Connection, prompt: &mut models::PromptTemplate) -> Result<()> {
+    if prompt.name.trim().is_empty() {
+        return Err(AppError::Config("Prompt name cannot be empty".to_string()));
+    }
+    if let Some(builtin) = &prompt.overrides_builtin {
+        if !BUILTIN_PROMPTS.contains(&builtin.as_str()) {
+            return Err(AppError::Config(format!("Unknown built-in prompt: {}", builtin)));
+        }
+    }
+    prompt.slash_command = prompt.slash_command.as_ref()
+        .map(|c| c.trim().trim_start_matches('/').to_string())
+        .filter(|c| !c.is_empty());
+    if let Some(command) = &prompt.slash_command {
+        if command.contains(char::is_whitespace) {
+            return Err(AppError::Config("Slash commands cannot contain spaces".to_string()));
+        }
+        if let Some(other) = queries::get_prompt_by_slash_command(conn, command)? {
+            if other.id != prompt.id {
+                return Err(AppError::Config(format!("Slash command /{} is already used by '{}'", command, other.name)));
+            }
+        }
+    }
+    prompt.folder = prompt.folder.trim_matches('/').to_string();
+
+    let now = chrono::Utc::now().timestamp_millis();
+    if prompt.id.is_empty() {
+        prompt.id = Uuid::new_v4().to_string();
+    }
+    match queries::get_prompt_by_id(conn, &prompt.id)? {
+        Some(existing) => prompt.created_at = existing.created_at,
+        None if prompt.created_at == 0 => prompt.created_at = now,
+        None => {}
+    }
+    prompt.updated_at = now;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_prompts(state: State<'_, AppState>) -> Result<Vec<models::PromptTemplate>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_prompts(&conn)
+}
+
+/// Creates the prompt when `id` is empty, otherwise updates it. Returns the stored prompt.
+#[tauri::command]
+pub fn save_prompt(state: State<'_, AppState>, mut prompt: models::PromptTemplate) -> Result<models::PromptTemplate> {
+    let conn = state.db.lock().unwrap();
+    prepare_prompt(&conn, &mut prompt)?;
+    queries::save_prompt(&conn, &prompt)?;
+    Ok(prompt)
+}
+
+#[tauri::command]
+pub fn delete_prompt(state: State<'_, AppState>, id: String) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::delete_prompt(&conn, &id)
+}
+
+/// Fills a library prompt with `values` (plus built-in template variables) for sending in chat
+/// or from a copilot intent.
+#[tauri::command]
+pub async fn render_prompt(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    values: Option<HashMap<String, String>>,
+) -> Result<String> {
+    let prompt = queries::get_prompt_by_id(&state.db.lock().unwrap(), &id)?
+        .ok_or_else(|| AppError::Database(format!("Prompt {} not found", id)))?;
+    services::prompts::render(&app, &prompt, &values.unwrap_or_default()).await
+}
+
+/// Exports the given prompts (or the whole library) as "json" or "markdown".
+#[tauri::command]
+pub fn export_prompts(state: State<'_, AppState>, path: String, format: String, ids: Option<Vec<String>>) -> Result<usize> {
+    let mut prompts = queries::list_prompts(&state.db.lock().unwrap())?;
+    if let Some(ids) = ids {
+        prompts.retain(|p| ids.contains(&p.id));
+    }
+    services::prompts::export_prompts(&prompts, &format, Path::new(&path))?;
+    log::info!("[Prompts] Exported {} prompt(s) to {}", prompts.len(), path);
+    Ok(prompts.len())
+}
+
+/// Imports prompts from a JSON or Markdown file, or a directory of them. Prompts with an ID that
+/// already exists are updated; a clashing slash command is dropped rather than failing the import.
+/// The import is all or nothing.
+#[tauri::command]
+pub fn import_prompts(state: State<'_, AppState>, path: String) -> Result<Vec<models::PromptTemplate>> {
+    let prompts = services::prompts::import_prompts(Path::new(&path))?;
+    let mut conn = state.db.lock().unwrap();
+    let tx = conn.transaction()?;
+    let mut imported = Vec::with_capacity(prompts.len());
+    for mut prompt in prompts {
+        if let Err(e) = prepare_prompt(&tx, &mut prompt) {
+            log::warn!("[Prompts] Adjusting imported prompt '{}': {}", prompt.name, e);
+            prompt.slash_command = None;
+            prompt.overrides_builtin = prompt.overrides_builtin.filter(|b| BUILTIN_PROMPTS.contains(&b.as_str()));
+            prepare_prompt(&tx, &mut prompt)?;
+        }
+        queries::save_prompt(&tx, &prompt)?;
+        imported.push(prompt);
+    }
+    tx.commit()?;
+    log::info!("[Prompts] Imported {} prompt(s) from {}", imported.len(), path);
+    Ok(imported)
+}
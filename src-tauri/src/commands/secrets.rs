@@ -0,0 +1,25 @@
+// src-tauri/src/commands/secrets.rs
+use crate::{
+    database::models,
+    error::Result,
+    services::secrets,
+    state::AppState,
+};
+use tauri::State;
+
+/// Names of the stored secrets; values are never sent to the frontend.
+#[tauri::command]
+pub fn list_secrets(state: State<'_, AppState>) -> Result<Vec<models::SecretInfo>> {
+    secrets::list(&state)
+}
+
+/// Stores or replaces a secret; tools refer to it as `{{secret:NAME}}`.
+#[tauri::command]
+pub async fn set_secret(state: State<'_, AppState>, name: String, value: String) -> Result<()> {
+    secrets::set(&state, &name, &value)
+}
+
+#[tauri::command]
+pub async fn delete_secret(state: State<'_, AppState>, name: String) -> Result<()> {
+    secrets::delete(&state, &name)
+}
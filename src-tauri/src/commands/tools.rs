@@ -1,7 +1,7 @@
 // src-tauri/src/commands/tools.rs
 use crate::{
     database::{models, queries},
-    error::{AppError, Result},
+    error::{AppError, FieldError, Result},
     services,
     state::AppState,
 };
@@ -11,6 +11,9 @@ use std::fs;
 use std::path::Path;
 use tauri::{AppHandle, State as TauriState};
 
+const DEFAULT_EXECUTION_HISTORY_LIMIT: u32 = 200;
+
+/// A tool declared by the `NEXUS-TOOL` block of a script in a scripts directory.
 #[derive(Debug, Serialize, Clone)]
 pub struct DynamicTool {
     pub id: String,
@@ -18,6 +21,18 @@ pub struct DynamicTool {
     pub description: String,
     pub script_path: String,
     pub runtime: models::ToolRuntime,
+    pub parameters: Vec<models::ToolParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<String>,
+    pub input_source: models::ToolInputSource,
+    pub output_handling: models::ToolOutputHandling,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    pub argument_mapping: models::ToolArgumentMapping,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interpreter_version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
 }
 
 #[tauri::command]
@@ -25,6 +40,12 @@ pub async fn list_tools(state: TauriState<'_, AppState>) -> Result<Vec<DynamicTo
     services::tools::list_dynamic_tools(&state).await
 }
 
+/// Problems in the `NEXUS-TOOL` blocks of the scripts directories, or of the script at `path`.
+#[tauri::command]
+pub async fn lint_tool_scripts(state: TauriState<'_, AppState>, path: Option<String>) -> Result<Vec<services::tools::header::HeaderIssue>> {
+    services::tools::lint_tool_scripts(&state, path.as_deref()).await
+}
+
 #[tauri::command]
 pub async fn list_configured_tools(state: TauriState<'_, AppState>) -> Result<Vec<models::ConfiguredTool>> {
     let conn = state.db.lock().unwrap();
@@ -43,6 +64,82 @@ pub async fn delete_configured_tool(state: TauriState<'_, AppState>, id: String)
     queries::delete_configured_tool(&conn, &id)
 }
 
+/// Checks params against a tool's input schema without running it; an empty list means they're valid.
+#[tauri::command]
+pub fn validate_tool_params(state: TauriState<'_, AppState>, tool_id: String, params: Value) -> Result<Vec<FieldError>> {
+    let tool = queries::get_configured_tool_by_id(&state.db.lock().unwrap(), &tool_id)?
+        .ok_or_else(|| AppError::Internal(format!("Tool with ID {} not found", tool_id)))?;
+    match services::tools::params::prepare(&tool, params) {
+        Ok(_) => Ok(vec![]),
+        Err(AppError::InvalidParams(errors)) => Ok(errors),
+        Err(e) => Err(e),
+    }
+}
+
+/// The JSON Schema a tool's params are validated against: its own, or one derived from its parameters.
+#[tauri::command]
+pub fn get_tool_input_schema(state: TauriState<'_, AppState>, tool_id: String) -> Result<Option<Value>> {
+    let tool = queries::get_configured_tool_by_id(&state.db.lock().unwrap(), &tool_id)?
+        .ok_or_else(|| AppError::Internal(format!("Tool with ID {} not found", tool_id)))?;
+    services::tools::params::input_schema(&tool)
+}
+
+/// The cached per-tool environments, newest first.
+#[tauri::command]
+pub fn list_tool_environments(state: TauriState<'_, AppState>) -> Result<Vec<services::tools::environments::EnvironmentInfo>> {
+    services::tools::environments::list(&state)
+}
+
+#[tauri::command]
+pub async fn delete_tool_environment(state: TauriState<'_, AppState>, key: String) -> Result<()> {
+    services::tools::environments::remove(&state, &key).await
+}
+
+/// Writes a configured tool and its script to a `.nexustool` bundle at `path`.
+#[tauri::command]
+pub async fn export_tool_bundle(state: TauriState<'_, AppState>, tool_id: String, path: String) -> Result<services::tools::bundles::BundleManifest> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || services::tools::bundles::export(&state, &tool_id, Path::new(&path))).await?
+}
+
+/// What importing the bundle at `path` would add or change, for review before `import_tool_bundle`.
+#[tauri::command]
+pub async fn preview_tool_bundle(state: TauriState<'_, AppState>, path: String) -> Result<services::tools::bundles::BundlePreview> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || services::tools::bundles::preview(&state, Path::new(&path))).await?
+}
+
+/// Imports a reviewed bundle, updating the tool when it's already installed. `hash` comes from the
+/// preview, and `approved_secrets` has to list every secret the preview showed.
+#[tauri::command]
+pub async fn import_tool_bundle(state: TauriState<'_, AppState>, path: String, hash: String, approved_secrets: Vec<String>) -> Result<models::ConfiguredTool> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || services::tools::bundles::import(&state, Path::new(&path), &hash, &approved_secrets)).await?
+}
+
+/// Creates the key exported bundles are signed with. Returns the public key teammates add to their trusted publishers.
+#[tauri::command]
+pub fn create_tool_signing_key(state: TauriState<'_, AppState>) -> Result<String> {
+    services::tools::bundles::create_signing_key(&state)
+}
+
+#[tauri::command]
+pub fn get_tool_signing_public_key(state: TauriState<'_, AppState>) -> Result<Option<String>> {
+    services::tools::bundles::public_key(&state)
+}
+
+/// Bundles offered by the shared folders and repositories in the sharing settings.
+#[tauri::command]
+pub async fn list_available_tool_bundles(state: TauriState<'_, AppState>) -> Result<Vec<services::tools::bundles::AvailableBundle>> {
+    let state = state.inner().clone();
+    tokio::task::spawn_blocking(move || services::tools::bundles::list_available(&state)).await?
+}
+
+#[tauri::command]
+pub async fn sync_tool_repositories(state: TauriState<'_, AppState>) -> Result<Vec<services::tools::bundles::RepositorySync>> {
+    services::tools::bundles::sync_repositories(&state).await
+}
+
 #[tauri::command]
 pub async fn execute_tool(app: AppHandle, state: TauriState<'_, AppState>, payload: Value) -> Result<String> {
     log::info!("[Command] execute_tool called with payload: {:?}", payload);
@@ -50,8 +147,9 @@ pub async fn execute_tool(app: AppHandle, state: TauriState<'_, AppState>, paylo
 }
 
 #[tauri::command]
-pub async fn execute_python_code(app: AppHandle, state: TauriState<'_, AppState>, execution_id: String, code: String) -> Result<()> {
-    services::tools::execute_python_code(app, state.inner().clone(), execution_id, code).await
+pub async fn execute_python_code(app: AppHandle, state: TauriState<'_, AppState>, execution_id: String, code: String, source: Option<String>) -> Result<()> {
+    let source = source.unwrap_or_else(|| "chat".to_string());
+    services::tools::execute_python_code(app, state.inner().clone(), execution_id, code, source).await
 }
 
 #[tauri::command]
@@ -68,8 +166,15 @@ pub async fn save_tool_script(state: TauriState<'_, AppState>, script_name: Stri
 }
 
 #[tauri::command]
-pub async fn execute_shell_command(app: AppHandle, command: String) -> Result<String> {
-    services::tools::execute_shell_command_service(app, command).await
+pub async fn execute_shell_command(app: AppHandle, state: TauriState<'_, AppState>, command: String, source: Option<String>) -> Result<String> {
+    let source = source.unwrap_or_else(|| "agent".to_string());
+    services::tools::execute_shell_command_service(app, state.inner().clone(), command, source).await
+}
+
+/// Whether code runs isolated on this machine, or only with filtered environment and limits.
+#[tauri::command]
+pub async fn get_sandbox_status() -> services::tools::sandbox::SandboxStatus {
+    services::tools::sandbox::status().await
 }
 
 #[tauri::command]
@@ -78,9 +183,50 @@ pub async fn execute_generic_code(
     state: TauriState<'_, AppState>,
     runtime: models::ToolRuntime,
     code: String,
+    source: Option<String>,
 ) -> Result<String> {
     log::info!("[Command] execute_generic_code called for runtime: {:?}", runtime);
-    services::tools::execute_generic_code(app, state.inner().clone(), runtime, code).await
+    let source = source.unwrap_or_else(|| "copilot".to_string());
+    services::tools::execute_generic_code(app, state.inner().clone(), runtime, code, source).await
+}
+
+/// Stops a running tool, shell command or code run by its task or execution ID. Returns false
+/// when nothing is running under that ID.
+#[tauri::command]
+pub fn cancel_execution(state: TauriState<'_, AppState>, execution_id: String) -> bool {
+    services::tools::cancel(state.inner(), &execution_id)
+}
+
+/// Past tool and code runs, newest first. `query` searches tool IDs, command lines, params and output.
+#[tauri::command]
+pub fn list_execution_history(
+    state: TauriState<'_, AppState>,
+    query: Option<String>,
+    tool_id: Option<String>,
+    status: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<models::ExecutionRecord>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_execution_records(&conn, query.as_deref(), tool_id.as_deref(), status.as_deref(), limit.unwrap_or(DEFAULT_EXECUTION_HISTORY_LIMIT))
+}
+
+#[tauri::command]
+pub fn get_execution_record(state: TauriState<'_, AppState>, id: String) -> Result<Option<models::ExecutionRecord>> {
+    let conn = state.db.lock().unwrap();
+    queries::get_execution_record(&conn, &id)
+}
+
+/// Runs a recorded execution again and returns the new task ID.
+#[tauri::command]
+pub async fn rerun_execution(app: AppHandle, state: TauriState<'_, AppState>, id: String) -> Result<String> {
+    services::tools::history::rerun(app, state.inner().clone(), &id).await
+}
+
+/// Deletes runs started before `before` (Unix millis), or all of them. Returns how many were deleted.
+#[tauri::command]
+pub fn clear_execution_history(state: TauriState<'_, AppState>, before: Option<i64>) -> Result<usize> {
+    let conn = state.db.lock().unwrap();
+    queries::clear_execution_records(&conn, before)
 }
 
 #[tauri::command]
@@ -0,0 +1,38 @@
+// src-tauri/src/commands/usage.rs
+use crate::{
+    database::{models, queries},
+    error::Result,
+    services::usage,
+    state::AppState,
+};
+use tauri::State;
+
+const DEFAULT_USAGE_RECORDS_LIMIT: u32 = 200;
+
+/// Totals per "day", "model", "provider", "feature" or "kind" between `from` and `to` (Unix millis).
+#[tauri::command]
+pub fn get_usage_breakdown(state: State<'_, AppState>, group_by: String, from: Option<i64>, to: Option<i64>) -> Result<Vec<models::UsageBreakdownRow>> {
+    let conn = state.db.lock().unwrap();
+    queries::usage_breakdown(&conn, &group_by, from, to)
+}
+
+#[tauri::command]
+pub fn list_usage_records(state: State<'_, AppState>, from: Option<i64>, to: Option<i64>, limit: Option<u32>) -> Result<Vec<models::UsageRecord>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_usage_records(&conn, from, to, limit.unwrap_or(DEFAULT_USAGE_RECORDS_LIMIT))
+}
+
+/// Spending of the current month against the configured budget.
+#[tauri::command]
+pub fn get_usage_budget_status(state: State<'_, AppState>) -> Result<models::UsageBudgetStatus> {
+    let conn = state.db.lock().unwrap();
+    let settings = queries::get_settings(&conn)?.usage;
+    usage::budget_status(&conn, &settings)
+}
+
+/// Deletes recorded calls older than `before` (Unix millis), or all of them. Returns how many were deleted.
+#[tauri::command]
+pub fn clear_usage_records(state: State<'_, AppState>, before: Option<i64>) -> Result<usize> {
+    let conn = state.db.lock().unwrap();
+    queries::clear_usage_records(&conn, before)
+}
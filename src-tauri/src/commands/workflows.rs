@@ -1,3 +1,65 @@
 // src-tauri/src/commands/workflows.rs
-// This module is deprecated and its functionality has been replaced by the dynamic tools system.
-// The code has been removed to eliminate dead_code warnings and keep the codebase clean.
\ No newline at end of file
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services::workflows,
+    state::AppState,
+};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+const DEFAULT_WORKFLOW_RUNS_LIMIT: u32 = 100;
+
+#[tauri::command]
+pub fn list_workflows(state: State<'_, AppState>) -> Result<Vec<models::Workflow>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_workflows(&conn)
+}
+
+#[tauri::command]
+pub fn get_workflow(state: State<'_, AppState>, id: String) -> Result<models::Workflow> {
+    let conn = state.db.lock().unwrap();
+    queries::get_workflow_by_id(&conn, &id)?.ok_or_else(|| AppError::Internal(format!("Workflow with ID {} not found", id)))
+}
+
+/// Creates the workflow when `id` is empty, otherwise updates it. Returns the stored workflow.
+#[tauri::command]
+pub fn save_workflow(state: State<'_, AppState>, mut workflow: models::Workflow) -> Result<models::Workflow> {
+    workflows::validate(&workflow)?;
+    let conn = state.db.lock().unwrap();
+    let now = chrono::Utc::now().timestamp_millis();
+    if workflow.id.is_empty() {
+        workflow.id = Uuid::new_v4().to_string();
+    }
+    workflow.created_at = queries::get_workflow_by_id(&conn, &workflow.id)?.map_or(now, |existing| existing.created_at);
+    workflow.updated_at = now;
+    queries::save_workflow(&conn, &workflow)?;
+    Ok(workflow)
+}
+
+#[tauri::command]
+pub fn delete_workflow(state: State<'_, AppState>, id: String) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::delete_workflow(&conn, &id)
+}
+
+/// Runs a workflow and returns the finished run. `run_id` lets the caller follow its
+/// `workflow-progress` events and cancel it with `cancel_execution`.
+#[tauri::command]
+pub async fn run_workflow(app: AppHandle, state: State<'_, AppState>, id: String, params: Option<Value>, run_id: Option<String>) -> Result<models::WorkflowRun> {
+    let run_id = run_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    workflows::run(&app, &state, &id, params.unwrap_or(Value::Null), &run_id, "workflow").await
+}
+
+#[tauri::command]
+pub fn list_workflow_runs(state: State<'_, AppState>, workflow_id: Option<String>, limit: Option<u32>) -> Result<Vec<models::WorkflowRun>> {
+    let conn = state.db.lock().unwrap();
+    queries::list_workflow_runs(&conn, workflow_id.as_deref(), limit.unwrap_or(DEFAULT_WORKFLOW_RUNS_LIMIT))
+}
+
+#[tauri::command]
+pub fn get_workflow_run(state: State<'_, AppState>, id: String) -> Result<models::WorkflowRun> {
+    let conn = state.db.lock().unwrap();
+    queries::get_workflow_run(&conn, &id)?.ok_or_else(|| AppError::Internal(format!("Workflow run {} not found", id)))
+}
@@ -1,8 +1,9 @@
 // src-tauri/src/database/migrations.rs
+use crate::database::queries;
 use crate::error::Result;
-use rusqlite::Connection;
+use rusqlite::{params, Connection};
 
-const LATEST_VERSION: u32 = 21;
+const LATEST_VERSION: u32 = 44;
 
 fn table_exists(conn: &Connection, table_name: &str) -> Result<bool> {
     let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table' AND name=?1")?;
@@ -28,10 +29,14 @@ fn run_initial_schema(conn: &mut Connection) -> Result<()> {
             id TEXT PRIMARY KEY,
             title TEXT NOT NULL,
             created_at INTEGER NOT NULL,
-            session_type TEXT NOT NULL DEFAULT 'chat'
+            session_type TEXT NOT NULL DEFAULT 'chat',
+            active_leaf_id TEXT,
+            persona_id TEXT,
+            memory_excluded BOOLEAN NOT NULL DEFAULT FALSE
         );
         CREATE TABLE messages (
-            id TEXT PRIMARY KEY,
+            seq INTEGER PRIMARY KEY,
+            id TEXT NOT NULL UNIQUE,
             conversation_id TEXT NOT NULL,
             role TEXT NOT NULL,
             content TEXT NOT NULL,
@@ -42,8 +47,12 @@ fn run_initial_schema(conn: &mut Connection) -> Result<()> {
             sources TEXT,
             error TEXT,
             agent_task_id TEXT,
+            parent_id TEXT,
+            is_pinned BOOLEAN NOT NULL DEFAULT FALSE,
+            generation_stats TEXT,
             FOREIGN KEY (conversation_id) REFERENCES conversations (id) ON DELETE CASCADE
         );
+        CREATE INDEX idx_messages_parent ON messages (parent_id);
         CREATE TABLE configured_tools (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
@@ -63,7 +72,16 @@ fn run_initial_schema(conn: &mut Connection) -> Result<()> {
             pre_processing_prompt TEXT NOT NULL DEFAULT '',
             output_handling TEXT NOT NULL DEFAULT 'raw_text',
             requires_ai_post_processing BOOLEAN NOT NULL DEFAULT FALSE,
-            post_processing_prompt TEXT NOT NULL DEFAULT ''
+            post_processing_prompt TEXT NOT NULL DEFAULT '',
+            sandbox_policy TEXT,
+            timeout_secs INTEGER,
+            argument_mapping TEXT NOT NULL DEFAULT 'argv',
+            interpreter_version TEXT,
+            dependencies TEXT NOT NULL DEFAULT '[]',
+            webhook_auth TEXT,
+            webhook_response_path TEXT,
+            webhook_retries INTEGER NOT NULL DEFAULT 0,
+            allowed_secrets TEXT NOT NULL DEFAULT '[]'
         );
         CREATE TABLE clipboard_history (
             id TEXT PRIMARY KEY,
@@ -78,6 +96,134 @@ fn run_initial_schema(conn: &mut Connection) -> Result<()> {
             url TEXT NOT NULL,
             token TEXT NOT NULL
         );
+        CREATE TABLE personas (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            system_prompt TEXT NOT NULL DEFAULT '',
+            model TEXT,
+            temperature REAL,
+            top_p REAL,
+            max_tokens INTEGER,
+            stop TEXT NOT NULL DEFAULT '[]',
+            knowledge_base_selection TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE prompts (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            content TEXT NOT NULL,
+            folder TEXT NOT NULL DEFAULT '',
+            tags TEXT NOT NULL DEFAULT '[]',
+            variables TEXT NOT NULL DEFAULT '[]',
+            slash_command TEXT UNIQUE COLLATE NOCASE,
+            show_in_copilot BOOLEAN NOT NULL DEFAULT FALSE,
+            overrides_builtin TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX idx_messages_conversation ON messages (conversation_id);
+        CREATE VIRTUAL TABLE messages_fts USING fts5(
+            body,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+        CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+            DELETE FROM messages_fts WHERE rowid = old.seq;
+        END;
+        CREATE TABLE user_memories (
+            id TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            category TEXT NOT NULL DEFAULT 'fact',
+            status TEXT NOT NULL DEFAULT 'active',
+            source_conversation_id TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE usage_records (
+            id TEXT PRIMARY KEY,
+            timestamp INTEGER NOT NULL,
+            provider_id TEXT NOT NULL,
+            provider_name TEXT NOT NULL,
+            model TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            purpose TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL DEFAULT 0,
+            completion_tokens INTEGER NOT NULL DEFAULT 0,
+            images INTEGER NOT NULL DEFAULT 0,
+            estimated BOOLEAN NOT NULL DEFAULT FALSE,
+            latency_ms INTEGER NOT NULL DEFAULT 0,
+            success BOOLEAN NOT NULL DEFAULT TRUE,
+            error TEXT,
+            cost REAL NOT NULL DEFAULT 0
+        );
+        CREATE INDEX idx_usage_records_timestamp ON usage_records (timestamp);
+        CREATE TABLE usage_budget_alerts (
+            month TEXT NOT NULL,
+            level TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (month, level)
+        );
+        CREATE TABLE attachments (
+            hash TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            path TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            extracted_text TEXT,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE execution_records (
+            id TEXT PRIMARY KEY,
+            started_at INTEGER NOT NULL,
+            tool_id TEXT,
+            runtime TEXT NOT NULL,
+            command_line TEXT NOT NULL DEFAULT '',
+            params TEXT NOT NULL DEFAULT 'null',
+            stdin TEXT,
+            stdout TEXT NOT NULL DEFAULT '',
+            stderr TEXT NOT NULL DEFAULT '',
+            exit_code INTEGER,
+            status TEXT NOT NULL,
+            error TEXT,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            source TEXT NOT NULL
+        );
+        CREATE INDEX idx_execution_records_started_at ON execution_records (started_at);
+        CREATE TABLE secrets (
+            name TEXT PRIMARY KEY,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE workflows (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            steps TEXT NOT NULL DEFAULT '[]',
+            show_in_copilot BOOLEAN NOT NULL DEFAULT FALSE,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE workflow_runs (
+            id TEXT PRIMARY KEY,
+            workflow_id TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            input TEXT NOT NULL DEFAULT 'null',
+            steps TEXT NOT NULL DEFAULT '[]',
+            output TEXT NOT NULL DEFAULT '',
+            error TEXT,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            source TEXT NOT NULL
+        );
+        CREATE INDEX idx_workflow_runs_started_at ON workflow_runs (started_at);
+        CREATE TABLE tool_bundles (
+            tool_id TEXT PRIMARY KEY,
+            hash TEXT NOT NULL,
+            source TEXT NOT NULL,
+            installed_at INTEGER NOT NULL
+        );
         CREATE TABLE creation_artifacts (
             id TEXT PRIMARY KEY,
             conversation_id TEXT NOT NULL,
@@ -89,6 +235,13 @@ fn run_initial_schema(conn: &mut Connection) -> Result<()> {
             metadata TEXT,
             FOREIGN KEY (conversation_id) REFERENCES conversations (id) ON DELETE CASCADE
         );
+        CREATE TABLE context_summaries (
+            covered_until_id TEXT PRIMARY KEY,
+            conversation_id TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            FOREIGN KEY (covered_until_id) REFERENCES messages (id) ON DELETE CASCADE,
+            FOREIGN KEY (conversation_id) REFERENCES conversations (id) ON DELETE CASCADE
+        );
         COMMIT;"
     )?;
     conn.execute(&format!("PRAGMA user_version = {}", LATEST_VERSION), [])?;
@@ -196,8 +349,633 @@ pub fn run(conn: &mut Connection) -> Result<()> {
         log::info!("Migration to version 21 successful.");
     }
 
+    if user_version < 22 {
+        log::info!("Migrating from version {} to 22...", user_version);
+        // Messages become a tree: each message points at the one it answers or follows,
+        // and each conversation remembers which leaf is currently displayed.
+        if !column_exists(conn, "messages", "parent_id")? {
+            conn.execute("ALTER TABLE messages ADD COLUMN parent_id TEXT;", [])?;
+        }
+        if !column_exists(conn, "conversations", "active_leaf_id")? {
+            conn.execute("ALTER TABLE conversations ADD COLUMN active_leaf_id TEXT;", [])?;
+        }
+        conn.execute_batch(
+            "BEGIN;
+            CREATE INDEX IF NOT EXISTS idx_messages_parent ON messages (parent_id);
+            UPDATE messages SET parent_id = (
+                SELECT prev.id FROM messages prev
+                WHERE prev.conversation_id = messages.conversation_id
+                  AND (prev.timestamp < messages.timestamp OR (prev.timestamp = messages.timestamp AND prev.id < messages.id))
+                ORDER BY prev.timestamp DESC, prev.id DESC
+                LIMIT 1
+            ) WHERE parent_id IS NULL;
+            UPDATE conversations SET active_leaf_id = (
+                SELECT id FROM messages WHERE conversation_id = conversations.id
+                ORDER BY timestamp DESC, id DESC
+                LIMIT 1
+            );
+            COMMIT;"
+        )?;
+        log::info!("Migration to version 22 successful.");
+    }
+
+    if user_version < 23 {
+        log::info!("Migrating from version {} to 23...", user_version);
+        if !column_exists(conn, "messages", "is_pinned")? {
+            conn.execute("ALTER TABLE messages ADD COLUMN is_pinned BOOLEAN NOT NULL DEFAULT FALSE;", [])?;
+        }
+        if !column_exists(conn, "conversations", "context_summary")? {
+            conn.execute("ALTER TABLE conversations ADD COLUMN context_summary TEXT;", [])?;
+        }
+        if !column_exists(conn, "conversations", "context_summary_until")? {
+            conn.execute("ALTER TABLE conversations ADD COLUMN context_summary_until TEXT;", [])?;
+        }
+        log::info!("Migration to version 23 successful.");
+    }
+
+    if user_version < 24 {
+        log::info!("Migrating from version {} to 24...", user_version);
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS personas (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                system_prompt TEXT NOT NULL DEFAULT '',
+                model TEXT,
+                temperature REAL,
+                top_p REAL,
+                max_tokens INTEGER,
+                stop TEXT NOT NULL DEFAULT '[]',
+                knowledge_base_selection TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );",
+            [],
+        )?;
+        if !column_exists(conn, "conversations", "persona_id")? {
+            conn.execute("ALTER TABLE conversations ADD COLUMN persona_id TEXT;", [])?;
+        }
+        log::info!("Migration to version 24 successful.");
+    }
+
+    if user_version < 25 {
+        log::info!("Migrating from version {} to 25...", user_version);
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS prompts (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                content TEXT NOT NULL,
+                folder TEXT NOT NULL DEFAULT '',
+                tags TEXT NOT NULL DEFAULT '[]',
+                variables TEXT NOT NULL DEFAULT '[]',
+                slash_command TEXT UNIQUE,
+                show_in_copilot BOOLEAN NOT NULL DEFAULT FALSE,
+                overrides_builtin TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );",
+            [],
+        )?;
+        log::info!("Migration to version 25 successful.");
+    }
+
+    if user_version < 26 {
+        log::info!("Migrating from version {} to 26...", user_version);
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                message_id UNINDEXED,
+                conversation_id UNINDEXED,
+                body,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            DELETE FROM messages_fts;"
+        )?;
+        // Index existing messages; the text has to be pulled out of the JSON content in Rust.
+        let tx = conn.transaction()?;
+        {
+            let mut select = tx.prepare("SELECT id, conversation_id, content FROM messages")?;
+            let mut insert = tx.prepare("INSERT INTO messages_fts (message_id, conversation_id, body) VALUES (?1, ?2, ?3)")?;
+            let rows = select.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+            let mut indexed = 0;
+            for row in rows {
+                let (id, conversation_id, content) = row?;
+                insert.execute(params![id, conversation_id, queries::search_text_from_content_json(&content)])?;
+                indexed += 1;
+            }
+            log::info!("Indexed {} existing messages for full-text search.", indexed);
+        }
+        tx.commit()?;
+        log::info!("Migration to version 26 successful.");
+    }
+
+    if user_version < 27 {
+        log::info!("Migrating from version {} to 27...", user_version);
+        if !column_exists(conn, "conversations", "memory_excluded")? {
+            conn.execute("ALTER TABLE conversations ADD COLUMN memory_excluded BOOLEAN NOT NULL DEFAULT FALSE;", [])?;
+        }
+        log::info!("Migration to version 27 successful.");
+    }
+
+    if user_version < 28 {
+        log::info!("Migrating from version {} to 28...", user_version);
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS user_memories (
+                id TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                category TEXT NOT NULL DEFAULT 'fact',
+                status TEXT NOT NULL DEFAULT 'active',
+                source_conversation_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );",
+            [],
+        )?;
+        log::info!("Migration to version 28 successful.");
+    }
+
+    if user_version < 29 {
+        log::info!("Migrating from version {} to 29...", user_version);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage_records (
+                id TEXT PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                provider_id TEXT NOT NULL,
+                provider_name TEXT NOT NULL,
+                model TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                purpose TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                images INTEGER NOT NULL DEFAULT 0,
+                estimated BOOLEAN NOT NULL DEFAULT FALSE,
+                latency_ms INTEGER NOT NULL DEFAULT 0,
+                success BOOLEAN NOT NULL DEFAULT TRUE,
+                error TEXT,
+                cost REAL NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS idx_usage_records_timestamp ON usage_records (timestamp);
+            CREATE TABLE IF NOT EXISTS usage_budget_alerts (
+                month TEXT NOT NULL,
+                level TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (month, level)
+            );"
+        )?;
+        log::info!("Migration to version 29 successful.");
+    }
+
+    if user_version < 30 {
+        log::info!("Migrating from version {} to 30...", user_version);
+        if !column_exists(conn, "messages", "generation_stats")? {
+            conn.execute("ALTER TABLE messages ADD COLUMN generation_stats TEXT;", [])?;
+        }
+        log::info!("Migration to version 30 successful.");
+    }
+
+    if user_version < 31 {
+        log::info!("Migrating from version {} to 31...", user_version);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS attachments (
+                hash TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                extracted_text TEXT,
+                created_at INTEGER NOT NULL
+            );"
+        )?;
+        log::info!("Migration to version 31 successful.");
+    }
+
+    if user_version < 32 {
+        log::info!("Migrating from version {} to 32...", user_version);
+        if !column_exists(conn, "configured_tools", "sandbox_policy")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN sandbox_policy TEXT;", [])?;
+        }
+        log::info!("Migration to version 32 successful.");
+    }
+
+    if user_version < 33 {
+        log::info!("Migrating from version {} to 33...", user_version);
+        if !column_exists(conn, "configured_tools", "timeout_secs")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN timeout_secs INTEGER;", [])?;
+        }
+        log::info!("Migration to version 33 successful.");
+    }
+
+    if user_version < 34 {
+        log::info!("Migrating from version {} to 34...", user_version);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS execution_records (
+                id TEXT PRIMARY KEY,
+                started_at INTEGER NOT NULL,
+                tool_id TEXT,
+                runtime TEXT NOT NULL,
+                command_line TEXT NOT NULL DEFAULT '',
+                params TEXT NOT NULL DEFAULT 'null',
+                stdin TEXT,
+                stdout TEXT NOT NULL DEFAULT '',
+                stderr TEXT NOT NULL DEFAULT '',
+                exit_code INTEGER,
+                status TEXT NOT NULL,
+                error TEXT,
+                duration_ms INTEGER NOT NULL DEFAULT 0,
+                source TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_execution_records_started_at ON execution_records (started_at);"
+        )?;
+        log::info!("Migration to version 34 successful.");
+    }
+
+    if user_version < 35 {
+        log::info!("Migrating from version {} to 35...", user_version);
+        if !column_exists(conn, "configured_tools", "argument_mapping")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN argument_mapping TEXT NOT NULL DEFAULT 'argv';", [])?;
+        }
+        log::info!("Migration to version 35 successful.");
+    }
+
+    if user_version < 36 {
+        log::info!("Migrating from version {} to 36...", user_version);
+        if !column_exists(conn, "configured_tools", "interpreter_version")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN interpreter_version TEXT;", [])?;
+        }
+        if !column_exists(conn, "configured_tools", "dependencies")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN dependencies TEXT NOT NULL DEFAULT '[]';", [])?;
+        }
+        log::info!("Migration to version 36 successful.");
+    }
+
+    if user_version < 37 {
+        log::info!("Migrating from version {} to 37...", user_version);
+        if !column_exists(conn, "configured_tools", "webhook_auth")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN webhook_auth TEXT;", [])?;
+        }
+        if !column_exists(conn, "configured_tools", "webhook_response_path")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN webhook_response_path TEXT;", [])?;
+        }
+        if !column_exists(conn, "configured_tools", "webhook_retries")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN webhook_retries INTEGER NOT NULL DEFAULT 0;", [])?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS secrets (
+                name TEXT PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );"
+        )?;
+        log::info!("Migration to version 37 successful.");
+    }
+
+    if user_version < 38 {
+        log::info!("Migrating from version {} to 38...", user_version);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS workflows (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                steps TEXT NOT NULL DEFAULT '[]',
+                show_in_copilot BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS workflow_runs (
+                id TEXT PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                input TEXT NOT NULL DEFAULT 'null',
+                steps TEXT NOT NULL DEFAULT '[]',
+                output TEXT NOT NULL DEFAULT '',
+                error TEXT,
+                duration_ms INTEGER NOT NULL DEFAULT 0,
+                source TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_workflow_runs_started_at ON workflow_runs (started_at);"
+        )?;
+        log::info!("Migration to version 38 successful.");
+    }
+
+    if user_version < 39 {
+        log::info!("Migrating from version {} to 39...", user_version);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tool_bundles (
+                tool_id TEXT PRIMARY KEY,
+                hash TEXT NOT NULL,
+                source TEXT NOT NULL,
+                installed_at INTEGER NOT NULL
+            );"
+        )?;
+        log::info!("Migration to version 39 successful.");
+    }
+
+    if user_version < 40 {
+        log::info!("Migrating from version {} to 40...", user_version);
+        if !column_exists(conn, "configured_tools", "allowed_secrets")? {
+            conn.execute("ALTER TABLE configured_tools ADD COLUMN allowed_secrets TEXT NOT NULL DEFAULT '[]';", [])?;
+        }
+        // Tools the user set up keep the secrets they already use; imported ones have to be allowed again.
+        let tools: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT id, COALESCE(webhook_url, '') || COALESCE(webhook_headers, '') || COALESCE(webhook_body_template, '') || COALESCE(webhook_auth, '')
+                 FROM configured_tools WHERE id NOT IN (SELECT tool_id FROM tool_bundles)",
+            )?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+        for (id, text) in tools {
+            let mut names = crate::services::secrets::references(&text);
+            if names.is_empty() {
+                continue;
+            }
+            names.sort();
+            names.dedup();
+            conn.execute("UPDATE configured_tools SET allowed_secrets = ?1 WHERE id = ?2", params![serde_json::to_string(&names)?, id])?;
+        }
+        log::info!("Migration to version 40 successful.");
+    }
+
+    if user_version < 41 {
+        log::info!("Migrating from version {} to 41...", user_version);
+        // The index is keyed on the messages' rowids instead of unindexed ID columns, so rows
+        // can be found without scanning the whole index, and a trigger keeps deletes in step.
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS messages_fts;
+            CREATE VIRTUAL TABLE messages_fts USING fts5(
+                body,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+                DELETE FROM messages_fts WHERE rowid = old.rowid;
+            END;
+            CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages (conversation_id);"
+        )?;
+        let tx = conn.transaction()?;
+        {
+            let mut select = tx.prepare("SELECT rowid, content FROM messages")?;
+            let mut insert = tx.prepare("INSERT INTO messages_fts (rowid, body) VALUES (?1, ?2)")?;
+            let rows = select.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+            for row in rows {
+                let (rowid, content) = row?;
+                insert.execute(params![rowid, queries::search_text_from_content_json(&content)])?;
+            }
+        }
+        tx.commit()?;
+        log::info!("Migration to version 41 successful.");
+    }
+
+    if user_version < 42 {
+        log::info!("Migrating from version {} to 42...", user_version);
+        // VACUUM may renumber implicit rowids, which the search index is keyed on. `seq` makes
+        // the rowid an explicit column, so it keeps the values the index already uses.
+        conn.execute_batch(
+            "BEGIN;
+            CREATE TABLE messages_new (
+                seq INTEGER PRIMARY KEY,
+                id TEXT NOT NULL UNIQUE,
+                conversation_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                suggestions TEXT,
+                model TEXT,
+                knowledge_base_selection TEXT,
+                sources TEXT,
+                error TEXT,
+                agent_task_id TEXT,
+                parent_id TEXT,
+                is_pinned BOOLEAN NOT NULL DEFAULT FALSE,
+                generation_stats TEXT,
+                FOREIGN KEY (conversation_id) REFERENCES conversations (id) ON DELETE CASCADE
+            );
+            INSERT INTO messages_new (seq, id, conversation_id, role, content, timestamp, suggestions, model, knowledge_base_selection, sources, error, agent_task_id, parent_id, is_pinned, generation_stats)
+                SELECT rowid, id, conversation_id, role, content, timestamp, suggestions, model, knowledge_base_selection, sources, error, agent_task_id, parent_id, is_pinned, generation_stats FROM messages;
+            DROP TABLE messages;
+            ALTER TABLE messages_new RENAME TO messages;
+            CREATE INDEX idx_messages_parent ON messages (parent_id);
+            CREATE INDEX idx_messages_conversation ON messages (conversation_id);
+            CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+                DELETE FROM messages_fts WHERE rowid = old.seq;
+            END;
+            COMMIT;"
+        )?;
+        log::info!("Migration to version 42 successful.");
+    }
+
+    if user_version < 43 {
+        log::info!("Migrating from version {} to 43...", user_version);
+        // Slash commands are looked up case-insensitively, so they must be unique that way too.
+        // Of commands that only differ in case, the oldest prompt keeps its own.
+        conn.execute_batch(
+            "BEGIN;
+            UPDATE prompts SET slash_command = NULL
+                WHERE EXISTS (SELECT 1 FROM prompts p WHERE p.slash_command = prompts.slash_command COLLATE NOCASE AND p.rowid < prompts.rowid);
+            CREATE TABLE prompts_new (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                content TEXT NOT NULL,
+                folder TEXT NOT NULL DEFAULT '',
+                tags TEXT NOT NULL DEFAULT '[]',
+                variables TEXT NOT NULL DEFAULT '[]',
+                slash_command TEXT UNIQUE COLLATE NOCASE,
+                show_in_copilot BOOLEAN NOT NULL DEFAULT FALSE,
+                overrides_builtin TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            INSERT INTO prompts_new (id, name, description, content, folder, tags, variables, slash_command, show_in_copilot, overrides_builtin, created_at, updated_at)
+                SELECT id, name, description, content, folder, tags, variables, slash_command, show_in_copilot, overrides_builtin, created_at, updated_at FROM prompts;
+            DROP TABLE prompts;
+            ALTER TABLE prompts_new RENAME TO prompts;
+            COMMIT;"
+        )?;
+        log::info!("Migration to version 43 successful.");
+    }
+
+    if user_version < 44 {
+        log::info!("Migrating from version {} to 44...", user_version);
+        // A conversation held a single rolling summary, which every branch then shared. Summaries
+        // are now keyed by the last message they cover, so a branch only reuses its own.
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS context_summaries (
+                covered_until_id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                FOREIGN KEY (covered_until_id) REFERENCES messages (id) ON DELETE CASCADE,
+                FOREIGN KEY (conversation_id) REFERENCES conversations (id) ON DELETE CASCADE
+            );"
+        )?;
+        if column_exists(&tx, "conversations", "context_summary")? {
+            tx.execute_batch(
+                "INSERT OR IGNORE INTO context_summaries (covered_until_id, conversation_id, summary)
+                    SELECT c.context_summary_until, c.id, c.context_summary FROM conversations c
+                    JOIN messages m ON m.id = c.context_summary_until AND m.conversation_id = c.id
+                    WHERE c.context_summary IS NOT NULL;
+                ALTER TABLE conversations DROP COLUMN context_summary;
+                ALTER TABLE conversations DROP COLUMN context_summary_until;"
+            )?;
+        }
+        tx.commit()?;
+        log::info!("Migration to version 44 successful.");
+    }
+
     conn.execute(&format!("PRAGMA user_version = {}", LATEST_VERSION), [])?;
     log::info!("All migrations applied. Database is now at version {}", LATEST_VERSION);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap()
+    }
+
+    fn search(conn: &Connection, term: &str) -> Vec<String> {
+        conn.prepare("SELECT m.id FROM messages_fts JOIN messages m ON m.seq = messages_fts.rowid WHERE messages_fts MATCH ?1 ORDER BY m.id")
+            .unwrap()
+            .query_map([term], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap()
+    }
+
+    fn insert_message(conn: &Connection, id: &str, text: &str) {
+        let content = serde_json::json!([{ "type": "text", "text": text }]).to_string();
+        conn.execute(
+            "INSERT INTO messages (id, conversation_id, role, content, timestamp) VALUES (?1, 'c1', 'user', ?2, 0)",
+            params![id, content],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn fresh_database_is_created_at_the_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), LATEST_VERSION);
+        assert!(table_exists(&conn, "configured_tools").unwrap());
+        assert!(column_exists(&conn, "configured_tools", "allowed_secrets").unwrap());
+
+        // Running again on an up-to-date database changes nothing.
+        run(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), LATEST_VERSION);
+    }
+
+    #[test]
+    fn search_index_is_rebuilt_on_rowids_and_follows_deletes() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        conn.execute("INSERT INTO conversations (id, title, created_at) VALUES ('c1', 'Chat', 0)", []).unwrap();
+        insert_message(&conn, "m1", "Ferris the crab");
+        insert_message(&conn, "m2", "another crab");
+        assert!(search(&conn, "crab").is_empty());
+
+        conn.execute("PRAGMA user_version = 40", []).unwrap();
+        run(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), LATEST_VERSION);
+        assert_eq!(search(&conn, "crab"), ["m1", "m2"]);
+        assert_eq!(search(&conn, "ferris"), ["m1"]);
+
+        conn.execute("DELETE FROM messages WHERE id = 'm1'", []).unwrap();
+        assert_eq!(search(&conn, "crab"), ["m2"]);
+        conn.execute("DELETE FROM conversations WHERE id = 'c1'", []).unwrap();
+        assert!(search(&conn, "crab").is_empty());
+    }
+
+    #[test]
+    fn messages_get_a_stable_rowid_column() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        // The messages table as it was before version 42, with only an implicit rowid.
+        conn.execute_batch(
+            "DROP TABLE messages;
+            CREATE TABLE messages (
+                id TEXT PRIMARY KEY, conversation_id TEXT NOT NULL, role TEXT NOT NULL, content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL, suggestions TEXT, model TEXT, knowledge_base_selection TEXT, sources TEXT,
+                error TEXT, agent_task_id TEXT, parent_id TEXT, is_pinned BOOLEAN NOT NULL DEFAULT FALSE, generation_stats TEXT
+            );
+            INSERT INTO conversations (id, title, created_at) VALUES ('c1', 'Chat', 0);
+            PRAGMA user_version = 40;",
+        )
+        .unwrap();
+        for (id, text) in [("m1", "first crab"), ("m2", "second lobster"), ("m3", "third crab")] {
+            insert_message(&conn, id, text);
+        }
+        run(&mut conn).unwrap();
+        assert!(column_exists(&conn, "messages", "seq").unwrap());
+
+        conn.execute("DELETE FROM messages WHERE id = 'm1'", []).unwrap();
+        conn.execute_batch("VACUUM").unwrap();
+        assert_eq!(search(&conn, "crab"), ["m3"]);
+        assert_eq!(search(&conn, "lobster"), ["m2"]);
+    }
+
+    #[test]
+    fn conversation_summaries_move_to_the_message_they_end_at() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        conn.execute_batch(
+            "ALTER TABLE conversations ADD COLUMN context_summary TEXT;
+            ALTER TABLE conversations ADD COLUMN context_summary_until TEXT;
+            INSERT INTO conversations (id, title, created_at, context_summary, context_summary_until) VALUES
+                ('c1', 'Chat', 0, 'kept', 'm1'), ('c2', 'Chat', 0, 'stale', 'gone');
+            PRAGMA user_version = 43;",
+        )
+        .unwrap();
+        insert_message(&conn, "m1", "hello");
+        run(&mut conn).unwrap();
+
+        assert!(!column_exists(&conn, "conversations", "context_summary").unwrap());
+        let summaries: Vec<(String, String, String)> = conn.prepare("SELECT covered_until_id, conversation_id, summary FROM context_summaries")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(summaries, [("m1".to_string(), "c1".to_string(), "kept".to_string())]);
+
+        conn.execute("DELETE FROM messages WHERE id = 'm1'", []).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM context_summaries", [], |row| row.get(0)).unwrap();
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn slash_commands_are_unique_regardless_of_case() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        // The prompts table as it was before version 43, where case-variants could coexist.
+        conn.execute_batch(
+            "DROP TABLE prompts;
+            CREATE TABLE prompts (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, description TEXT NOT NULL DEFAULT '', content TEXT NOT NULL,
+                folder TEXT NOT NULL DEFAULT '', tags TEXT NOT NULL DEFAULT '[]', variables TEXT NOT NULL DEFAULT '[]',
+                slash_command TEXT UNIQUE, show_in_copilot BOOLEAN NOT NULL DEFAULT FALSE, overrides_builtin TEXT,
+                created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+            );
+            INSERT INTO prompts (id, name, content, slash_command, created_at, updated_at) VALUES
+                ('p1', 'First', '', 'sum', 0, 0), ('p2', 'Second', '', 'Sum', 0, 0), ('p3', 'Third', '', 'fix', 0, 0);
+            PRAGMA user_version = 42;",
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+
+        let commands: Vec<(String, Option<String>)> = conn.prepare("SELECT id, slash_command FROM prompts ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(commands, [
+            ("p1".to_string(), Some("sum".to_string())),
+            ("p2".to_string(), None),
+            ("p3".to_string(), Some("fix".to_string())),
+        ]);
+        assert!(conn.execute("UPDATE prompts SET slash_command = 'FIX' WHERE id = 'p2'", []).is_err());
+    }
+}
@@ -50,6 +50,9 @@ pub struct ModelAssignments {
     pub debate_judge: Option<ModelEndpoint>,
     pub write: Option<ModelEndpoint>,
     pub refine: Option<ModelEndpoint>,
+    /// Speech-to-text, used to transcribe audio attachments.
+    #[serde(default)]
+    pub stt: Option<ModelEndpoint>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -155,6 +158,19 @@ pub struct ExecutionSettings {
     pub auto_start_backend: bool,
     #[serde(default = "default_backend_url")]
     pub backend_url: String,
+    /// Applies to the code runners and to tools without a policy of their own.
+    #[serde(default)]
+    pub sandbox: SandboxPolicy,
+    /// Wall-clock seconds before a tool or code run is stopped; 0 for no limit.
+    #[serde(default = "default_execution_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub history: ExecutionHistorySettings,
+    /// Installs the dependencies of Python tools into their environments.
+    #[serde(default)]
+    pub package_installer: PackageInstaller,
+    #[serde(default)]
+    pub sharing: ToolSharingSettings,
 }
 
 impl Default for ExecutionSettings {
@@ -165,6 +181,373 @@ impl Default for ExecutionSettings {
             working_directory: "".to_string(),
             auto_start_backend: false,
             backend_url: default_backend_url(),
+            sandbox: SandboxPolicy::default(),
+            timeout_secs: default_execution_timeout_secs(),
+            history: ExecutionHistorySettings::default(),
+            package_installer: PackageInstaller::default(),
+            sharing: ToolSharingSettings::default(),
+        }
+    }
+}
+
+fn default_execution_timeout_secs() -> u64 { 300 }
+
+/// `auto` uses uv when it is on the PATH and falls back to `python -m venv` with pip.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageInstaller {
+    #[default]
+    Auto,
+    Uv,
+    Pip,
+}
+
+/// How much of the execution log is kept. Runs beyond either limit are deleted as new ones are recorded.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionHistorySettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 0 keeps runs regardless of age.
+    #[serde(default = "default_execution_retention_days")]
+    pub retention_days: u32,
+    /// 0 for no limit.
+    #[serde(default = "default_execution_max_records")]
+    pub max_records: u32,
+    /// Stdout and stderr are each cut to this many bytes.
+    #[serde(default = "default_execution_log_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+impl Default for ExecutionHistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: default_execution_retention_days(),
+            max_records: default_execution_max_records(),
+            max_output_bytes: default_execution_log_output_bytes(),
+        }
+    }
+}
+
+/// Signing of exported tool bundles and the places shared bundles are offered from.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolSharingSettings {
+    /// Secret store entry holding the user's ed25519 signing key; exports are signed with it.
+    /// Empty to export unsigned.
+    #[serde(default)]
+    pub signing_secret: String,
+    /// Publishers whose signed bundles are trusted. The user's own key is always trusted.
+    #[serde(default)]
+    pub trusted_publishers: Vec<TrustedPublisher>,
+    /// Refuses bundle files and shared folder bundles without a trusted signature.
+    /// Repositories have their own setting.
+    #[serde(default)]
+    pub require_signature: bool,
+    /// Folders, e.g. on a network share, scanned for `.nexustool` bundles.
+    #[serde(default)]
+    pub bundle_directories: Vec<String>,
+    #[serde(default)]
+    pub repositories: Vec<ToolRepository>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedPublisher {
+    pub name: String,
+    /// Base64 ed25519 public key.
+    pub public_key: String,
+}
+
+/// A git repository of tool bundles, cloned under the app data directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolRepository {
+    pub url: String,
+    /// The remote's default branch when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Only bundles signed by a trusted publisher are imported from it.
+    #[serde(default = "default_true")]
+    pub require_signature: bool,
+}
+
+fn default_execution_retention_days() -> u32 { 30 }
+fn default_execution_max_records() -> u32 { 1000 }
+fn default_execution_log_output_bytes() -> usize { 64 * 1024 }
+
+fn default_sandbox_env() -> Vec<String> {
+    ["PATH", "HOME", "USER", "LANG", "LC_*", "TERM", "TZ", "TMPDIR", "TEMP", "TMP", "SYSTEMROOT", "COMSPEC", "PATHEXT"]
+        .iter().map(|s| s.to_string()).collect()
+}
+fn default_sandbox_cpu_time_secs() -> u64 { 60 }
+fn default_sandbox_memory_mb() -> u64 { 2048 }
+fn default_sandbox_max_output_bytes() -> usize { 1024 * 1024 }
+
+/// What code run by tools and the code runners may do. On Linux the process runs in a
+/// bubblewrap sandbox that only sees system directories (read-only), its working directory
+/// and the listed paths; elsewhere only the environment filter and the limits apply.
+/// Off unless the user turns it on, so existing tools keep working as they did.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Extra files or directories the process may read.
+    #[serde(default)]
+    pub read_paths: Vec<String>,
+    /// Extra files or directories the process may write, besides its working directory.
+    #[serde(default)]
+    pub write_paths: Vec<String>,
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Environment variables passed to the process; a trailing `*` matches a prefix.
+    #[serde(default = "default_sandbox_env")]
+    pub env_allowlist: Vec<String>,
+    /// CPU seconds before the process is killed; 0 for no limit.
+    #[serde(default = "default_sandbox_cpu_time_secs")]
+    pub cpu_time_secs: u64,
+    /// Limit on allocated memory (the data segment) in MB; 0 for no limit.
+    #[serde(default = "default_sandbox_memory_mb")]
+    pub memory_mb: u64,
+    /// Output beyond this many bytes stops the process; 0 for no limit.
+    #[serde(default = "default_sandbox_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            read_paths: vec![],
+            write_paths: vec![],
+            allow_network: false,
+            env_allowlist: default_sandbox_env(),
+            cpu_time_secs: default_sandbox_cpu_time_secs(),
+            memory_mb: default_sandbox_memory_mb(),
+            max_output_bytes: default_sandbox_max_output_bytes(),
+        }
+    }
+}
+
+fn default_api_server_host() -> String { "127.0.0.1".to_string() }
+fn default_api_server_port() -> u16 { 8765 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_api_server_host")]
+    pub host: String,
+    #[serde(default = "default_api_server_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub api_key: String,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_api_server_host(),
+            port: default_api_server_port(),
+            api_key: "".to_string(),
+        }
+    }
+}
+
+fn default_true() -> bool { true }
+fn default_memory_top_k() -> u32 { 3 }
+fn default_memory_score_threshold() -> f32 { 0.5 }
+
+/// Opt-in semantic memory: finished exchanges are embedded into a separate vector collection
+/// and relevant ones from other conversations are offered to the model as extra context.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationMemorySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Include recalled exchanges in chat requests; when false, exchanges are only stored.
+    #[serde(default = "default_true")]
+    pub include_in_chat: bool,
+    #[serde(default = "default_memory_top_k")]
+    pub top_k: u32,
+    #[serde(default = "default_memory_score_threshold")]
+    pub score_threshold: f32,
+}
+
+impl Default for ConversationMemorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            include_in_chat: true,
+            top_k: default_memory_top_k(),
+            score_threshold: default_memory_score_threshold(),
+        }
+    }
+}
+
+fn default_max_injected_memories() -> usize { 10 }
+
+/// Long-term facts and preferences about the user. Proposed memories are only used after
+/// the user accepts them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMemorySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Ask the suggestion model after each reply whether the exchange revealed something worth remembering.
+    #[serde(default = "default_true")]
+    pub propose_after_reply: bool,
+    #[serde(default = "default_max_injected_memories")]
+    pub max_injected: usize,
+}
+
+impl Default for UserMemorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            propose_after_reply: true,
+            max_injected: default_max_injected_memories(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 { 2 }
+fn default_initial_backoff_ms() -> u64 { 500 }
+fn default_max_backoff_ms() -> u64 { 8000 }
+fn default_breaker_failure_threshold() -> u32 { 3 }
+fn default_breaker_cooldown_secs() -> u64 { 60 }
+
+/// Retries, fallbacks and circuit breaking for model calls. Transient errors (429, 5xx, timeouts)
+/// are retried with exponential backoff; when a model keeps failing, the next entry of its
+/// assignment's fallback chain is tried. A provider that fails `breaker_failure_threshold` times
+/// in a row is skipped for `breaker_cooldown_secs`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FailoverSettings {
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[serde(default = "default_breaker_failure_threshold")]
+    pub breaker_failure_threshold: u32,
+    #[serde(default = "default_breaker_cooldown_secs")]
+    pub breaker_cooldown_secs: u64,
+    /// Models to try in order after the primary one, keyed by assignment ("chat", "suggestion", ...).
+    #[serde(default)]
+    pub fallbacks: HashMap<String, Vec<ModelEndpoint>>,
+}
+
+impl Default for FailoverSettings {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            breaker_failure_threshold: default_breaker_failure_threshold(),
+            breaker_cooldown_secs: default_breaker_cooldown_secs(),
+            fallbacks: HashMap::new(),
+        }
+    }
+}
+
+fn default_budget_alert_threshold() -> f64 { 0.8 }
+
+fn model_price(model: &str, input_per_million: f64, output_per_million: f64, per_image: f64) -> ModelPrice {
+    ModelPrice { provider_id: String::new(), model: model.to_string(), input_per_million, output_per_million, per_image }
+}
+
+fn default_model_prices() -> Vec<ModelPrice> {
+    vec![
+        model_price("gpt-4o-mini", 0.15, 0.6, 0.0),
+        model_price("gpt-4o", 2.5, 10.0, 0.0),
+        model_price("gpt-4-turbo", 10.0, 30.0, 0.0),
+        model_price("gpt-3.5-turbo", 0.5, 1.5, 0.0),
+        model_price("text-embedding-3-small", 0.02, 0.0, 0.0),
+        model_price("text-embedding-3-large", 0.13, 0.0, 0.0),
+        model_price("dall-e-3", 0.0, 0.0, 0.04),
+    ]
+}
+
+/// Price of a model in US dollars. `model` also matches dated variants (e.g. "gpt-4o" matches
+/// "gpt-4o-2024-08-06"); an empty `provider_id` applies to every provider.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub provider_id: String,
+    pub model: String,
+    #[serde(default)]
+    pub input_per_million: f64,
+    #[serde(default)]
+    pub output_per_million: f64,
+    #[serde(default)]
+    pub per_image: f64,
+}
+
+/// Local accounting of every model call made by the app.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Monthly spending limit in US dollars; no alerts when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_budget: Option<f64>,
+    /// Fraction of the budget at which the first alert is sent; a second one is sent when it is exceeded.
+    #[serde(default = "default_budget_alert_threshold")]
+    pub alert_threshold: f64,
+    #[serde(default = "default_model_prices")]
+    pub prices: Vec<ModelPrice>,
+}
+
+impl Default for UsageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            monthly_budget: None,
+            alert_threshold: default_budget_alert_threshold(),
+            prices: default_model_prices(),
+        }
+    }
+}
+
+fn default_context_strategy() -> String { "summarize".to_string() }
+fn default_context_window() -> u32 { 8192 }
+fn default_response_reserve() -> u32 { 1024 }
+fn default_min_recent_messages() -> usize { 4 }
+
+/// Controls how chat history is trimmed to fit the model's context window.
+/// `strategy` is one of "summarize" (older turns are folded into a rolling summary),
+/// "sliding_window" (older turns are dropped) or "off" (full history is always sent).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSettings {
+    #[serde(default = "default_context_strategy")]
+    pub strategy: String,
+    /// Used when the selected model has no `maxTokens` configured.
+    #[serde(default = "default_context_window")]
+    pub default_context_window: u32,
+    /// Tokens kept free for the model's reply.
+    #[serde(default = "default_response_reserve")]
+    pub response_reserve: u32,
+    /// The most recent messages are always sent, even when they exceed the budget.
+    #[serde(default = "default_min_recent_messages")]
+    pub min_recent_messages: usize,
+}
+
+impl Default for ContextSettings {
+    fn default() -> Self {
+        Self {
+            strategy: default_context_strategy(),
+            default_context_window: default_context_window(),
+            response_reserve: default_response_reserve(),
+            min_recent_messages: default_min_recent_messages(),
         }
     }
 }
@@ -213,6 +596,18 @@ pub struct Settings {
     pub execution: ExecutionSettings,
     #[serde(default)]
     pub shortcuts: ShortcutsSettings,
+    #[serde(rename = "apiServer", default)]
+    pub api_server: ApiServerSettings,
+    #[serde(default)]
+    pub context: ContextSettings,
+    #[serde(rename = "conversationMemory", default)]
+    pub conversation_memory: ConversationMemorySettings,
+    #[serde(rename = "userMemory", default)]
+    pub user_memory: UserMemorySettings,
+    #[serde(default)]
+    pub usage: UsageSettings,
+    #[serde(default)]
+    pub failover: FailoverSettings,
 }
 
 impl Settings {
@@ -251,6 +646,12 @@ impl Settings {
             appearance: AppearanceSettings::default(),
             execution: ExecutionSettings::default(),
             shortcuts: ShortcutsSettings::default(),
+            api_server: ApiServerSettings::default(),
+            context: ContextSettings::default(),
+            conversation_memory: ConversationMemorySettings::default(),
+            user_memory: UserMemorySettings::default(),
+            usage: UsageSettings::default(),
+            failover: FailoverSettings::default(),
         }
     }
 }
@@ -262,6 +663,29 @@ impl Settings {
 pub enum ChatMessageContentPart {
     Text { text: String },
     ImageUrl { image_url: ImageUrl },
+    /// A document, spreadsheet or source file; its extracted text is given to the model.
+    File { file: Attachment },
+    /// A voice note; the model gets its transcript, which is filled in when the message is sent.
+    Audio {
+        audio: Attachment,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+}
+
+/// A file in the attachment store. Files are named after the SHA-256 of their content, so
+/// attaching the same file twice stores it once.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub hash: String,
+    pub name: String,
+    pub path: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Estimated tokens of the extracted text, for documents that could be parsed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_tokens: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -279,8 +703,8 @@ pub struct ChatMessage {
     pub timestamp: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<Vec<knowledge_base::models::KnowledgeSource>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ChatError>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggestions: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -295,6 +719,107 @@ pub struct ChatMessage {
     pub agent_task_id: Option<String>,
     #[serde(rename = "agentTask", default, skip_serializing_if = "Option::is_none")]
     pub agent_task: Option<AgentTask>,
+    #[serde(rename = "parentId", default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// IDs of all messages sharing this message's parent (including itself), oldest first.
+    /// Only populated when the message has alternative versions.
+    #[serde(rename = "siblingIds", default, skip_serializing_if = "Option::is_none")]
+    pub sibling_ids: Option<Vec<String>>,
+    /// Pinned messages are always kept in the model's context, however long the chat grows.
+    #[serde(rename = "isPinned", default)]
+    pub is_pinned: bool,
+    #[serde(rename = "generationStats", default, skip_serializing_if = "Option::is_none")]
+    pub generation_stats: Option<GenerationStats>,
+}
+
+/// Timing and size of a generated reply. Token counts are `estimated` when the provider
+/// didn't report usage.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationStats {
+    pub latency_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_token_ms: Option<i64>,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub estimated: bool,
+}
+
+/// Why a reply failed, so the UI can explain it and offer the right fix.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatErrorKind {
+    /// The provider rejected the API key or the account has no access to the model.
+    Auth,
+    RateLimit,
+    /// The prompt plus the requested output doesn't fit the model's context window.
+    ContextLength,
+    /// The connection dropped or timed out on the way to the provider.
+    Network,
+    /// The provider rejected the request (4xx other than the cases above).
+    ProviderClient,
+    /// The provider failed (5xx).
+    ProviderServer,
+    /// The local Python backend isn't running or can't be reached.
+    BackendUnavailable,
+    /// Nothing was sent: no model selected, unknown provider and the like.
+    Config,
+    Unknown,
+}
+
+/// The error stored on a failed AI message. `partial` is set when some text arrived before the
+/// failure; `retry_message` then continues that text instead of starting over.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", from = "StoredChatError")]
+pub struct ChatError {
+    pub kind: ChatErrorKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub retryable: bool,
+    #[serde(default)]
+    pub partial: bool,
+}
+
+impl ChatError {
+    pub fn new(kind: ChatErrorKind, message: impl Into<String>) -> Self {
+        let retryable = !matches!(kind, ChatErrorKind::Auth | ChatErrorKind::Config | ChatErrorKind::ContextLength);
+        Self { kind, message: message.into(), status: None, retryable, partial: false }
+    }
+}
+
+/// Messages saved before errors were classified (and the frontend) store a bare string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredChatError {
+    #[serde(rename_all = "camelCase")]
+    Typed {
+        kind: ChatErrorKind,
+        message: String,
+        #[serde(default)]
+        status: Option<u16>,
+        retryable: bool,
+        #[serde(default)]
+        partial: bool,
+    },
+    Message(String),
+}
+
+impl From<StoredChatError> for ChatError {
+    fn from(stored: StoredChatError) -> Self {
+        match stored {
+            StoredChatError::Typed { kind, message, status, retryable, partial } => Self { kind, message, status, retryable, partial },
+            StoredChatError::Message(message) => Self::new(ChatErrorKind::Unknown, message),
+        }
+    }
+}
+
+/// One model of a compare request and the placeholder ID its reply streams under.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareTarget {
+    pub model: String,
+    pub ai_message_id: String,
 }
 
 impl Default for ChatMessage {
@@ -314,6 +839,10 @@ impl Default for ChatMessage {
             execution_output: None,
             agent_task_id: None,
             agent_task: None,
+            parent_id: None,
+            sibling_ids: None,
+            is_pinned: false,
+            generation_stats: None,
         }
     }
 }
@@ -327,6 +856,375 @@ pub struct Conversation {
     pub session_type: String, // 'chat' or 'creation'
     #[serde(default)]
     pub artifacts: Vec<CreationArtifact>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_leaf_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persona_id: Option<String>,
+}
+
+/// A reusable assistant configuration that can be assigned to conversations.
+/// `system_prompt` may contain template variables such as `{{date}}`, `{{clipboard}}` and
+/// `{{selection}}`, which are expanded every time a message is sent.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Persona {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub system_prompt: String,
+    /// Default model as "providerId::modelName", used when a message doesn't name one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub stop: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub knowledge_base_selection: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSearchOptions {
+    /// Match the query as one exact phrase instead of all of its words.
+    #[serde(default)]
+    pub phrase: bool,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    /// Inclusive lower bound, epoch milliseconds.
+    #[serde(default)]
+    pub from: Option<i64>,
+    /// Inclusive upper bound, epoch milliseconds.
+    #[serde(default)]
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A message matching a full-text search. `snippet` is HTML-escaped text with the matched
+/// terms in `<mark>` tags. Open the hit with `switch_branch(conversation_id, message_id)`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSearchHit {
+    pub conversation_id: String,
+    pub conversation_title: String,
+    pub message_id: String,
+    pub role: String,
+    pub model: Option<String>,
+    pub timestamp: i64,
+    pub snippet: String,
+}
+
+/// A remembered fact or preference about the user.
+/// `category` is "preference" (always relevant) or "fact" (used when it relates to the message);
+/// `status` is "proposed" until the user accepts it, then "active".
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMemory {
+    #[serde(default)]
+    pub id: String,
+    pub content: String,
+    #[serde(default = "default_user_memory_category")]
+    pub category: String,
+    #[serde(default = "default_user_memory_status")]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_conversation_id: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn default_user_memory_category() -> String { "fact".to_string() }
+fn default_user_memory_status() -> String { "active".to_string() }
+
+/// One model call. `kind` is "chat", "embedding" or "image"; `purpose` names the feature that made
+/// the call ("chat", "title", "suggestions", "summary", "memory", "kb_search", "api_server", ...).
+/// Token counts are `estimated` when the provider didn't report usage.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    pub id: String,
+    pub timestamp: i64,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub model: String,
+    pub kind: String,
+    pub purpose: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub images: u32,
+    pub estimated: bool,
+    pub latency_ms: i64,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Cost in US dollars at the prices configured when the call was made.
+    pub cost: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    Success,
+    Error,
+    Cancelled,
+    Timeout,
+}
+
+/// One tool run or ad-hoc code run. `runtime` is a `ToolRuntime` name or "built_in"; ad-hoc code
+/// has no `tool_id` and keeps its source in `params.code`. `source` is the surface that started
+/// the run: "copilot", "chat", "agent" or "api_server".
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub started_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_id: Option<String>,
+    pub runtime: String,
+    /// The program and arguments as run, before sandboxing; the method and URL template for
+    /// webhook tools, empty for built-in ones.
+    pub command_line: String,
+    pub params: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub status: ExecutionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub source: String,
+}
+
+/// A saved pipeline of steps. Each step's inputs are templates that can use `{{input}}`,
+/// `{{params.NAME}}`, `{{previous}}` and `{{steps.ID.output}}` (also `.status`, `.error`, and
+/// `.json.FIELD` for JSON output), so outputs feed into later steps. Runs as `workflow::<id>`
+/// wherever a tool can run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Workflow {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<WorkflowStep>,
+    #[serde(default)]
+    pub show_in_copilot: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStep {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub action: WorkflowAction,
+    /// Steps that must finish first. Without it the step follows the one before it in the
+    /// list; an empty list makes it a starting step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
+    /// Runs the step only if this holds, e.g. `{{steps.check.output}} contains yes`. Steps that
+    /// depend on a skipped step are skipped too, which makes branches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(default)]
+    pub on_error: StepErrorPolicy,
+    /// Extra attempts before the step counts as failed.
+    #[serde(default)]
+    pub retries: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum WorkflowAction {
+    /// A configured, dynamic or built-in tool. String values in `params` are templates.
+    Tool {
+        tool_id: String,
+        #[serde(default)]
+        params: serde_json::Value,
+    },
+    /// Sends the prompt to the suggestion model.
+    Prompt { prompt: String },
+    KbSearch {
+        query: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        top_k: Option<u32>,
+    },
+    ClipboardWrite { text: String },
+    SaveToKb { content: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StepErrorPolicy {
+    /// The run fails.
+    #[default]
+    Stop,
+    /// The run goes on; later steps see the failure in `{{steps.ID.status}}` and `.error`.
+    Continue,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowRunStatus {
+    Running,
+    Success,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStepStatus {
+    Success,
+    Failed,
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStepResult {
+    pub step_id: String,
+    pub status: WorkflowStepStatus,
+    pub output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub attempts: u32,
+    pub duration_ms: i64,
+}
+
+/// One run of a workflow, with the outcome of every step in the order they ran.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowRun {
+    pub id: String,
+    pub workflow_id: String,
+    pub started_at: i64,
+    pub status: WorkflowRunStatus,
+    pub input: serde_json::Value,
+    pub steps: Vec<WorkflowStepResult>,
+    /// Output of the last step that ran.
+    pub output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub source: String,
+}
+
+/// Usage totals for one day, model, provider or feature.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBreakdownRow {
+    pub key: String,
+    pub calls: i64,
+    pub failed_calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBudgetStatus {
+    /// Calendar month in local time, as "YYYY-MM".
+    pub month: String,
+    pub spent: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+}
+
+/// Outcome of importing chat history from another assistant. With a dry run nothing is written
+/// and the "new" counters report what would be imported.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportStats {
+    pub format: String,
+    pub dry_run: bool,
+    pub conversations_found: usize,
+    pub conversations_new: usize,
+    pub conversations_updated: usize,
+    pub messages_found: usize,
+    pub messages_new: usize,
+    pub messages_skipped: usize,
+    pub images_imported: usize,
+    pub images_missing: usize,
+    pub errors: Vec<String>,
+}
+
+/// What to include when exporting conversations.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationExportOptions {
+    #[serde(default)]
+    pub include_thinking: bool,
+    #[serde(default = "default_true")]
+    pub include_sources: bool,
+    /// Inline local images as data URIs instead of linking to their paths.
+    #[serde(default = "default_true")]
+    pub embed_images: bool,
+    #[serde(default = "default_true")]
+    pub include_agent_reports: bool,
+    #[serde(default = "default_true")]
+    pub include_artifacts: bool,
+}
+
+impl Default for ConversationExportOptions {
+    fn default() -> Self {
+        Self {
+            include_thinking: false,
+            include_sources: true,
+            embed_images: true,
+            include_agent_reports: true,
+            include_artifacts: true,
+        }
+    }
+}
+
+/// Rolling summary of the part of a conversation branch that no longer fits in the context window.
+/// `covered_until_id` is the last message folded into the summary; it is only reused on branches
+/// that contain that message.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSummary {
+    pub summary: String,
+    pub covered_until_id: String,
+}
+
+/// Token accounting for the context that would be sent with the next request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextUsage {
+    pub budget: u32,
+    pub used_tokens: u32,
+    pub total_messages: usize,
+    pub included_messages: usize,
+    pub pinned_messages: usize,
+    pub summarized: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -397,21 +1295,56 @@ pub struct IntentSuggestion {
     pub icon: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum ToolParameterType {
+    #[default]
     Text,
     Textarea,
+    Number,
+    Boolean,
+    Select,
+    File,
+    Directory,
+    /// An ISO date, "YYYY-MM-DD".
+    Date,
+    /// A string that is masked in the execution log.
+    Secret,
+    /// Any JSON value; given as text, it is parsed before validation.
+    Json,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolParameter {
     pub name: String,
+    #[serde(default)]
     pub label: String,
+    #[serde(default, alias = "type")]
     pub param_type: ToolParameterType,
+    #[serde(default, alias = "default")]
     pub default_value: String,
+    #[serde(default)]
     pub required: bool,
+    /// Allowed values for `select` parameters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// How a script tool receives its params; `stdin` always goes to standard input in the
+/// first two modes.
+/// - `argv`: `--name value` per param, declared params first. `true` booleans become a bare
+///   `--name` flag; `false`, null and empty strings are left out; arrays and objects are passed as JSON.
+///   Secret params are passed as env variables instead, since other users can read a process's argv.
+/// - `env`: one `NEXUS_PARAM_<NAME>` variable per param, values formatted as for `argv`.
+/// - `json_stdin`: the whole params object as JSON on standard input.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolArgumentMapping {
+    #[default]
+    Argv,
+    Env,
+    JsonStdin,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -431,6 +1364,67 @@ pub enum ToolOutputHandling {
     NewChat,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptVariableType {
+    #[default]
+    Text,
+    Textarea,
+    Number,
+    Boolean,
+    Select,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptVariable {
+    pub name: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(rename = "type", default)]
+    pub var_type: PromptVariableType,
+    #[serde(default)]
+    pub default_value: String,
+    /// Allowed values for `select` variables.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A saved prompt in the prompt library. `content` uses `{{variable}}` placeholders; besides the
+/// declared `variables`, the built-in template variables (`{{date}}`, `{{clipboard}}`, ...) are available.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub content: String,
+    /// Slash-separated folder path, empty for the library root.
+    #[serde(default)]
+    pub folder: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub variables: Vec<PromptVariable>,
+    /// Invokes the prompt from chat as `/<slash_command> ...`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slash_command: Option<String>,
+    #[serde(default)]
+    pub show_in_copilot: bool,
+    /// Replaces a built-in prompt: "title" (receives `{{query}}` and `{{response}}`)
+    /// or "suggestions" (receives `{{context}}`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overrides_builtin: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ToolRuntime {
@@ -460,8 +1454,9 @@ pub struct ConfiguredTool {
     pub webhook_headers: Option<String>, // JSON string
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub webhook_body_template: Option<String>, // JSON string
+    /// JSON Schema the params must satisfy. Without one, a schema is derived from `parameters`.
     #[serde(default)]
-    pub input_schema: Option<String>, // JSON Schema string
+    pub input_schema: Option<String>,
     #[serde(default)]
     pub runtime: ToolRuntime,
     pub parameters: Vec<ToolParameter>,
@@ -473,6 +1468,8 @@ pub struct ConfiguredTool {
     pub input_source: ToolInputSource,
     #[serde(default)]
     pub requires_ai_pre_processing: bool,
+    /// Its answer replaces the input. A prompt that mentions `{{params}}` extracts params as a
+    /// JSON object instead.
     #[serde(default)]
     pub pre_processing_prompt: String,
     #[serde(default = "default_output_handling")]
@@ -481,11 +1478,104 @@ pub struct ConfiguredTool {
     pub requires_ai_post_processing: bool,
     #[serde(default)]
     pub post_processing_prompt: String,
+    /// Overrides the default sandbox policy from the execution settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxPolicy>,
+    /// Overrides the global execution timeout; 0 for no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub argument_mapping: ToolArgumentMapping,
+    /// Interpreter versions the script supports, e.g. ">=3.10" or ">=18, <23"; checked before each run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter_version: Option<String>,
+    /// pip requirements for Python tools, npm package specs for Node tools.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_auth: Option<WebhookAuth>,
+    /// JSONPath applied to a JSON response, e.g. "$.data.items[*].name"; the tool's output is what it selects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_response_path: Option<String>,
+    /// Extra attempts after a connection error, a 429 or a 5xx response.
+    #[serde(default)]
+    pub webhook_retries: u32,
+    /// Secrets the tool may reference as `{{secret:NAME}}`. Only ever set by the local user;
+    /// bundles don't carry it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_secrets: Vec<String>,
 }
 
 fn default_input_source() -> ToolInputSource { ToolInputSource::UserInput }
 fn default_output_handling() -> ToolOutputHandling { ToolOutputHandling::RawText }
 
+/// How a webhook tool authenticates. Credential fields may reference the secret store as
+/// `{{secret:NAME}}` instead of holding the value.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum WebhookAuth {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    ApiKey {
+        name: String,
+        value: String,
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+    /// Signs the request body with HMAC-SHA256. With a `timestamp_header`, the signed message is
+    /// `<unix seconds>.<body>` and the timestamp is sent in that header.
+    Hmac {
+        secret: String,
+        #[serde(default = "default_signature_header")]
+        header: String,
+        /// Put before the signature, e.g. "sha256=".
+        #[serde(default)]
+        prefix: String,
+        #[serde(default)]
+        encoding: SignatureEncoding,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timestamp_header: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+fn default_signature_header() -> String { "X-Signature".to_string() }
+
+/// A named credential. The value lives in the OS keychain, never in the database.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Which bundle a configured tool was last imported from.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledToolBundle {
+    pub tool_id: String,
+    pub hash: String,
+    /// Path of the bundle file it was imported from.
+    pub source: String,
+    pub installed_at: i64,
+}
+
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClipboardHistoryItem {
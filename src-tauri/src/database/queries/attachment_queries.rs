@@ -0,0 +1,52 @@
+// src-tauri/src/database/queries/attachment_queries.rs
+use crate::database::models::*;
+use crate::error::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub fn get_attachment(conn: &Connection, hash: &str) -> Result<Option<Attachment>> {
+    conn.query_row(
+        "SELECT hash, name, path, mime_type, size FROM attachments WHERE hash = ?1",
+        params![hash],
+        |row| Ok(Attachment {
+            hash: row.get(0)?,
+            name: row.get(1)?,
+            path: row.get(2)?,
+            mime_type: row.get(3)?,
+            size: row.get(4)?,
+            text_tokens: None,
+        }),
+    ).optional().map_err(Into::into)
+}
+
+/// Stores the attachment, or points an existing row for the same content at the new copy
+/// (the old file may have been deleted). A transcript already stored is kept.
+pub fn upsert_attachment(conn: &Connection, attachment: &Attachment, extracted_text: Option<&str>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO attachments (hash, name, path, mime_type, size, extracted_text, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(hash) DO UPDATE SET name = excluded.name, path = excluded.path, mime_type = excluded.mime_type, size = excluded.size,
+             extracted_text = COALESCE(excluded.extracted_text, attachments.extracted_text)",
+        params![
+            attachment.hash,
+            attachment.name,
+            attachment.path,
+            attachment.mime_type,
+            attachment.size,
+            extracted_text,
+            chrono::Utc::now().timestamp_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Text extracted from a document, or the transcript of an audio file.
+pub fn get_attachment_text(conn: &Connection, hash: &str) -> Result<Option<String>> {
+    let text: Option<Option<String>> = conn
+        .query_row("SELECT extracted_text FROM attachments WHERE hash = ?1", params![hash], |row| row.get(0))
+        .optional()?;
+    Ok(text.flatten())
+}
+
+pub fn set_attachment_text(conn: &Connection, hash: &str, text: &str) -> Result<()> {
+    conn.execute("UPDATE attachments SET extracted_text = ?1 WHERE hash = ?2", params![text, hash])?;
+    Ok(())
+}
@@ -1,16 +1,119 @@
 // src-tauri/src/database/queries/chat_queries.rs
 use crate::database::models::*;
-use crate::error::Result;
+use crate::error::{AppError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
 
+const MESSAGE_COLUMNS: &str = "id, conversation_id, role, content, timestamp, suggestions, model, knowledge_base_selection, sources, error, agent_task_id, parent_id, is_pinned, generation_stats";
+
+static THINK_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<think>.*?(</think>|$)").unwrap());
+
+/// Plain text of a message as indexed for full-text search: text parts only, without `<think>` blocks.
+pub fn search_text_from_content(content: &[ChatMessageContentPart]) -> String {
+    content.iter()
+        .filter_map(|part| match part {
+            ChatMessageContentPart::Text { text } => Some(THINK_BLOCK.replace_all(text, "").trim().to_string()),
+            _ => None,
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn search_text_from_content_json(content_json: &str) -> String {
+    serde_json::from_str::<Vec<ChatMessageContentPart>>(content_json)
+        .map(|content| search_text_from_content(&content))
+        .unwrap_or_default()
+}
+
+/// Index rows share the message's `seq`; a trigger removes them when the message is deleted.
+fn index_message_text(conn: &Connection, message_id: &str, text: &str) -> Result<()> {
+    let seq: i64 = conn.query_row("SELECT seq FROM messages WHERE id = ?1", params![message_id], |row| row.get(0))?;
+    conn.execute("DELETE FROM messages_fts WHERE rowid = ?1", params![seq])?;
+    conn.execute("INSERT INTO messages_fts (rowid, body) VALUES (?1, ?2)", params![seq, text])?;
+    Ok(())
+}
+
+fn map_message_row(row: &rusqlite::Row) -> rusqlite::Result<ChatMessage> {
+    let agent_task_id: Option<String> = row.get(10)?;
+
+    let agent_task = if agent_task_id.is_some() {
+        Some(AgentTask::default())
+    } else {
+        None
+    };
+
+    Ok(ChatMessage {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        role: row.get(2)?,
+        content: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+        timestamp: row.get(4)?,
+        suggestions: row.get::<_, Option<String>>(5)?.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
+        model: row.get(6)?,
+        knowledge_base_selection: row.get(7)?,
+        sources: row.get::<_, Option<String>>(8)?.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
+        error: row.get::<_, Option<String>>(9)?.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
+        agent_task_id,
+        agent_task,
+        is_executing: None,
+        execution_output: None,
+        parent_id: row.get(11)?,
+        sibling_ids: None,
+        is_pinned: row.get(12)?,
+        generation_stats: row.get::<_, Option<String>>(13)?.and_then(|s| serde_json::from_str(&s).ok()),
+    })
+}
+
+/// Saves a message into the conversation tree.
+/// A new message without an explicit `parent_id` is attached to the conversation's active leaf
+/// and becomes the new active leaf; use `save_root_message` to start a new tree instead.
+/// Re-saving an existing message keeps its position in the tree and its pin; use
+/// `set_message_pinned` to unpin.
 pub fn save_message(conn: &Connection, msg: &ChatMessage) -> Result<()> {
+    let existing = existing_placement(conn, &msg.id)?;
+    let parent_id = match (&msg.parent_id, &existing) {
+        (Some(parent_id), _) => Some(parent_id.clone()),
+        (None, Some((existing, _))) => existing.clone(),
+        (None, None) => get_active_leaf_id(conn, &msg.conversation_id)?.filter(|leaf| leaf != &msg.id),
+    };
+    write_message(conn, msg, parent_id, existing)
+}
+
+/// Saves a message without a parent, as a first message of the conversation, whatever the
+/// active leaf is. A new one becomes the active leaf.
+pub fn save_root_message(conn: &Connection, msg: &ChatMessage) -> Result<()> {
+    let existing = existing_placement(conn, &msg.id)?;
+    write_message(conn, msg, None, existing)
+}
+
+/// Parent and pin of a message that is already stored.
+fn existing_placement(conn: &Connection, message_id: &str) -> Result<Option<(Option<String>, bool)>> {
+    conn.query_row("SELECT parent_id, is_pinned FROM messages WHERE id = ?1", params![message_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+        .map_err(Into::into)
+}
+
+fn write_message(conn: &Connection, msg: &ChatMessage, parent_id: Option<String>, existing: Option<(Option<String>, bool)>) -> Result<()> {
     let content_json = serde_json::to_string(&msg.content)?;
     let sources_json = serde_json::to_string(&msg.sources)?;
     let suggestions_json = serde_json::to_string(&msg.suggestions)?;
     let error_json = serde_json::to_string(&msg.error)?;
+    let stats_json = msg.generation_stats.as_ref().map(serde_json::to_string).transpose()?;
+
+    let is_new = existing.is_none();
+    let is_pinned = msg.is_pinned || existing.as_ref().is_some_and(|(_, pinned)| *pinned);
 
+    // An upsert rather than INSERT OR REPLACE, so the row keeps its `seq` and with it its search index row.
     conn.execute(
-        "INSERT OR REPLACE INTO messages (id, conversation_id, role, content, timestamp, suggestions, model, knowledge_base_selection, sources, error, agent_task_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        "INSERT INTO messages (id, conversation_id, role, content, timestamp, suggestions, model, knowledge_base_selection, sources, error, agent_task_id, parent_id, is_pinned, generation_stats) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+         ON CONFLICT(id) DO UPDATE SET conversation_id = excluded.conversation_id, role = excluded.role, content = excluded.content,
+             timestamp = excluded.timestamp, suggestions = excluded.suggestions, model = excluded.model,
+             knowledge_base_selection = excluded.knowledge_base_selection, sources = excluded.sources, error = excluded.error,
+             agent_task_id = excluded.agent_task_id, parent_id = excluded.parent_id, is_pinned = excluded.is_pinned,
+             generation_stats = excluded.generation_stats",
         params![
             msg.id,
             msg.conversation_id,
@@ -23,60 +126,291 @@ pub fn save_message(conn: &Connection, msg: &ChatMessage) -> Result<()> {
             sources_json,
             error_json,
             msg.agent_task_id,
+            parent_id,
+            is_pinned,
+            stats_json,
         ],
     )?;
+    index_message_text(conn, &msg.id, &search_text_from_content(&msg.content))?;
+
+    if is_new {
+        set_active_leaf(conn, &msg.conversation_id, &msg.id)?;
+    }
     Ok(())
 }
 
+pub fn get_message_by_id(conn: &Connection, message_id: &str) -> Result<Option<ChatMessage>> {
+    conn.query_row(
+        &format!("SELECT {} FROM messages WHERE id = ?1", MESSAGE_COLUMNS),
+        params![message_id],
+        map_message_row,
+    ).optional().map_err(Into::into)
+}
+
+/// IDs of a message and every message below it in the conversation tree.
+pub fn get_message_subtree_ids(conn: &Connection, message_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE subtree(id) AS (
+            SELECT id FROM messages WHERE id = ?1
+            UNION ALL
+            SELECT m.id FROM messages m JOIN subtree s ON m.parent_id = s.id
+        )
+        SELECT id FROM subtree"
+    )?;
+    let ids = stmt.query_map(params![message_id], |row| row.get(0))?;
+    ids.collect::<rusqlite::Result<Vec<String>>>().map_err(Into::into)
+}
+
+/// IDs of the direct replies to a message, oldest first.
+pub fn get_child_message_ids(conn: &Connection, parent_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT id FROM messages WHERE parent_id = ?1 ORDER BY timestamp ASC")?;
+    let ids = stmt.query_map(params![parent_id], |row| row.get(0))?;
+    ids.collect::<rusqlite::Result<Vec<String>>>().map_err(Into::into)
+}
+
+/// Deletes a message together with every reply branching off it.
 pub fn delete_message(conn: &Connection, message_id: &str) -> Result<()> {
-    let affected = conn.execute("DELETE FROM messages WHERE id = ?1", params![message_id])?;
-    if affected == 0 {
-        log::warn!("Attempted to delete a non-existent message with ID: {}", message_id);
+    let message = match get_message_by_id(conn, message_id)? {
+        Some(message) => message,
+        None => {
+            log::warn!("Attempted to delete a non-existent message with ID: {}", message_id);
+            return Ok(());
+        }
+    };
+
+    let affected = conn.execute(
+        "WITH RECURSIVE subtree(id) AS (
+            SELECT id FROM messages WHERE id = ?1
+            UNION ALL
+            SELECT m.id FROM messages m JOIN subtree s ON m.parent_id = s.id
+        )
+        DELETE FROM messages WHERE id IN (SELECT id FROM subtree)",
+        params![message_id],
+    )?;
+    log::info!("Deleted message {} and {} descendant(s).", message_id, affected.saturating_sub(1));
+
+    let active_leaf_exists = match get_active_leaf_id(conn, &message.conversation_id)? {
+        Some(leaf) => get_message_by_id(conn, &leaf)?.is_some(),
+        None => false,
+    };
+    if !active_leaf_exists {
+        let fallback = match message.parent_id {
+            Some(parent_id) => Some(find_latest_leaf(conn, &parent_id)?),
+            None => conn.query_row(
+                "SELECT id FROM messages WHERE conversation_id = ?1 ORDER BY timestamp DESC LIMIT 1",
+                params![message.conversation_id],
+                |row| row.get::<_, String>(0),
+            ).optional()?,
+        };
+        conn.execute(
+            "UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1",
+            params![message.conversation_id, fallback],
+        )?;
     }
     Ok(())
 }
 
 pub fn update_message_content(conn: &Connection, message_id: &str, new_content_json: &str) -> Result<()> {
-    conn.execute(
+    let updated = conn.execute(
         "UPDATE messages SET content = ?2 WHERE id = ?1",
         params![message_id, new_content_json],
     )?;
+    if updated > 0 {
+        index_message_text(conn, message_id, &search_text_from_content_json(new_content_json))?;
+    }
     Ok(())
 }
 
-pub fn get_conversation_history(conn: &Connection, conversation_id: &str) -> Result<Vec<ChatMessage>> {
-    let mut stmt = conn.prepare("SELECT id, conversation_id, role, content, timestamp, suggestions, model, knowledge_base_selection, sources, error, agent_task_id FROM messages WHERE conversation_id = ?1 ORDER BY timestamp ASC")?;
-    let msg_iter = stmt.query_map(params![conversation_id], |row| {
-        let agent_task_id: Option<String> = row.get(10)?;
-        
-        let agent_task = if agent_task_id.is_some() {
-            Some(AgentTask::default())
-        } else {
-            None
-        };
+/// Turns free text into an FTS5 query: either one exact phrase, or all words (the last one as a
+/// prefix so results update while typing). Quoting every term keeps FTS operators out of user input.
+fn build_fts_query(query: &str, phrase: bool) -> Option<String> {
+    let quote = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+    if phrase {
+        let trimmed = query.trim();
+        return (!trimmed.is_empty()).then(|| quote(trimmed));
+    }
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    let (last, rest) = terms.split_last()?;
+    let mut parts: Vec<String> = rest.iter().map(|t| quote(t)).collect();
+    parts.push(format!("{}*", quote(last)));
+    Some(parts.join(" "))
+}
 
-        Ok(ChatMessage {
-            id: row.get(0)?,
-            conversation_id: row.get(1)?,
-            role: row.get(2)?,
-            content: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
-            timestamp: row.get(4)?,
-            suggestions: row.get::<_, Option<String>>(5)?.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
-            model: row.get(6)?,
-            knowledge_base_selection: row.get(7)?,
-            sources: row.get::<_, Option<String>>(8)?.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
-            error: row.get::<_, Option<String>>(9)?.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
-            agent_task_id: row.get(10)?,
-            agent_task,
-            is_executing: None,
-            execution_output: None,
-        })
+/// Marks the matched terms in a snippet. FTS wraps them in control characters, which can't
+/// occur in the escaped text, so the message text itself never turns into markup.
+fn highlight_snippet(snippet: &str) -> String {
+    crate::services::export::html_escape(snippet).replace('\u{2}', "<mark>").replace('\u{3}', "</mark>")
+}
+
+pub fn search_messages(conn: &Connection, query: &str, options: &MessageSearchOptions) -> Result<Vec<MessageSearchHit>> {
+    let Some(fts_query) = build_fts_query(query, options.phrase) else { return Ok(vec![]) };
+
+    let mut stmt = conn.prepare(
+        "SELECT m.conversation_id, c.title, m.id, m.role, m.model, m.timestamp,
+                snippet(messages_fts, 0, char(2), char(3), '…', 16)
+         FROM messages_fts
+         JOIN messages m ON m.seq = messages_fts.rowid
+         JOIN conversations c ON c.id = m.conversation_id
+         WHERE messages_fts MATCH ?1
+           AND (?2 IS NULL OR m.model = ?2)
+           AND (?3 IS NULL OR m.role = ?3)
+           AND (?4 IS NULL OR m.conversation_id = ?4)
+           AND (?5 IS NULL OR m.timestamp >= ?5)
+           AND (?6 IS NULL OR m.timestamp <= ?6)
+         ORDER BY bm25(messages_fts), m.timestamp DESC
+         LIMIT ?7"
+    )?;
+    let hit_iter = stmt.query_map(
+        params![
+            fts_query,
+            options.model,
+            options.role,
+            options.conversation_id,
+            options.from,
+            options.to,
+            options.limit.unwrap_or(50).min(500),
+        ],
+        |row| {
+            Ok(MessageSearchHit {
+                conversation_id: row.get(0)?,
+                conversation_title: row.get(1)?,
+                message_id: row.get(2)?,
+                role: row.get(3)?,
+                model: row.get(4)?,
+                timestamp: row.get(5)?,
+                snippet: highlight_snippet(&row.get::<_, String>(6)?),
+            })
+        },
+    )?;
+    hit_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn set_message_pinned(conn: &Connection, message_id: &str, pinned: bool) -> Result<()> {
+    conn.execute(
+        "UPDATE messages SET is_pinned = ?2 WHERE id = ?1",
+        params![message_id, pinned],
+    )?;
+    Ok(())
+}
+
+/// Rolling summaries stored for a conversation, one for each message a summary ended at.
+pub fn list_context_summaries(conn: &Connection, conversation_id: &str) -> Result<Vec<ContextSummary>> {
+    let mut stmt = conn.prepare("SELECT summary, covered_until_id FROM context_summaries WHERE conversation_id = ?1")?;
+    let summary_iter = stmt.query_map(params![conversation_id], |row| {
+        Ok(ContextSummary { summary: row.get(0)?, covered_until_id: row.get(1)? })
     })?;
-    msg_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+    summary_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn save_context_summary(conn: &Connection, conversation_id: &str, summary: &ContextSummary) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO context_summaries (covered_until_id, conversation_id, summary) VALUES (?1, ?2, ?3)",
+        params![summary.covered_until_id, conversation_id, summary.summary],
+    )?;
+    Ok(())
+}
+
+pub fn clear_context_summaries(conn: &Connection, conversation_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM context_summaries WHERE conversation_id = ?1", params![conversation_id])?;
+    Ok(())
+}
+
+pub fn is_conversation_memory_excluded(conn: &Connection, conversation_id: &str) -> Result<bool> {
+    let excluded: Option<bool> = conn
+        .query_row("SELECT memory_excluded FROM conversations WHERE id = ?1", params![conversation_id], |row| row.get(0))
+        .optional()?;
+    Ok(excluded.unwrap_or(false))
+}
+
+pub fn set_conversation_memory_excluded(conn: &Connection, conversation_id: &str, excluded: bool) -> Result<()> {
+    conn.execute(
+        "UPDATE conversations SET memory_excluded = ?2 WHERE id = ?1",
+        params![conversation_id, excluded],
+    )?;
+    Ok(())
+}
+
+pub fn get_active_leaf_id(conn: &Connection, conversation_id: &str) -> Result<Option<String>> {
+    let leaf: Option<Option<String>> = conn
+        .query_row("SELECT active_leaf_id FROM conversations WHERE id = ?1", params![conversation_id], |row| row.get(0))
+        .optional()?;
+    Ok(leaf.flatten())
+}
+
+pub fn set_active_leaf(conn: &Connection, conversation_id: &str, message_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1",
+        params![conversation_id, message_id],
+    )?;
+    Ok(())
+}
+
+/// Sets the active leaf to the newest leaf below `message_id` and returns the path to it. The
+/// message has to be in `conversation_id`, or the conversation would show another one's tree.
+pub fn switch_branch(conn: &Connection, conversation_id: &str, message_id: &str) -> Result<Vec<ChatMessage>> {
+    let message = get_message_by_id(conn, message_id)?
+        .ok_or_else(|| AppError::Database(format!("Message {} not found", message_id)))?;
+    if message.conversation_id != conversation_id {
+        return Err(AppError::Database(format!("Message {} is not in conversation {}", message_id, conversation_id)));
+    }
+    let leaf_id = find_latest_leaf(conn, message_id)?;
+    set_active_leaf(conn, conversation_id, &leaf_id)?;
+    get_conversation_history(conn, conversation_id)
+}
+
+/// Follows the most recent reply at every level below `message_id` and returns the leaf it ends on.
+pub fn find_latest_leaf(conn: &Connection, message_id: &str) -> Result<String> {
+    let mut current = message_id.to_string();
+    loop {
+        let child: Option<String> = conn.query_row(
+            "SELECT id FROM messages WHERE parent_id = ?1 ORDER BY timestamp DESC LIMIT 1",
+            params![current],
+            |row| row.get(0),
+        ).optional()?;
+        match child {
+            Some(child_id) => current = child_id,
+            None => return Ok(current),
+        }
+    }
+}
+
+/// Returns the active branch of a conversation, from the root message down to the active leaf.
+/// Messages with alternative versions carry the IDs of their siblings so the UI can switch branches.
+pub fn get_conversation_history(conn: &Connection, conversation_id: &str) -> Result<Vec<ChatMessage>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM messages WHERE conversation_id = ?1 ORDER BY timestamp ASC", MESSAGE_COLUMNS))?;
+    let all_messages = stmt
+        .query_map(params![conversation_id], map_message_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut children: HashMap<Option<String>, Vec<String>> = HashMap::new();
+    for msg in &all_messages {
+        children.entry(msg.parent_id.clone()).or_default().push(msg.id.clone());
+    }
+    let by_id: HashMap<&str, &ChatMessage> = all_messages.iter().map(|m| (m.id.as_str(), m)).collect();
+
+    let leaf_id = get_active_leaf_id(conn, conversation_id)?
+        .filter(|id| by_id.contains_key(id.as_str()))
+        .or_else(|| all_messages.last().map(|m| m.id.clone()));
+
+    let mut path = Vec::new();
+    let mut current = leaf_id;
+    while let Some(id) = current {
+        let Some(msg) = by_id.get(id.as_str()) else { break };
+        if path.len() > all_messages.len() {
+            log::error!("Detected a cycle in the message tree of conversation {}", conversation_id);
+            break;
+        }
+        let mut msg = (*msg).clone();
+        msg.sibling_ids = children.get(&msg.parent_id).filter(|ids| ids.len() > 1).cloned();
+        current = msg.parent_id.clone();
+        path.push(msg);
+    }
+    path.reverse();
+    Ok(path)
 }
 
 pub fn list_conversations(conn: &Connection) -> Result<Vec<Conversation>> {
-    let mut stmt = conn.prepare("SELECT id, title, created_at, session_type FROM conversations ORDER BY created_at DESC")?;
+    let mut stmt = conn.prepare("SELECT id, title, created_at, session_type, active_leaf_id, persona_id FROM conversations ORDER BY created_at DESC")?;
     let convo_iter = stmt.query_map([], |row| {
         Ok(Conversation {
             id: row.get(0)?,
@@ -84,6 +418,8 @@ pub fn list_conversations(conn: &Connection) -> Result<Vec<Conversation>> {
             created_at: row.get(2)?,
             session_type: row.get(3)?,
             artifacts: vec![],
+            active_leaf_id: row.get(4)?,
+            persona_id: row.get(5)?,
         })
     })?;
     convo_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
@@ -91,7 +427,7 @@ pub fn list_conversations(conn: &Connection) -> Result<Vec<Conversation>> {
 
 pub fn get_conversation_by_id(conn: &Connection, id: &str) -> Result<Option<Conversation>> {
     conn.query_row(
-        "SELECT id, title, created_at, session_type FROM conversations WHERE id = ?1",
+        "SELECT id, title, created_at, session_type, active_leaf_id, persona_id FROM conversations WHERE id = ?1",
         [id],
         |row| {
             Ok(Conversation {
@@ -100,6 +436,8 @@ pub fn get_conversation_by_id(conn: &Connection, id: &str) -> Result<Option<Conv
                 created_at: row.get(2)?,
                 session_type: row.get(3)?,
                 artifacts: vec![],
+                active_leaf_id: row.get(4)?,
+                persona_id: row.get(5)?,
             })
         },
     ).optional().map_err(Into::into)
@@ -122,6 +460,22 @@ pub fn ensure_conversation_exists(conn: &Connection, id: &str, title: &str, sess
     Ok(())
 }
 
+/// Creates a conversation with a known creation time (used by importers). Returns false if it already existed.
+pub fn insert_conversation_if_missing(conn: &Connection, id: &str, title: &str, created_at: i64) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO conversations (id, title, created_at, session_type) VALUES (?1, ?2, ?3, 'chat')",
+        params![id, title, created_at],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn message_exists(conn: &Connection, message_id: &str) -> Result<bool> {
+    let exists: Option<i64> = conn
+        .query_row("SELECT 1 FROM messages WHERE id = ?1", params![message_id], |row| row.get(0))
+        .optional()?;
+    Ok(exists.is_some())
+}
+
 pub fn update_conversation_title(conn: &Connection, id: &str, new_title: &str) -> Result<()> {
     conn.execute("UPDATE conversations SET title = ?2 WHERE id = ?1", params![id, new_title])?;
     Ok(())
@@ -170,4 +524,46 @@ pub fn get_artifacts_for_conversation(conn: &Connection, conversation_id: &str)
         })
     })?;
     artifact_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migrations;
+
+    fn message(id: &str, conversation_id: &str, parent_id: Option<&str>, timestamp: i64) -> ChatMessage {
+        ChatMessage {
+            id: id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            role: "user".to_string(),
+            timestamp,
+            parent_id: parent_id.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn switch_branch_stays_in_the_conversation() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::run(&mut conn).unwrap();
+        for id in ["c1", "c2"] {
+            insert_conversation_if_missing(&conn, id, "Chat", 0).unwrap();
+        }
+        save_root_message(&conn, &message("a", "c1", None, 1)).unwrap();
+        save_message(&conn, &message("b1", "c1", Some("a"), 2)).unwrap();
+        save_message(&conn, &message("b2", "c1", Some("a"), 3)).unwrap();
+        save_root_message(&conn, &message("x", "c2", None, 4)).unwrap();
+
+        let path: Vec<String> = switch_branch(&conn, "c1", "b1").unwrap().into_iter().map(|m| m.id).collect();
+        assert_eq!(path, ["a", "b1"]);
+
+        assert!(switch_branch(&conn, "c1", "x").is_err());
+        assert!(switch_branch(&conn, "c1", "missing").is_err());
+        assert_eq!(get_active_leaf_id(&conn, "c1").unwrap().as_deref(), Some("b1"));
+    }
+
+    #[test]
+    fn snippets_escape_message_text_and_mark_matches() {
+        let raw = "<img src=x onerror=alert(1)> \u{2}crab\u{3} & \"more\"";
+        assert_eq!(highlight_snippet(raw), "&lt;img src=x onerror=alert(1)&gt; <mark>crab</mark> &amp; &quot;more&quot;");
+    }
+}
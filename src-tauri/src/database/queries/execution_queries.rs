@@ -0,0 +1,93 @@
+// src-tauri/src/database/queries/execution_queries.rs
+use crate::database::models::*;
+use crate::error::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const EXECUTION_COLUMNS: &str = "id, started_at, tool_id, runtime, command_line, params, stdin, stdout, stderr, exit_code, status, error, duration_ms, source";
+
+fn map_execution_row(row: &rusqlite::Row) -> rusqlite::Result<ExecutionRecord> {
+    Ok(ExecutionRecord {
+        id: row.get(0)?,
+        started_at: row.get(1)?,
+        tool_id: row.get(2)?,
+        runtime: row.get(3)?,
+        command_line: row.get(4)?,
+        params: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+        stdin: row.get(6)?,
+        stdout: row.get(7)?,
+        stderr: row.get(8)?,
+        exit_code: row.get(9)?,
+        status: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(10)?)).unwrap_or(ExecutionStatus::Error),
+        error: row.get(11)?,
+        duration_ms: row.get(12)?,
+        source: row.get(13)?,
+    })
+}
+
+pub fn insert_execution_record(conn: &Connection, record: &ExecutionRecord) -> Result<()> {
+    let params_json = serde_json::to_string(&record.params)?;
+    let status_str = serde_json::to_string(&record.status)?.trim_matches('"').to_string();
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO execution_records ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)", EXECUTION_COLUMNS),
+        params![
+            &record.id,
+            &record.started_at,
+            &record.tool_id,
+            &record.runtime,
+            &record.command_line,
+            params_json,
+            &record.stdin,
+            &record.stdout,
+            &record.stderr,
+            &record.exit_code,
+            status_str,
+            &record.error,
+            &record.duration_ms,
+            &record.source,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_execution_record(conn: &Connection, id: &str) -> Result<Option<ExecutionRecord>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM execution_records WHERE id = ?1", EXECUTION_COLUMNS))?;
+    let record = stmt.query_row(params![id], map_execution_row).optional()?;
+    Ok(record)
+}
+
+/// Lists runs newest first. `query` matches the tool ID, command line, params and output;
+/// `tool_id` and `status` ("success", "error", "cancelled", "timeout") filter exactly.
+pub fn list_execution_records(conn: &Connection, query: Option<&str>, tool_id: Option<&str>, status: Option<&str>, limit: u32) -> Result<Vec<ExecutionRecord>> {
+    let pattern = query.filter(|q| !q.trim().is_empty()).map(|q| format!("%{}%", q.trim()));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM execution_records
+         WHERE (?1 IS NULL OR tool_id LIKE ?1 OR command_line LIKE ?1 OR params LIKE ?1 OR stdout LIKE ?1 OR stderr LIKE ?1)
+           AND (?2 IS NULL OR tool_id = ?2)
+           AND (?3 IS NULL OR status = ?3)
+         ORDER BY started_at DESC LIMIT ?4",
+        EXECUTION_COLUMNS
+    ))?;
+    let record_iter = stmt.query_map(params![pattern, tool_id, status, limit], map_execution_row)?;
+    record_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+/// Deletes runs started before `before` (Unix millis) and all but the newest `keep`. Returns how many were deleted.
+pub fn prune_execution_records(conn: &Connection, before: Option<i64>, keep: Option<u32>) -> Result<usize> {
+    let mut deleted = 0;
+    if let Some(before) = before {
+        deleted += conn.execute("DELETE FROM execution_records WHERE started_at < ?1", params![before])?;
+    }
+    if let Some(keep) = keep {
+        deleted += conn.execute(
+            "DELETE FROM execution_records WHERE id NOT IN (SELECT id FROM execution_records ORDER BY started_at DESC LIMIT ?1)",
+            params![keep],
+        )?;
+    }
+    Ok(deleted)
+}
+
+/// Deletes runs started before `before` (Unix millis), or all of them.
+pub fn clear_execution_records(conn: &Connection, before: Option<i64>) -> Result<usize> {
+    let deleted = conn.execute("DELETE FROM execution_records WHERE ?1 IS NULL OR started_at < ?1", params![before])?;
+    Ok(deleted)
+}
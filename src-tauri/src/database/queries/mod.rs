@@ -7,6 +7,15 @@ mod note_queries;
 mod clipboard_queries;
 mod agent_queries;
 mod online_kb_queries;
+mod persona_queries;
+mod prompt_queries;
+mod user_memory_queries;
+mod usage_queries;
+mod attachment_queries;
+mod execution_queries;
+mod secret_queries;
+mod workflow_queries;
+mod tool_bundle_queries;
 
 // Re-export all public functions from the sub-modules
 pub use settings_queries::*;
@@ -14,4 +23,13 @@ pub use chat_queries::*;
 pub use tool_queries::*;
 pub use clipboard_queries::*;
 pub use agent_queries::*;
-pub use online_kb_queries::*;
\ No newline at end of file
+pub use online_kb_queries::*;
+pub use persona_queries::*;
+pub use prompt_queries::*;
+pub use user_memory_queries::*;
+pub use usage_queries::*;
+pub use attachment_queries::*;
+pub use execution_queries::*;
+pub use secret_queries::*;
+pub use workflow_queries::*;
+pub use tool_bundle_queries::*;
\ No newline at end of file
@@ -0,0 +1,86 @@
+// src-tauri/src/database/queries/persona_queries.rs
+use crate::database::models::*;
+use crate::error::{AppError, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const PERSONA_COLUMNS: &str = "id, name, description, system_prompt, model, temperature, top_p, max_tokens, stop, knowledge_base_selection, created_at, updated_at";
+
+fn map_persona_row(row: &rusqlite::Row) -> rusqlite::Result<Persona> {
+    Ok(Persona {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        system_prompt: row.get(3)?,
+        model: row.get(4)?,
+        temperature: row.get::<_, Option<f64>>(5)?.map(|v| v as f32),
+        top_p: row.get::<_, Option<f64>>(6)?.map(|v| v as f32),
+        max_tokens: row.get(7)?,
+        stop: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_default(),
+        knowledge_base_selection: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+pub fn list_personas(conn: &Connection) -> Result<Vec<Persona>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM personas ORDER BY name COLLATE NOCASE ASC", PERSONA_COLUMNS))?;
+    let persona_iter = stmt.query_map([], map_persona_row)?;
+    persona_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn get_persona_by_id(conn: &Connection, id: &str) -> Result<Option<Persona>> {
+    conn.query_row(
+        &format!("SELECT {} FROM personas WHERE id = ?1", PERSONA_COLUMNS),
+        params![id],
+        map_persona_row,
+    ).optional().map_err(Into::into)
+}
+
+pub fn save_persona(conn: &Connection, persona: &Persona) -> Result<()> {
+    let stop_json = serde_json::to_string(&persona.stop)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO personas (id, name, description, system_prompt, model, temperature, top_p, max_tokens, stop, knowledge_base_selection, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            &persona.id,
+            &persona.name,
+            &persona.description,
+            &persona.system_prompt,
+            &persona.model,
+            persona.temperature.map(f64::from),
+            persona.top_p.map(f64::from),
+            &persona.max_tokens,
+            stop_json,
+            &persona.knowledge_base_selection,
+            &persona.created_at,
+            &persona.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_persona(conn: &mut Connection, id: &str) -> Result<()> {
+    let tx = conn.transaction()?;
+    let affected = tx.execute("DELETE FROM personas WHERE id = ?1", params![id])?;
+    if affected == 0 {
+        return Err(AppError::Database("Persona not found for deletion".to_string()));
+    }
+    tx.execute("UPDATE conversations SET persona_id = NULL WHERE persona_id = ?1", params![id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn set_conversation_persona(conn: &Connection, conversation_id: &str, persona_id: Option<&str>) -> Result<()> {
+    conn.execute(
+        "UPDATE conversations SET persona_id = ?2 WHERE id = ?1",
+        params![conversation_id, persona_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_conversation_persona(conn: &Connection, conversation_id: &str) -> Result<Option<Persona>> {
+    conn.query_row(
+        &format!("SELECT {} FROM personas WHERE id = (SELECT persona_id FROM conversations WHERE id = ?1)", PERSONA_COLUMNS),
+        params![conversation_id],
+        map_persona_row,
+    ).optional().map_err(Into::into)
+}
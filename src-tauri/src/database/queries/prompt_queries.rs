@@ -0,0 +1,91 @@
+// src-tauri/src/database/queries/prompt_queries.rs
+use crate::database::models::*;
+use crate::error::{AppError, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const PROMPT_COLUMNS: &str = "id, name, description, content, folder, tags, variables, slash_command, show_in_copilot, overrides_builtin, created_at, updated_at";
+
+fn map_prompt_row(row: &rusqlite::Row) -> rusqlite::Result<PromptTemplate> {
+    Ok(PromptTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        content: row.get(3)?,
+        folder: row.get(4)?,
+        tags: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+        variables: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+        slash_command: row.get(7)?,
+        show_in_copilot: row.get(8)?,
+        overrides_builtin: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+pub fn list_prompts(conn: &Connection) -> Result<Vec<PromptTemplate>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM prompts ORDER BY folder COLLATE NOCASE ASC, name COLLATE NOCASE ASC", PROMPT_COLUMNS))?;
+    let prompt_iter = stmt.query_map([], map_prompt_row)?;
+    prompt_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn get_prompt_by_id(conn: &Connection, id: &str) -> Result<Option<PromptTemplate>> {
+    conn.query_row(
+        &format!("SELECT {} FROM prompts WHERE id = ?1", PROMPT_COLUMNS),
+        params![id],
+        map_prompt_row,
+    ).optional().map_err(Into::into)
+}
+
+pub fn get_prompt_by_slash_command(conn: &Connection, command: &str) -> Result<Option<PromptTemplate>> {
+    conn.query_row(
+        &format!("SELECT {} FROM prompts WHERE slash_command = ?1 COLLATE NOCASE", PROMPT_COLUMNS),
+        params![command],
+        map_prompt_row,
+    ).optional().map_err(Into::into)
+}
+
+/// Returns the user's replacement for a built-in prompt ("title" or "suggestions"), if any.
+pub fn get_builtin_prompt_override(conn: &Connection, builtin: &str) -> Result<Option<PromptTemplate>> {
+    conn.query_row(
+        &format!("SELECT {} FROM prompts WHERE overrides_builtin = ?1 ORDER BY updated_at DESC LIMIT 1", PROMPT_COLUMNS),
+        params![builtin],
+        map_prompt_row,
+    ).optional().map_err(Into::into)
+}
+
+pub fn get_copilot_prompts(conn: &Connection) -> Result<Vec<PromptTemplate>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM prompts WHERE show_in_copilot = TRUE ORDER BY name COLLATE NOCASE ASC", PROMPT_COLUMNS))?;
+    let prompt_iter = stmt.query_map([], map_prompt_row)?;
+    prompt_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn save_prompt(conn: &Connection, prompt: &PromptTemplate) -> Result<()> {
+    let tags_json = serde_json::to_string(&prompt.tags)?;
+    let variables_json = serde_json::to_string(&prompt.variables)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO prompts (id, name, description, content, folder, tags, variables, slash_command, show_in_copilot, overrides_builtin, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            &prompt.id,
+            &prompt.name,
+            &prompt.description,
+            &prompt.content,
+            &prompt.folder,
+            tags_json,
+            variables_json,
+            &prompt.slash_command,
+            &prompt.show_in_copilot,
+            &prompt.overrides_builtin,
+            &prompt.created_at,
+            &prompt.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_prompt(conn: &Connection, id: &str) -> Result<()> {
+    let affected = conn.execute("DELETE FROM prompts WHERE id = ?1", params![id])?;
+    if affected == 0 {
+        return Err(AppError::Database("Prompt not found for deletion".to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+// src-tauri/src/database/queries/secret_queries.rs
+use crate::database::models::*;
+use crate::error::Result;
+use rusqlite::{params, Connection};
+
+fn map_secret_row(row: &rusqlite::Row) -> rusqlite::Result<SecretInfo> {
+    Ok(SecretInfo {
+        name: row.get(0)?,
+        created_at: row.get(1)?,
+        updated_at: row.get(2)?,
+    })
+}
+
+pub fn list_secrets(conn: &Connection) -> Result<Vec<SecretInfo>> {
+    let mut stmt = conn.prepare("SELECT name, created_at, updated_at FROM secrets ORDER BY name")?;
+    let secret_iter = stmt.query_map([], map_secret_row)?;
+    secret_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn secret_exists(conn: &Connection, name: &str) -> Result<bool> {
+    Ok(conn.query_row("SELECT EXISTS(SELECT 1 FROM secrets WHERE name = ?1)", params![name], |row| row.get(0))?)
+}
+
+pub fn upsert_secret(conn: &Connection, name: &str, now: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO secrets (name, created_at, updated_at) VALUES (?1, ?2, ?2)
+         ON CONFLICT(name) DO UPDATE SET updated_at = excluded.updated_at",
+        params![name, now],
+    )?;
+    Ok(())
+}
+
+pub fn delete_secret(conn: &Connection, name: &str) -> Result<()> {
+    conn.execute("DELETE FROM secrets WHERE name = ?1", params![name])?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+// src-tauri/src/database/queries/tool_bundle_queries.rs
+use crate::database::models::*;
+use crate::error::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const TOOL_BUNDLE_COLUMNS: &str = "tool_id, hash, source, installed_at";
+
+fn map_tool_bundle_row(row: &rusqlite::Row) -> rusqlite::Result<InstalledToolBundle> {
+    Ok(InstalledToolBundle {
+        tool_id: row.get(0)?,
+        hash: row.get(1)?,
+        source: row.get(2)?,
+        installed_at: row.get(3)?,
+    })
+}
+
+pub fn list_installed_tool_bundles(conn: &Connection) -> Result<Vec<InstalledToolBundle>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM tool_bundles", TOOL_BUNDLE_COLUMNS))?;
+    let bundle_iter = stmt.query_map([], map_tool_bundle_row)?;
+    bundle_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn get_installed_tool_bundle(conn: &Connection, tool_id: &str) -> Result<Option<InstalledToolBundle>> {
+    conn.query_row(&format!("SELECT {} FROM tool_bundles WHERE tool_id = ?1", TOOL_BUNDLE_COLUMNS), params![tool_id], map_tool_bundle_row)
+        .optional()
+        .map_err(Into::into)
+}
+
+pub fn save_installed_tool_bundle(conn: &Connection, bundle: &InstalledToolBundle) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO tool_bundles (tool_id, hash, source, installed_at) VALUES (?1, ?2, ?3, ?4)",
+        params![bundle.tool_id, bundle.hash, bundle.source, bundle.installed_at],
+    )?;
+    Ok(())
+}
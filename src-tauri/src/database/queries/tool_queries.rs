@@ -2,87 +2,56 @@ use crate::database::models::*;
 use crate::error::{AppError, Result};
 use rusqlite::{params, Connection, OptionalExtension};
 
+const TOOL_COLUMNS: &str = "id, name, description, script_path, webhook_url, webhook_method, webhook_headers, webhook_body_template, input_schema, runtime, parameters, show_in_copilot, is_favorite, input_source, requires_ai_pre_processing, pre_processing_prompt, output_handling, requires_ai_post_processing, post_processing_prompt, sandbox_policy, timeout_secs, argument_mapping, interpreter_version, dependencies, webhook_auth, webhook_response_path, webhook_retries, allowed_secrets";
+
+fn map_tool_row(row: &rusqlite::Row) -> rusqlite::Result<ConfiguredTool> {
+    Ok(ConfiguredTool {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        script_path: row.get(3)?,
+        webhook_url: row.get(4)?,
+        webhook_method: row.get(5)?,
+        webhook_headers: row.get(6)?,
+        webhook_body_template: row.get(7)?,
+        input_schema: row.get(8)?,
+        runtime: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(9)?)).unwrap_or_default(),
+        parameters: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
+        show_in_copilot: row.get(11)?,
+        is_favorite: row.get(12)?,
+        input_source: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(13)?)).unwrap_or(ToolInputSource::UserInput),
+        requires_ai_pre_processing: row.get(14)?,
+        pre_processing_prompt: row.get(15)?,
+        output_handling: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(16)?)).unwrap_or(ToolOutputHandling::RawText),
+        requires_ai_post_processing: row.get(17)?,
+        post_processing_prompt: row.get(18)?,
+        sandbox: row.get::<_, Option<String>>(19)?.and_then(|s| serde_json::from_str(&s).ok()),
+        timeout_secs: row.get(20)?,
+        argument_mapping: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(21)?)).unwrap_or_default(),
+        interpreter_version: row.get(22)?,
+        dependencies: serde_json::from_str(&row.get::<_, String>(23)?).unwrap_or_default(),
+        webhook_auth: row.get::<_, Option<String>>(24)?.and_then(|s| serde_json::from_str(&s).ok()),
+        webhook_response_path: row.get(25)?,
+        webhook_retries: row.get(26)?,
+        allowed_secrets: serde_json::from_str(&row.get::<_, String>(27)?).unwrap_or_default(),
+    })
+}
+
 pub fn list_configured_tools(conn: &Connection) -> Result<Vec<ConfiguredTool>> {
-    let mut stmt = conn.prepare("SELECT id, name, description, script_path, webhook_url, webhook_method, webhook_headers, webhook_body_template, input_schema, runtime, parameters, show_in_copilot, is_favorite, input_source, requires_ai_pre_processing, pre_processing_prompt, output_handling, requires_ai_post_processing, post_processing_prompt FROM configured_tools")?;
-    let tool_iter = stmt.query_map([], |row| {
-        Ok(ConfiguredTool {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            script_path: row.get(3)?,
-            webhook_url: row.get(4)?,
-            webhook_method: row.get(5)?,
-            webhook_headers: row.get(6)?,
-            webhook_body_template: row.get(7)?,
-            input_schema: row.get(8)?,
-            runtime: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(9)?)).unwrap_or_default(),
-            parameters: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
-            show_in_copilot: row.get(11)?,
-            is_favorite: row.get(12)?,
-            input_source: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(13)?)).unwrap_or(ToolInputSource::UserInput),
-            requires_ai_pre_processing: row.get(14)?,
-            pre_processing_prompt: row.get(15)?,
-            output_handling: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(16)?)).unwrap_or(ToolOutputHandling::RawText),
-            requires_ai_post_processing: row.get(17)?,
-            post_processing_prompt: row.get(18)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM configured_tools", TOOL_COLUMNS))?;
+    let tool_iter = stmt.query_map([], map_tool_row)?;
     tool_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
 }
 
 pub fn get_copilot_tools(conn: &Connection) -> Result<Vec<ConfiguredTool>> {
-    let mut stmt = conn.prepare("SELECT id, name, description, script_path, webhook_url, webhook_method, webhook_headers, webhook_body_template, input_schema, runtime, parameters, show_in_copilot, is_favorite, input_source, requires_ai_pre_processing, pre_processing_prompt, output_handling, requires_ai_post_processing, post_processing_prompt FROM configured_tools WHERE show_in_copilot = TRUE")?;
-    let tool_iter = stmt.query_map([], |row| {
-        Ok(ConfiguredTool {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            script_path: row.get(3)?,
-            webhook_url: row.get(4)?,
-            webhook_method: row.get(5)?,
-            webhook_headers: row.get(6)?,
-            webhook_body_template: row.get(7)?,
-            input_schema: row.get(8)?,
-            runtime: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(9)?)).unwrap_or_default(),
-            parameters: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
-            show_in_copilot: row.get(11)?,
-            is_favorite: row.get(12)?,
-            input_source: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(13)?)).unwrap_or(ToolInputSource::UserInput),
-            requires_ai_pre_processing: row.get(14)?,
-            pre_processing_prompt: row.get(15)?,
-            output_handling: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(16)?)).unwrap_or(ToolOutputHandling::RawText),
-            requires_ai_post_processing: row.get(17)?,
-            post_processing_prompt: row.get(18)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM configured_tools WHERE show_in_copilot = TRUE", TOOL_COLUMNS))?;
+    let tool_iter = stmt.query_map([], map_tool_row)?;
     tool_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
 }
 
 pub fn get_configured_tool_by_id(conn: &Connection, id: &str) -> Result<Option<ConfiguredTool>> {
-    let mut stmt = conn.prepare("SELECT id, name, description, script_path, webhook_url, webhook_method, webhook_headers, webhook_body_template, input_schema, runtime, parameters, show_in_copilot, is_favorite, input_source, requires_ai_pre_processing, pre_processing_prompt, output_handling, requires_ai_post_processing, post_processing_prompt FROM configured_tools WHERE id = ?1")?;
-    let tool = stmt.query_row(params![id], |row| {
-        Ok(ConfiguredTool {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            script_path: row.get(3)?,
-            webhook_url: row.get(4)?,
-            webhook_method: row.get(5)?,
-            webhook_headers: row.get(6)?,
-            webhook_body_template: row.get(7)?,
-            input_schema: row.get(8)?,
-            runtime: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(9)?)).unwrap_or_default(),
-            parameters: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
-            show_in_copilot: row.get(11)?,
-            is_favorite: row.get(12)?,
-            input_source: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(13)?)).unwrap_or(ToolInputSource::UserInput),
-            requires_ai_pre_processing: row.get(14)?,
-            pre_processing_prompt: row.get(15)?,
-            output_handling: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(16)?)).unwrap_or(ToolOutputHandling::RawText),
-            requires_ai_post_processing: row.get(17)?,
-            post_processing_prompt: row.get(18)?,
-        })
-    }).optional()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM configured_tools WHERE id = ?1", TOOL_COLUMNS))?;
+    let tool = stmt.query_row(params![id], map_tool_row).optional()?;
     Ok(tool)
 }
 
@@ -91,9 +60,14 @@ pub fn save_configured_tool(conn: &Connection, tool: &ConfiguredTool) -> Result<
     let runtime_str = serde_json::to_string(&tool.runtime)?.trim_matches('"').to_string();
     let input_source_str = serde_json::to_string(&tool.input_source)?.trim_matches('"').to_string();
     let output_handling_str = serde_json::to_string(&tool.output_handling)?.trim_matches('"').to_string();
+    let sandbox_json = tool.sandbox.as_ref().map(serde_json::to_string).transpose()?;
+    let argument_mapping_str = serde_json::to_string(&tool.argument_mapping)?.trim_matches('"').to_string();
+    let dependencies_json = serde_json::to_string(&tool.dependencies)?;
+    let webhook_auth_json = tool.webhook_auth.as_ref().map(serde_json::to_string).transpose()?;
+    let allowed_secrets_json = serde_json::to_string(&tool.allowed_secrets)?;
 
     conn.execute(
-        "INSERT OR REPLACE INTO configured_tools (id, name, description, script_path, webhook_url, webhook_method, webhook_headers, webhook_body_template, input_schema, runtime, parameters, show_in_copilot, is_favorite, input_source, requires_ai_pre_processing, pre_processing_prompt, output_handling, requires_ai_post_processing, post_processing_prompt) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        "INSERT OR REPLACE INTO configured_tools (id, name, description, script_path, webhook_url, webhook_method, webhook_headers, webhook_body_template, input_schema, runtime, parameters, show_in_copilot, is_favorite, input_source, requires_ai_pre_processing, pre_processing_prompt, output_handling, requires_ai_post_processing, post_processing_prompt, sandbox_policy, timeout_secs, argument_mapping, interpreter_version, dependencies, webhook_auth, webhook_response_path, webhook_retries, allowed_secrets) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)",
         params![
             &tool.id,
             &tool.name,
@@ -113,7 +87,16 @@ pub fn save_configured_tool(conn: &Connection, tool: &ConfiguredTool) -> Result<
             &tool.pre_processing_prompt,
             output_handling_str,
             &tool.requires_ai_post_processing,
-            &tool.post_processing_prompt
+            &tool.post_processing_prompt,
+            sandbox_json,
+            &tool.timeout_secs,
+            argument_mapping_str,
+            &tool.interpreter_version,
+            dependencies_json,
+            webhook_auth_json,
+            &tool.webhook_response_path,
+            &tool.webhook_retries,
+            allowed_secrets_json,
         ],
     )?;
     Ok(())
@@ -124,5 +107,6 @@ pub fn delete_configured_tool(conn: &Connection, id: &str) -> Result<()> {
     if affected == 0 {
         return Err(AppError::Database("Tool not found for deletion".to_string()));
     }
+    conn.execute("DELETE FROM tool_bundles WHERE tool_id = ?1", params![id])?;
     Ok(())
 }
\ No newline at end of file
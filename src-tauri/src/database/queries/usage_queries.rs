@@ -0,0 +1,115 @@
+// src-tauri/src/database/queries/usage_queries.rs
+use crate::database::models::*;
+use crate::error::{AppError, Result};
+use rusqlite::{params, Connection};
+
+const USAGE_COLUMNS: &str = "id, timestamp, provider_id, provider_name, model, kind, purpose, prompt_tokens, completion_tokens, images, estimated, latency_ms, success, error, cost";
+
+fn map_usage_row(row: &rusqlite::Row) -> rusqlite::Result<UsageRecord> {
+    Ok(UsageRecord {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        provider_id: row.get(2)?,
+        provider_name: row.get(3)?,
+        model: row.get(4)?,
+        kind: row.get(5)?,
+        purpose: row.get(6)?,
+        prompt_tokens: row.get(7)?,
+        completion_tokens: row.get(8)?,
+        images: row.get(9)?,
+        estimated: row.get(10)?,
+        latency_ms: row.get(11)?,
+        success: row.get(12)?,
+        error: row.get(13)?,
+        cost: row.get(14)?,
+    })
+}
+
+pub fn insert_usage_record(conn: &Connection, record: &UsageRecord) -> Result<()> {
+    conn.execute(
+        &format!("INSERT INTO usage_records ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)", USAGE_COLUMNS),
+        params![
+            &record.id,
+            &record.timestamp,
+            &record.provider_id,
+            &record.provider_name,
+            &record.model,
+            &record.kind,
+            &record.purpose,
+            &record.prompt_tokens,
+            &record.completion_tokens,
+            &record.images,
+            &record.estimated,
+            &record.latency_ms,
+            &record.success,
+            &record.error,
+            &record.cost,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Lists calls between `from` and `to` (Unix millis, both optional), newest first.
+pub fn list_usage_records(conn: &Connection, from: Option<i64>, to: Option<i64>, limit: u32) -> Result<Vec<UsageRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM usage_records WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2) ORDER BY timestamp DESC LIMIT ?3",
+        USAGE_COLUMNS
+    ))?;
+    let record_iter = stmt.query_map(params![from, to, limit], map_usage_row)?;
+    record_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+/// Totals grouped by "day" (local date), "model", "provider", "feature" or "kind".
+pub fn usage_breakdown(conn: &Connection, group_by: &str, from: Option<i64>, to: Option<i64>) -> Result<Vec<UsageBreakdownRow>> {
+    let (key, order) = match group_by {
+        "day" => ("strftime('%Y-%m-%d', timestamp / 1000, 'unixepoch', 'localtime')", "key ASC"),
+        "model" => ("model", "cost DESC, calls DESC"),
+        "provider" => ("provider_name", "cost DESC, calls DESC"),
+        "feature" => ("purpose", "cost DESC, calls DESC"),
+        "kind" => ("kind", "cost DESC, calls DESC"),
+        _ => return Err(AppError::Config(format!("Unknown usage grouping: {}", group_by))),
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} AS key, COUNT(*) AS calls, SUM(CASE WHEN success THEN 0 ELSE 1 END), SUM(prompt_tokens), SUM(completion_tokens), SUM(cost) AS cost
+         FROM usage_records WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2)
+         GROUP BY key ORDER BY {}",
+        key, order
+    ))?;
+    let row_iter = stmt.query_map(params![from, to], |row| {
+        Ok(UsageBreakdownRow {
+            key: row.get(0)?,
+            calls: row.get(1)?,
+            failed_calls: row.get(2)?,
+            prompt_tokens: row.get(3)?,
+            completion_tokens: row.get(4)?,
+            cost: row.get(5)?,
+        })
+    })?;
+    row_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn total_usage_cost_since(conn: &Connection, since: i64) -> Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost), 0) FROM usage_records WHERE timestamp >= ?1",
+        params![since],
+        |row| row.get(0),
+    ).map_err(Into::into)
+}
+
+/// Records that the budget alert `level` was sent for `month`. Returns false if it already was.
+pub fn record_budget_alert(conn: &Connection, month: &str, level: &str) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO usage_budget_alerts (month, level, created_at) VALUES (?1, ?2, ?3)",
+        params![month, level, chrono::Utc::now().timestamp_millis()],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Deletes calls older than `before` (Unix millis), or all of them.
+pub fn clear_usage_records(conn: &Connection, before: Option<i64>) -> Result<usize> {
+    let deleted = conn.execute("DELETE FROM usage_records WHERE ?1 IS NULL OR timestamp < ?1", params![before])?;
+    if before.is_none() {
+        conn.execute("DELETE FROM usage_budget_alerts", [])?;
+    }
+    Ok(deleted)
+}
@@ -0,0 +1,71 @@
+// src-tauri/src/database/queries/user_memory_queries.rs
+use crate::database::models::*;
+use crate::error::{AppError, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const USER_MEMORY_COLUMNS: &str = "id, content, category, status, source_conversation_id, created_at, updated_at";
+
+fn map_user_memory_row(row: &rusqlite::Row) -> rusqlite::Result<UserMemory> {
+    Ok(UserMemory {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        category: row.get(2)?,
+        status: row.get(3)?,
+        source_conversation_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Lists memories, optionally only those with the given status ("active" or "proposed").
+pub fn list_user_memories(conn: &Connection, status: Option<&str>) -> Result<Vec<UserMemory>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM user_memories WHERE ?1 IS NULL OR status = ?1 ORDER BY updated_at DESC",
+        USER_MEMORY_COLUMNS
+    ))?;
+    let memory_iter = stmt.query_map(params![status], map_user_memory_row)?;
+    memory_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn get_user_memory_by_id(conn: &Connection, id: &str) -> Result<Option<UserMemory>> {
+    conn.query_row(
+        &format!("SELECT {} FROM user_memories WHERE id = ?1", USER_MEMORY_COLUMNS),
+        params![id],
+        map_user_memory_row,
+    ).optional().map_err(Into::into)
+}
+
+pub fn save_user_memory(conn: &Connection, memory: &UserMemory) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO user_memories (id, content, category, status, source_conversation_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            &memory.id,
+            &memory.content,
+            &memory.category,
+            &memory.status,
+            &memory.source_conversation_id,
+            &memory.created_at,
+            &memory.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn set_user_memory_status(conn: &Connection, id: &str, status: &str) -> Result<()> {
+    let affected = conn.execute(
+        "UPDATE user_memories SET status = ?2, updated_at = ?3 WHERE id = ?1",
+        params![id, status, chrono::Utc::now().timestamp_millis()],
+    )?;
+    if affected == 0 {
+        return Err(AppError::Database("Memory not found".to_string()));
+    }
+    Ok(())
+}
+
+pub fn delete_user_memory(conn: &Connection, id: &str) -> Result<()> {
+    let affected = conn.execute("DELETE FROM user_memories WHERE id = ?1", params![id])?;
+    if affected == 0 {
+        return Err(AppError::Database("Memory not found for deletion".to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+// src-tauri/src/database/queries/workflow_queries.rs
+use crate::database::models::*;
+use crate::error::{AppError, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const WORKFLOW_COLUMNS: &str = "id, name, description, steps, show_in_copilot, created_at, updated_at";
+const WORKFLOW_RUN_COLUMNS: &str = "id, workflow_id, started_at, status, input, steps, output, error, duration_ms, source";
+
+fn map_workflow_row(row: &rusqlite::Row) -> rusqlite::Result<Workflow> {
+    Ok(Workflow {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        steps: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+        show_in_copilot: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn map_workflow_run_row(row: &rusqlite::Row) -> rusqlite::Result<WorkflowRun> {
+    Ok(WorkflowRun {
+        id: row.get(0)?,
+        workflow_id: row.get(1)?,
+        started_at: row.get(2)?,
+        status: serde_json::from_str(&format!("\"{}\"", row.get::<_, String>(3)?)).unwrap_or(WorkflowRunStatus::Failed),
+        input: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+        steps: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+        output: row.get(6)?,
+        error: row.get(7)?,
+        duration_ms: row.get(8)?,
+        source: row.get(9)?,
+    })
+}
+
+pub fn list_workflows(conn: &Connection) -> Result<Vec<Workflow>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM workflows ORDER BY name COLLATE NOCASE ASC", WORKFLOW_COLUMNS))?;
+    let workflow_iter = stmt.query_map([], map_workflow_row)?;
+    workflow_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn get_copilot_workflows(conn: &Connection) -> Result<Vec<Workflow>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM workflows WHERE show_in_copilot = TRUE ORDER BY name COLLATE NOCASE ASC", WORKFLOW_COLUMNS))?;
+    let workflow_iter = stmt.query_map([], map_workflow_row)?;
+    workflow_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+pub fn get_workflow_by_id(conn: &Connection, id: &str) -> Result<Option<Workflow>> {
+    conn.query_row(
+        &format!("SELECT {} FROM workflows WHERE id = ?1", WORKFLOW_COLUMNS),
+        params![id],
+        map_workflow_row,
+    ).optional().map_err(Into::into)
+}
+
+pub fn save_workflow(conn: &Connection, workflow: &Workflow) -> Result<()> {
+    let steps_json = serde_json::to_string(&workflow.steps)?;
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO workflows ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", WORKFLOW_COLUMNS),
+        params![
+            &workflow.id,
+            &workflow.name,
+            &workflow.description,
+            steps_json,
+            &workflow.show_in_copilot,
+            &workflow.created_at,
+            &workflow.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Deletes the workflow along with its run history.
+pub fn delete_workflow(conn: &Connection, id: &str) -> Result<()> {
+    let affected = conn.execute("DELETE FROM workflows WHERE id = ?1", params![id])?;
+    if affected == 0 {
+        return Err(AppError::Database("Workflow not found for deletion".to_string()));
+    }
+    conn.execute("DELETE FROM workflow_runs WHERE workflow_id = ?1", params![id])?;
+    Ok(())
+}
+
+pub fn insert_workflow_run(conn: &Connection, run: &WorkflowRun) -> Result<()> {
+    let status_str = serde_json::to_string(&run.status)?.trim_matches('"').to_string();
+    let input_json = serde_json::to_string(&run.input)?;
+    let steps_json = serde_json::to_string(&run.steps)?;
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO workflow_runs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)", WORKFLOW_RUN_COLUMNS),
+        params![
+            &run.id,
+            &run.workflow_id,
+            &run.started_at,
+            status_str,
+            input_json,
+            steps_json,
+            &run.output,
+            &run.error,
+            &run.duration_ms,
+            &run.source,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_workflow_run(conn: &Connection, id: &str) -> Result<Option<WorkflowRun>> {
+    conn.query_row(
+        &format!("SELECT {} FROM workflow_runs WHERE id = ?1", WORKFLOW_RUN_COLUMNS),
+        params![id],
+        map_workflow_run_row,
+    ).optional().map_err(Into::into)
+}
+
+/// Lists runs newest first, of one workflow or of all.
+pub fn list_workflow_runs(conn: &Connection, workflow_id: Option<&str>, limit: u32) -> Result<Vec<WorkflowRun>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workflow_runs WHERE ?1 IS NULL OR workflow_id = ?1 ORDER BY started_at DESC LIMIT ?2",
+        WORKFLOW_RUN_COLUMNS
+    ))?;
+    let run_iter = stmt.query_map(params![workflow_id, limit], map_workflow_run_row)?;
+    run_iter.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+}
+
+/// Deletes runs started before `before` (Unix millis) and all but the newest `keep`.
+pub fn prune_workflow_runs(conn: &Connection, before: Option<i64>, keep: Option<u32>) -> Result<usize> {
+    let mut deleted = 0;
+    if let Some(before) = before {
+        deleted += conn.execute("DELETE FROM workflow_runs WHERE started_at < ?1", params![before])?;
+    }
+    if let Some(keep) = keep {
+        deleted += conn.execute(
+            "DELETE FROM workflow_runs WHERE id NOT IN (SELECT id FROM workflow_runs ORDER BY started_at DESC LIMIT ?1)",
+            params![keep],
+        )?;
+    }
+    Ok(deleted)
+}
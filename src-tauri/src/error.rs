@@ -20,6 +20,27 @@ pub enum AppError {
     Config(String),
     #[error("Internal error: {0}")]
     Internal(String),
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("Invalid parameters: {}", describe_fields(.0))]
+    InvalidParams(Vec<FieldError>),
+}
+
+/// A value that failed validation. `field` is its JSON pointer, e.g. "/limit"; "" for the whole input.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+fn describe_fields(errors: &[FieldError]) -> String {
+    errors.iter()
+        .map(|e| if e.field.is_empty() { e.message.clone() } else { format!("{}: {}", e.field, e.message) })
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 impl From<rusqlite::Error> for AppError {
@@ -61,6 +82,12 @@ impl From<tauri::Error> for AppError {
     }
 }
 
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::Internal(format!("Background task failed: {}", e))
+    }
+}
+
 impl From<CannotCloneRequestError> for AppError {
     fn from(e: CannotCloneRequestError) -> Self {
         AppError::ApiClient(format!("Failed to build streaming request: {}", e))
@@ -7,7 +7,9 @@ use std::path::Path;
 pub fn parse_file(path: &Path) -> Result<String> {
     let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
     match extension.to_lowercase().as_str() {
-        "txt" | "md" | "rs" | "js" | "ts" | "py" | "html" | "css" => {
+        "txt" | "md" | "rs" | "js" | "ts" | "py" | "html" | "css"
+        | "jsx" | "tsx" | "go" | "java" | "kt" | "c" | "h" | "cpp" | "hpp" | "cs" | "rb" | "php" | "swift" | "sh" | "sql"
+        | "csv" | "tsv" | "json" | "yaml" | "yml" | "toml" | "xml" | "log" => {
             std::fs::read_to_string(path).map_err(|e| e.into())
         }
         "pdf" => {
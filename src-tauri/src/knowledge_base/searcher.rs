@@ -4,7 +4,10 @@ use crate::{
     database::{models, queries},
     error::{AppError, Result},
     services::{
+        chat::context,
+        failover::{self, AttemptError},
         proxy_types::{EmbeddingResponse, ProxyEmbeddingPayload},
+        usage::{CallUsage, UsageCall},
         vector_client,
     },
     state::AppState,
@@ -13,18 +16,36 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::path::Path;
 
-async fn get_embeddings_from_proxy(state: &AppState, provider_config: &models::ApiProvider, model_name: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+/// Embeds `texts`; `purpose` names the feature in the usage log. Transient errors are retried,
+/// but there is no fallback to other models: their vectors wouldn't match the stored ones.
+pub async fn get_embeddings_from_proxy(state: &AppState, provider_config: &models::ApiProvider, model_name: &str, texts: &[String], purpose: &str) -> Result<Vec<Vec<f32>>> {
     if texts.is_empty() { return Ok(vec![]); }
-    let backend_url = {
+    let (backend_url, failover_settings) = {
         let conn = state.db.lock().unwrap();
-        queries::get_settings(&conn)?.execution.backend_url
+        let settings = queries::get_settings(&conn)?;
+        (settings.execution.backend_url, settings.failover)
     };
     let url = format!("{}/api/v1/proxy/embeddings", backend_url);
-    
-    let payload = ProxyEmbeddingPayload { model: model_name, input: texts, provider_config };
+    let prompt_tokens = texts.iter().map(|t| context::estimate_tokens(t)).sum::<u32>();
+
+    let candidates = vec![(provider_config.clone(), model_name.to_string())];
+    let (mut response_data, _, _) = failover::run(&failover_settings, candidates, |provider, model| {
+        let url = &url;
+        async move {
+            let payload = ProxyEmbeddingPayload { model: &model, input: texts, provider_config: &provider };
+            let call = UsageCall::start(&provider, &model, "embedding", purpose);
+            let result: std::result::Result<EmbeddingResponse, AttemptError> = async {
+                let response = state.http_client.post(url).json(&payload).send().await?.error_for_status()?;
+                Ok(response.json().await?)
+            }.await;
+            match &result {
+                Ok(response_data) => call.finish(state, CallUsage::reported_or(response_data.usage.as_ref(), || (prompt_tokens, 0)), None),
+                Err(e) => call.finish(state, CallUsage::estimated(prompt_tokens, 0), Some(e.message.clone())),
+            }
+            result
+        }
+    }).await?;
 
-    let response = state.http_client.post(url).json(&payload).send().await?.error_for_status()?;
-    let mut response_data: EmbeddingResponse = response.json().await?;
     response_data.data.sort_by_key(|d| d.index);
     Ok(response_data.data.into_iter().map(|d| d.embedding).collect())
 }
@@ -45,7 +66,7 @@ pub async fn search(
         (provider, embedding_endpoint.model_name, settings.execution.backend_url)
     };
 
-    let query_embedding = get_embeddings_from_proxy(state, &provider_config, &model_name, &[query])
+    let query_embedding = get_embeddings_from_proxy(state, &provider_config, &model_name, &[query], "kb_search")
         .await?
         .pop()
         .ok_or_else(|| AppError::Internal("Failed to generate query embedding".to_string()))?;
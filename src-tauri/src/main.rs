@@ -159,6 +159,16 @@ fn main() {
             services::shortcuts::update_global_shortcuts(&handle, &settings)
                 .map_err(|e| anyhow::anyhow!("Failed to initialize shortcuts: {}", e))?;
 
+            if settings.api_server.enabled {
+                let handle_clone = handle.clone();
+                let state_clone = app_state.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = services::api_server::start(handle_clone, state_clone).await {
+                        log::error!("Failed to start local API server: {}", e);
+                    }
+                });
+            }
+
             if settings.execution.auto_start_backend {
                 let handle_clone = handle.clone();
                 let state_clone = app_state.clone();
@@ -215,6 +225,19 @@ fn main() {
             commands::chat::create_conversation,
             commands::chat::process_chat_message,
             commands::chat::stop_chat_generation,
+            commands::chat::retry_message,
+            commands::chat::process_chat_compare,
+            commands::chat::select_compare_answer,
+            commands::chat::regenerate_message,
+            commands::chat::edit_user_message,
+            commands::chat::switch_branch,
+            commands::chat::set_message_pinned,
+            commands::chat::get_context_usage,
+            commands::chat::clear_context_summary,
+            commands::chat::search_conversations,
+            commands::chat::set_conversation_memory_excluded,
+            commands::chat::forget_conversation_memory,
+            commands::chat::clear_conversation_memory,
             commands::chat::save_message,
             commands::chat::link_agent_task_to_message,
             commands::chat::delete_message,
@@ -236,14 +259,32 @@ fn main() {
             commands::agent::refine_agent_task_section,
             commands::intent::get_intent_suggestions,
             commands::tools::list_tools,
+            commands::tools::lint_tool_scripts,
             commands::tools::execute_tool,
+            commands::tools::validate_tool_params,
+            commands::tools::get_tool_input_schema,
+            commands::tools::list_tool_environments,
+            commands::tools::delete_tool_environment,
+            commands::tools::export_tool_bundle,
+            commands::tools::preview_tool_bundle,
+            commands::tools::import_tool_bundle,
+            commands::tools::create_tool_signing_key,
+            commands::tools::get_tool_signing_public_key,
+            commands::tools::list_available_tool_bundles,
+            commands::tools::sync_tool_repositories,
             commands::tools::list_configured_tools,
             commands::tools::save_configured_tool,
             commands::tools::delete_configured_tool,
             commands::tools::execute_python_code,
             commands::tools::save_tool_script,
             commands::tools::execute_shell_command,
+            commands::tools::get_sandbox_status,
             commands::tools::execute_generic_code,
+            commands::tools::cancel_execution,
+            commands::tools::list_execution_history,
+            commands::tools::get_execution_record,
+            commands::tools::rerun_execution,
+            commands::tools::clear_execution_history,
             commands::tools::setup_task_workspace,
             commands::tools::write_file_to_task_dir,
             commands::assets::save_temp_asset_with_hash,
@@ -262,7 +303,46 @@ fn main() {
             commands::execution::check_backend_status,
             commands::execution::install_backend_service,
             commands::execution::start_backend_service,
-            commands::execution::stop_backend_service
+            commands::execution::stop_backend_service,
+            commands::api_server::get_api_server_status,
+            commands::api_server::start_api_server,
+            commands::api_server::stop_api_server,
+            commands::api_server::regenerate_api_server_key,
+            commands::personas::list_personas,
+            commands::personas::save_persona,
+            commands::personas::delete_persona,
+            commands::personas::set_conversation_persona,
+            commands::personas::preview_prompt_template,
+            commands::prompts::list_prompts,
+            commands::prompts::save_prompt,
+            commands::prompts::delete_prompt,
+            commands::prompts::render_prompt,
+            commands::prompts::export_prompts,
+            commands::prompts::import_prompts,
+            commands::memory::list_user_memories,
+            commands::memory::save_user_memory,
+            commands::memory::accept_user_memory,
+            commands::memory::delete_user_memory,
+            commands::backup::export_conversation,
+            commands::backup::export_all_conversations,
+            commands::backup::import_chat_history,
+            commands::workflows::list_workflows,
+            commands::workflows::get_workflow,
+            commands::workflows::save_workflow,
+            commands::workflows::delete_workflow,
+            commands::workflows::run_workflow,
+            commands::workflows::list_workflow_runs,
+            commands::workflows::get_workflow_run,
+            commands::secrets::list_secrets,
+            commands::secrets::set_secret,
+            commands::secrets::delete_secret,
+            commands::usage::get_usage_breakdown,
+            commands::usage::list_usage_records,
+            commands::usage::get_usage_budget_status,
+            commands::usage::clear_usage_records,
+            commands::attachments::add_attachment,
+            commands::attachments::add_attachment_data,
+            commands::attachments::transcribe_attachment
         ])
         .run(tauri::generate_context!());
 
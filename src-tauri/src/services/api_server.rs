@@ -0,0 +1,529 @@
+// src-tauri/src/services/api_server.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    knowledge_base,
+    services::{
+        chat::{context, llm_utils},
+        proxy_types::{ProxyChatPayload, ProxyEmbeddingPayload, ProxyMessage, ProxyStreamChunk, ProxyUsage, SamplingParams},
+        tools,
+        usage::{self, CallUsage, UsageCall},
+    },
+    state::AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tauri::AppHandle;
+use futures_util::StreamExt;
+use tokio::sync::{oneshot, Mutex};
+use uuid::Uuid;
+
+struct ServerHandle {
+    address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+static API_SERVER: Lazy<Arc<Mutex<Option<ServerHandle>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+
+#[derive(Clone)]
+struct ServerContext {
+    app: AppHandle,
+    state: AppState,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+}
+
+/// An error rendered in the OpenAI `{"error": {...}}` shape so that existing clients surface it properly.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({ "error": { "message": self.1, "type": "nexus_error", "code": self.0.as_u16() } });
+        (self.0, Json(body)).into_response()
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(e: AppError) -> Self {
+        let status = match e {
+            AppError::Config(_) | AppError::Parse(_) | AppError::InvalidParams(_) => StatusCode::BAD_REQUEST,
+            AppError::ApiClient(_) | AppError::VectorService(_) => StatusCode::BAD_GATEWAY,
+            AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::from(e).into()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+pub fn generate_api_key() -> String {
+    format!("nxs-{}", Uuid::new_v4().simple())
+}
+
+pub async fn status() -> ApiServerStatus {
+    let guard = API_SERVER.lock().await;
+    ApiServerStatus {
+        running: guard.is_some(),
+        address: guard.as_ref().map(|h| format!("http://{}", h.address)),
+    }
+}
+
+pub async fn start(app: AppHandle, state: AppState) -> Result<ApiServerStatus> {
+    let mut guard = API_SERVER.lock().await;
+    if let Some(handle) = guard.as_ref() {
+        log::info!("[ApiServer] Already running on {}", handle.address);
+        return Ok(ApiServerStatus { running: true, address: Some(format!("http://{}", handle.address)) });
+    }
+
+    let server_settings = {
+        let conn = state.db.lock().unwrap();
+        let mut settings = queries::get_settings(&conn)?;
+        if settings.api_server.api_key.is_empty() {
+            settings.api_server.api_key = generate_api_key();
+            queries::save_settings(&conn, &settings)?;
+            log::info!("[ApiServer] Generated a new API key for the local API server.");
+        }
+        settings.api_server
+    };
+
+    let bind_addr = format!("{}:{}", server_settings.host, server_settings.port);
+    let listener = tokio::net::TcpListener::bind(&bind_addr)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to bind local API server to {}: {}", bind_addr, e)))?;
+    let address = listener.local_addr()?;
+
+    let context = ServerContext { app, state };
+    let router = Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/embeddings", post(embeddings))
+        .route("/v1/kb/search", post(kb_search))
+        .route("/v1/tools", get(list_tools))
+        .route("/v1/tools/execute", post(execute_tool))
+        .layer(middleware::from_fn_with_state(context.clone(), require_api_key))
+        .with_state(context);
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        });
+        if let Err(e) = server.await {
+            log::error!("[ApiServer] Server terminated with error: {}", e);
+        }
+        log::info!("[ApiServer] Server stopped.");
+    });
+
+    log::info!("[ApiServer] Local API server listening on http://{}", address);
+    *guard = Some(ServerHandle { address, shutdown: shutdown_tx });
+    Ok(ApiServerStatus { running: true, address: Some(format!("http://{}", address)) })
+}
+
+pub async fn stop() -> Result<()> {
+    let mut guard = API_SERVER.lock().await;
+    if let Some(handle) = guard.take() {
+        handle.shutdown.send(()).ok();
+        log::info!("[ApiServer] Shutdown requested for server on {}", handle.address);
+    }
+    Ok(())
+}
+
+async fn require_api_key(State(ctx): State<ServerContext>, headers: HeaderMap, request: Request, next: Next) -> Response {
+    let settings_result = queries::get_settings(&ctx.state.db.lock().unwrap());
+    let expected_key = match settings_result {
+        Ok(settings) => settings.api_server.api_key,
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+
+    if is_authorized(&headers, &expected_key) {
+        next.run(request).await
+    } else {
+        ApiError(StatusCode::UNAUTHORIZED, "Invalid or missing API key.".to_string()).into_response()
+    }
+}
+
+/// Whether the request carries `Authorization: Bearer <key>`. An empty key authorizes nothing.
+fn is_authorized(headers: &HeaderMap, expected_key: &str) -> bool {
+    let provided_key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    match provided_key {
+        Some(key) => !expected_key.is_empty() && bool::from(key.as_bytes().ct_eq(expected_key.as_bytes())),
+        None => false,
+    }
+}
+
+/// Resolves an OpenAI-style `model` field into a provider and model name.
+/// Accepts `providerId::modelName`, a bare model name offered by any provider,
+/// or nothing at all, in which case the given assignment is used.
+fn resolve_model(
+    api_config: &models::ApiConfig,
+    requested: Option<&str>,
+    fallback: Option<&models::ModelEndpoint>,
+) -> Result<(models::ApiProvider, String)> {
+    let (provider_id, model_name) = match requested.filter(|m| !m.is_empty() && *m != "default") {
+        Some(model) => match model.split_once("::") {
+            Some((provider_id, model_name)) => (provider_id.to_string(), model_name.to_string()),
+            None => {
+                let provider = api_config.providers.iter()
+                    .find(|p| p.models.iter().any(|m| m.name == model))
+                    .ok_or_else(|| AppError::Config(format!("No provider offers model '{}'", model)))?;
+                (provider.id.clone(), model.to_string())
+            }
+        },
+        None => {
+            let endpoint = fallback.ok_or_else(|| AppError::Config("No model specified and no default model assigned".to_string()))?;
+            (endpoint.provider_id.clone(), endpoint.model_name.clone())
+        }
+    };
+
+    let provider = api_config.providers.iter()
+        .find(|p| p.id == provider_id)
+        .cloned()
+        .ok_or_else(|| AppError::Config(format!("Provider with ID {} not found", provider_id)))?;
+    Ok((provider, model_name))
+}
+
+async fn list_models(State(ctx): State<ServerContext>) -> ApiResult<Json<Value>> {
+    let settings = queries::get_settings(&ctx.state.db.lock().unwrap())?;
+    let data: Vec<Value> = settings.api_config.providers.iter()
+        .flat_map(|p| p.models.iter().map(move |m| json!({
+            "id": format!("{}::{}", p.id, m.name),
+            "object": "model",
+            "owned_by": p.name,
+            "capabilities": m.capabilities,
+        })))
+        .collect();
+    Ok(Json(json!({ "object": "list", "data": data })))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OpenAiContent {
+    Text(String),
+    Parts(Vec<models::ChatMessageContentPart>),
+}
+
+#[derive(Deserialize)]
+struct OpenAiMessage {
+    role: String,
+    content: OpenAiContent,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionRequest {
+    model: Option<String>,
+    messages: Vec<OpenAiMessage>,
+    #[serde(default)]
+    stream: bool,
+    /// Nexus extension: a KB selection (`all`, a directory prefix, or `online::<id>`) that triggers RAG.
+    #[serde(default)]
+    knowledge_base_selection: Option<String>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    top_p: Option<f32>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    stop: Option<StopSequences>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StopSequences {
+    Single(String),
+    Many(Vec<String>),
+}
+
+async fn chat_completions(State(ctx): State<ServerContext>, Json(request): Json<ChatCompletionRequest>) -> ApiResult<Response> {
+    let (api_config, backend_url) = {
+        let conn = ctx.state.db.lock().unwrap();
+        let settings = queries::get_settings(&conn)?;
+        let mut api_config = settings.api_config;
+        api_config.online_kbs = Some(queries::list_online_kbs(&conn)?);
+        (api_config, settings.execution.backend_url)
+    };
+    let (provider, model_name) = resolve_model(&api_config, request.model.as_deref(), api_config.assignments.chat.as_ref())?;
+
+    let contents: Vec<(String, Vec<models::ChatMessageContentPart>)> = request.messages.into_iter()
+        .map(|m| {
+            let parts = match m.content {
+                OpenAiContent::Text(text) => vec![models::ChatMessageContentPart::Text { text }],
+                OpenAiContent::Parts(parts) => parts,
+            };
+            (m.role, parts)
+        })
+        .collect();
+    let messages: Vec<ProxyMessage> = contents.iter()
+        .map(|(role, content)| ProxyMessage { role: role.clone(), content })
+        .collect();
+
+    let request_body = ProxyChatPayload {
+        model: &model_name,
+        messages,
+        stream: request.stream,
+        provider_config: &provider,
+        knowledge_base_selection: request.knowledge_base_selection.filter(|s| !s.is_empty() && s != "none"),
+        api_config: Some(&api_config),
+        sampling: SamplingParams {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            max_tokens: request.max_tokens,
+            stop: match request.stop {
+                Some(StopSequences::Single(stop)) => vec![stop],
+                Some(StopSequences::Many(stops)) => stops,
+                None => vec![],
+            },
+        },
+    };
+
+    let url = llm_utils::chat_completions_url(&backend_url);
+    let prompt_tokens = contents.iter().map(|(_, content)| context::estimate_content_tokens(content)).sum::<u32>();
+    let call = UsageCall::start(&provider, &model_name, "chat", "api_server");
+    let response = match ctx.state.http_client.post(url).json(&request_body).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            call.finish(&ctx.state, CallUsage::estimated(prompt_tokens, 0), Some(e.to_string()));
+            return Err(e.into());
+        }
+    };
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown backend error".to_string());
+        call.finish(&ctx.state, CallUsage::estimated(prompt_tokens, 0), Some(error_text.clone()));
+        return Err(ApiError(status, format!("Backend proxy failed: {}", error_text)));
+    }
+
+    if request.stream {
+        // Relay the proxy's SSE stream untouched; RAG sources arrive as a named `sources` event.
+        let mut tap = StreamUsageTap {
+            app: ctx.app.clone(),
+            state: ctx.state.clone(),
+            call: Some(call),
+            prompt_tokens,
+            pending: Vec::new(),
+            completion: String::new(),
+            reported: None,
+            error: None,
+        };
+        let stream = response.bytes_stream().map(move |chunk| {
+            tap.observe(&chunk);
+            chunk
+        });
+        let body = Body::from_stream(stream);
+        return Ok(([(header::CONTENT_TYPE, "text/event-stream"), (header::CACHE_CONTROL, "no-cache")], body).into_response());
+    }
+
+    let response_data: Value = response.json().await?;
+    let reported = response_data.get("usage").and_then(|u| serde_json::from_value::<ProxyUsage>(u.clone()).ok());
+    let completion_tokens = response_data.pointer("/choices/0/message/content").and_then(Value::as_str).map_or(0, context::estimate_tokens);
+    call.finish(&ctx.state, CallUsage::reported_or(reported.as_ref(), || (prompt_tokens, completion_tokens)), None);
+    if let Err(e) = usage::check_budget(&ctx.app, &ctx.state) {
+        log::warn!("[ApiServer] Failed to check the usage budget: {}", e);
+    }
+    Ok(Json(response_data).into_response())
+}
+
+/// Follows a relayed SSE stream to count its tokens. The call is recorded when the stream is
+/// dropped, i.e. when it ends or the client disconnects.
+struct StreamUsageTap {
+    app: AppHandle,
+    state: AppState,
+    call: Option<UsageCall>,
+    prompt_tokens: u32,
+    pending: Vec<u8>,
+    completion: String,
+    reported: Option<ProxyUsage>,
+    error: Option<String>,
+}
+
+impl StreamUsageTap {
+    fn observe(&mut self, chunk: &reqwest::Result<bytes::Bytes>) {
+        let bytes = match chunk {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        self.pending.extend_from_slice(bytes);
+        while let Some(pos) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else { continue };
+            let Ok(parsed) = serde_json::from_str::<ProxyStreamChunk>(data.trim()) else { continue };
+            for choice in &parsed.choices {
+                self.completion.extend(choice.delta.reasoning_content.as_deref());
+                self.completion.extend(choice.delta.content.as_deref());
+            }
+            if parsed.usage.is_some() {
+                self.reported = parsed.usage;
+            }
+        }
+    }
+}
+
+impl Drop for StreamUsageTap {
+    fn drop(&mut self) {
+        let Some(call) = self.call.take() else { return };
+        let (prompt_tokens, completion_tokens) = (self.prompt_tokens, context::estimate_tokens(&self.completion));
+        call.finish(&self.state, CallUsage::reported_or(self.reported.as_ref(), || (prompt_tokens, completion_tokens)), self.error.take());
+        if let Err(e) = usage::check_budget(&self.app, &self.state) {
+            log::warn!("[ApiServer] Failed to check the usage budget: {}", e);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EmbeddingInput {
+    Single(String),
+    Batch(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct EmbeddingRequest {
+    model: Option<String>,
+    input: EmbeddingInput,
+}
+
+async fn embeddings(State(ctx): State<ServerContext>, Json(request): Json<EmbeddingRequest>) -> ApiResult<Json<Value>> {
+    let settings = queries::get_settings(&ctx.state.db.lock().unwrap())?;
+    let (provider, model_name) = resolve_model(&settings.api_config, request.model.as_deref(), settings.api_config.assignments.embedding.as_ref())?;
+
+    let input = match request.input {
+        EmbeddingInput::Single(text) => vec![text],
+        EmbeddingInput::Batch(texts) => texts,
+    };
+    let payload = ProxyEmbeddingPayload { model: &model_name, input: &input, provider_config: &provider };
+
+    let url = format!("{}/api/v1/proxy/embeddings", settings.execution.backend_url);
+    let prompt_tokens = input.iter().map(|t| context::estimate_tokens(t)).sum::<u32>();
+    let call = UsageCall::start(&provider, &model_name, "embedding", "api_server");
+    let result: Result<Value> = async {
+        let response = ctx.state.http_client.post(url).json(&payload).send().await?.error_for_status()?;
+        Ok(response.json().await?)
+    }.await;
+    let response_data = match result {
+        Ok(response_data) => response_data,
+        Err(e) => {
+            call.finish(&ctx.state, CallUsage::estimated(prompt_tokens, 0), Some(e.to_string()));
+            return Err(e.into());
+        }
+    };
+    let reported = response_data.get("usage").and_then(|u| serde_json::from_value::<ProxyUsage>(u.clone()).ok());
+    call.finish(&ctx.state, CallUsage::reported_or(reported.as_ref(), || (prompt_tokens, 0)), None);
+    if let Err(e) = usage::check_budget(&ctx.app, &ctx.state) {
+        log::warn!("[ApiServer] Failed to check the usage budget: {}", e);
+    }
+    Ok(Json(response_data))
+}
+
+#[derive(Deserialize)]
+struct KbSearchRequest {
+    query: String,
+    #[serde(default)]
+    knowledge_base_selection: Option<String>,
+    top_k: Option<u32>,
+    score_threshold: Option<f32>,
+}
+
+async fn kb_search(State(ctx): State<ServerContext>, Json(request): Json<KbSearchRequest>) -> ApiResult<Json<Value>> {
+    let kb_settings = queries::get_settings(&ctx.state.db.lock().unwrap())?.knowledge_base;
+    let top_k = request.top_k.unwrap_or(kb_settings.top_k);
+    let score_threshold = request.score_threshold.unwrap_or(kb_settings.score_threshold);
+    let selection = request.knowledge_base_selection.unwrap_or_else(|| "all".to_string());
+
+    let sources = if let Some(online_kb_id) = selection.strip_prefix("online::") {
+        knowledge_base::searcher::search_online(&ctx.state, online_kb_id, &request.query, top_k, score_threshold).await?
+    } else {
+        let where_filter = if selection == "all" { None } else { Some(json!({ "file_path": { "$like": format!("{}%", selection) } })) };
+        knowledge_base::searcher::search(&ctx.state, request.query, where_filter, top_k, score_threshold).await?
+    };
+
+    Ok(Json(json!({ "object": "list", "data": sources })))
+}
+
+async fn list_tools(State(ctx): State<ServerContext>) -> ApiResult<Json<Value>> {
+    let configured = queries::list_configured_tools(&ctx.state.db.lock().unwrap())?;
+    let dynamic = tools::list_dynamic_tools(&ctx.state).await?;
+
+    let data: Vec<Value> = configured.iter()
+        .map(|t| json!({ "id": t.id, "name": t.name, "description": t.description, "parameters": t.parameters, "input_schema": tools::params::input_schema(t).ok().flatten() }))
+        .chain(dynamic.iter().map(|t| {
+            let schema = tools::params::input_schema(&tools::header::to_configured_tool(t)).ok().flatten();
+            json!({ "id": t.id, "name": t.name, "description": t.description, "parameters": t.parameters, "input_schema": schema })
+        }))
+        .collect();
+    Ok(Json(json!({ "object": "list", "data": data })))
+}
+
+#[derive(Deserialize)]
+struct ToolExecutionRequest {
+    tool_id: String,
+    #[serde(default)]
+    params: Value,
+}
+
+async fn execute_tool(State(ctx): State<ServerContext>, Json(request): Json<ToolExecutionRequest>) -> ApiResult<Json<Value>> {
+    let task_id = Uuid::new_v4().to_string();
+    log::info!("[ApiServer] Executing tool {} via local API (task {})", request.tool_id, task_id);
+    let output = tools::execute(&ctx.state, &request.tool_id, request.params, &task_id, &ctx.app, "api_server").await?;
+    Ok(Json(json!({ "task_id": task_id, "tool_id": request.tool_id, "output": output })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bearer(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn accepts_only_the_configured_key() {
+        assert!(is_authorized(&bearer("Bearer nxs-secret"), "nxs-secret"));
+        assert!(is_authorized(&bearer("Bearer  nxs-secret "), "nxs-secret"));
+        assert!(!is_authorized(&bearer("Bearer nxs-other"), "nxs-secret"));
+        assert!(!is_authorized(&bearer("Bearer nxs-secret-longer"), "nxs-secret"));
+        assert!(!is_authorized(&bearer("nxs-secret"), "nxs-secret"));
+        assert!(!is_authorized(&HeaderMap::new(), "nxs-secret"));
+    }
+
+    #[test]
+    fn an_empty_key_authorizes_nothing() {
+        assert!(!is_authorized(&bearer("Bearer "), ""));
+        assert!(!is_authorized(&HeaderMap::new(), ""));
+    }
+}
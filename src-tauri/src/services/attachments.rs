@@ -0,0 +1,185 @@
+// src-tauri/src/services/attachments.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    knowledge_base::parser,
+    services::{
+        chat::context,
+        failover::{self, AttemptError},
+        proxy_types::{ProxyTranscriptionPayload, TranscriptionResponse},
+        usage::{CallUsage, UsageCall},
+    },
+    state::AppState,
+};
+use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Documents longer than this are cut before they go into the prompt.
+const MAX_ATTACHMENT_CHARS: usize = 60_000;
+
+fn extension(name: &str) -> Option<String> {
+    Path::new(name).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase())
+}
+
+/// MIME type of a file. Images are recognised by their content, so a PNG saved as `.webp`
+/// is still sent as PNG; everything else goes by extension.
+pub fn detect_mime(name: &str, bytes: &[u8]) -> String {
+    if let Ok(format) = image::guess_format(bytes) {
+        return format.to_mime_type().to_string();
+    }
+    let mime = match extension(name).as_deref() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("bmp") => "image/bmp",
+        Some("pdf") => "application/pdf",
+        Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some("csv") => "text/csv",
+        Some("tsv") => "text/tab-separated-values",
+        Some("json") => "application/json",
+        Some("md") => "text/markdown",
+        Some("html") => "text/html",
+        Some("xml") => "application/xml",
+        Some("mp3") => "audio/mpeg",
+        Some("wav") => "audio/wav",
+        Some("m4a") => "audio/mp4",
+        Some("ogg") | Some("oga") => "audio/ogg",
+        Some("flac") => "audio/flac",
+        Some("webm") => "audio/webm",
+        Some(_) if std::str::from_utf8(bytes).is_ok() => "text/plain",
+        _ => "application/octet-stream",
+    };
+    mime.to_string()
+}
+
+/// Copies `bytes` into the attachment store and, for documents, extracts their text once so it
+/// doesn't have to be parsed again on every message. Content that is already stored is reused.
+pub fn store(state: &AppState, name: &str, bytes: &[u8]) -> Result<models::Attachment> {
+    let hash: String = Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect();
+
+    if let Some(existing) = queries::get_attachment(&state.db.lock().unwrap(), &hash)? {
+        if Path::new(&existing.path).exists() {
+            log::info!("[Attachments] Reusing stored copy of {} ({})", name, hash);
+            let text = queries::get_attachment_text(&state.db.lock().unwrap(), &hash)?;
+            return Ok(models::Attachment {
+                name: name.to_string(),
+                text_tokens: text.as_deref().map(context::estimate_tokens),
+                ..existing
+            });
+        }
+    }
+
+    let dir = state.context.app_data_dir.join("attachments");
+    fs::create_dir_all(&dir)?;
+    let file_name = match extension(name) {
+        Some(ext) => format!("{}.{}", hash, ext),
+        None => hash.clone(),
+    };
+    let path = dir.join(file_name);
+    fs::write(&path, bytes)?;
+
+    let mime_type = detect_mime(name, bytes);
+    let text = if mime_type.starts_with("image/") || mime_type.starts_with("audio/") {
+        None
+    } else {
+        match parser::parse_file(&path) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                log::warn!("[Attachments] Could not extract text from {}: {}", name, e);
+                None
+            }
+        }
+    };
+
+    let attachment = models::Attachment {
+        hash,
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        mime_type,
+        size: bytes.len() as u64,
+        text_tokens: text.as_deref().map(context::estimate_tokens),
+    };
+    queries::upsert_attachment(&state.db.lock().unwrap(), &attachment, text.as_deref())?;
+    log::info!("[Attachments] Stored {} as {} ({})", name, attachment.path, attachment.mime_type);
+    Ok(attachment)
+}
+
+/// The stored copy of an attachment. Attachments come back from the frontend, so only the
+/// hash is trusted and the path is looked up rather than taken from the attachment.
+fn stored_path(state: &AppState, attachment: &models::Attachment) -> Result<PathBuf> {
+    queries::get_attachment(&state.db.lock().unwrap(), &attachment.hash)?
+        .map(|stored| PathBuf::from(stored.path))
+        .ok_or_else(|| AppError::Config(format!("Attachment {} is not in the attachment store", attachment.name)))
+}
+
+pub fn store_file(state: &AppState, path: &Path) -> Result<models::Attachment> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("attachment");
+    store(state, name, &fs::read(path)?)
+}
+
+/// Transcribes an audio attachment with the speech-to-text assignment (or its fallbacks).
+/// Transcripts are kept with the attachment, so a recording is only sent once.
+pub async fn transcribe(state: &AppState, attachment: &models::Attachment) -> Result<String> {
+    if let Some(transcript) = queries::get_attachment_text(&state.db.lock().unwrap(), &attachment.hash)? {
+        return Ok(transcript);
+    }
+
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let endpoint = settings.api_config.assignments.stt.as_ref()
+        .ok_or_else(|| AppError::Config("Speech-to-text model not assigned".to_string()))?;
+    let provider = settings.api_config.providers.iter().find(|p| p.id == endpoint.provider_id).cloned()
+        .ok_or_else(|| AppError::Config(format!("Provider with ID {} not found", endpoint.provider_id)))?;
+
+    let audio = general_purpose::STANDARD.encode(fs::read(stored_path(state, attachment)?)?);
+    let url = format!("{}/api/v1/proxy/audio/transcriptions", settings.execution.backend_url);
+    let candidates = failover::candidates(&settings, "stt", (provider, endpoint.model_name.clone()));
+    let (response, _, _) = failover::run(&settings.failover, candidates, |provider, model| {
+        let (url, audio) = (&url, &audio);
+        async move {
+            let payload = ProxyTranscriptionPayload {
+                model: &model,
+                audio: audio.clone(),
+                file_name: &attachment.name,
+                mime_type: &attachment.mime_type,
+                provider_config: &provider,
+            };
+            let call = UsageCall::start(&provider, &model, "transcription", "chat");
+            let result: std::result::Result<TranscriptionResponse, AttemptError> = async {
+                let response = state.http_client.post(url).json(&payload).send().await?.error_for_status()?;
+                Ok(response.json().await?)
+            }.await;
+            match &result {
+                Ok(response) => call.finish(state, CallUsage::estimated(0, context::estimate_tokens(&response.text)), None),
+                Err(e) => call.finish(state, CallUsage::estimated(0, 0), Some(e.message.clone())),
+            }
+            result
+        }
+    }).await?;
+
+    let transcript = response.text.trim().to_string();
+    queries::set_attachment_text(&state.db.lock().unwrap(), &attachment.hash, &transcript)?;
+    Ok(transcript)
+}
+
+/// The text a document attachment contributes to the prompt.
+pub fn document_prompt(state: &AppState, attachment: &models::Attachment) -> Result<String> {
+    let stored = queries::get_attachment_text(&state.db.lock().unwrap(), &attachment.hash)?;
+    let text = match stored {
+        Some(text) => text,
+        // Stored before its type was supported, or the parser failed at the time: try again.
+        None => parser::parse_file(&stored_path(state, attachment)?).unwrap_or_default(),
+    };
+    if text.trim().is_empty() {
+        return Ok(format!("[Attachment: {} ({}) — its content could not be read]", attachment.name, attachment.mime_type));
+    }
+
+    let mut body: String = text.chars().take(MAX_ATTACHMENT_CHARS).collect();
+    if body.len() < text.len() {
+        body.push_str("\n[… truncated]");
+    }
+    Ok(format!("[Attachment: {}]\n```\n{}\n```", attachment.name, body))
+}
@@ -0,0 +1,99 @@
+// src-tauri/src/services/chat/compare.rs
+use super::message_handler;
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    state::AppState,
+};
+use futures_util::future::join_all;
+use serde_json::json;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+/// Sends one user message to several models at once. Every reply streams under its own
+/// placeholder ID through the usual `stream-chunk`/`stream-end` events, can be stopped with
+/// `stop_chat_generation`, and is stored as a sibling reply to the user message. Title and
+/// memory follow-ups only run for the first reply to complete. If the comparison can't start,
+/// every placeholder gets `stream-error`. When all replies are done, the first successful one
+/// becomes the active branch and `compare-end` reports each reply's stats.
+pub async fn compare_models(
+    app: AppHandle,
+    state: AppState,
+    mut user_message: models::ChatMessage,
+    targets: Vec<models::CompareTarget>,
+    api_config: models::ApiConfig,
+    template_variables: HashMap<String, String>,
+) -> Result<()> {
+    let started = if targets.len() < 2 {
+        Err(AppError::Config("Compare mode needs at least two models".to_string()))
+    } else {
+        log::info!("[ChatService] Comparing {} models for user message ID: {}", targets.len(), user_message.id);
+        message_handler::store_user_message(&app, &state, &mut user_message, &template_variables).await
+    };
+    if let Err(e) = started {
+        // The frontend already shows a placeholder for every target; end each one.
+        for target in &targets {
+            let message = models::ChatMessage { model: Some(target.model.clone()), ..user_message.clone() };
+            message_handler::report_failure(&app, &state, &message, &target.ai_message_id, &e);
+        }
+        return Err(e);
+    }
+
+    let options = message_handler::ReplyOptions::default();
+    let replies = targets.iter().map(|target| {
+        let mut message = user_message.clone();
+        message.model = Some(target.model.clone());
+        let (app, state) = (app.clone(), state.clone());
+        let (ai_message_id, api_config, template_variables) = (target.ai_message_id.clone(), api_config.clone(), template_variables.clone());
+        let follow_ups = options.follow_ups.clone();
+        async move {
+            let options = message_handler::ReplyOptions { follow_ups, resume_from: None };
+            message_handler::generate_reply_with(app, state, message, ai_message_id, api_config, template_variables, options).await
+        }
+    });
+    let results = join_all(replies).await;
+
+    let stored: Vec<Option<models::ChatMessage>> = {
+        let conn = state.db.lock().unwrap();
+        let stored = targets.iter()
+            .map(|target| queries::get_message_by_id(&conn, &target.ai_message_id))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(first) = stored.iter().flatten().find(|m| m.error.is_none()) {
+            queries::set_active_leaf(&conn, &first.conversation_id, &first.id)?;
+        }
+        stored
+    };
+
+    let summary: Vec<serde_json::Value> = targets.iter().zip(stored).zip(results).map(|((target, message), result)| {
+        json!({
+            "messageId": target.ai_message_id,
+            "requestedModel": target.model,
+            "model": message.as_ref().and_then(|m| m.model.clone()),
+            "generationStats": message.as_ref().and_then(|m| m.generation_stats.clone()),
+            "error": message.and_then(|m| m.error).or_else(|| result.err().map(|e| message_handler::classify_app_error(&e))),
+        })
+    }).collect();
+    app.emit_all("compare-end", json!({ "userMessageId": user_message.id, "results": summary }))?;
+    Ok(())
+}
+
+/// Makes `message_id` the canonical answer of a comparison: its branch becomes the active one and,
+/// with `discard_others`, the other replies to the same user message are deleted. Returns the IDs
+/// of deleted messages.
+pub fn select_answer(conn: &rusqlite::Connection, message_id: &str, discard_others: bool) -> Result<Vec<String>> {
+    let message = queries::get_message_by_id(conn, message_id)?
+        .ok_or_else(|| AppError::Database(format!("Message {} not found", message_id)))?;
+    let leaf_id = queries::find_latest_leaf(conn, &message.id)?;
+    queries::set_active_leaf(conn, &message.conversation_id, &leaf_id)?;
+
+    let mut removed = Vec::new();
+    if discard_others {
+        let parent_id = message.parent_id
+            .ok_or_else(|| AppError::Internal(format!("Message {} has no parent", message_id)))?;
+        for sibling in queries::get_child_message_ids(conn, &parent_id)?.into_iter().filter(|id| *id != message.id) {
+            removed.extend(queries::get_message_subtree_ids(conn, &sibling)?);
+            queries::delete_message(conn, &sibling)?;
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,215 @@
+// src-tauri/src/services/chat/context.rs
+use super::llm_utils;
+use crate::{
+    database::{models, queries},
+    error::Result,
+    state::AppState,
+};
+
+/// Per-message framing overhead (role markers, separators) added by chat templates.
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+/// Rough cost of one image at default detail.
+const IMAGE_TOKENS: u32 = 765;
+
+/// Estimates the token count of a piece of text without a model-specific tokenizer.
+/// Latin text averages about four characters per token; CJK and other wide scripts
+/// are closer to one token per character.
+pub fn estimate_tokens(text: &str) -> u32 {
+    let (wide, narrow) = text.chars().fold((0u32, 0u32), |(wide, narrow), c| {
+        if (c as u32) >= 0x2E80 { (wide + 1, narrow) } else { (wide, narrow + 1) }
+    });
+    wide + narrow.div_ceil(4)
+}
+
+/// Estimated tokens of one message with the given content, including its framing.
+pub fn estimate_content_tokens(content: &[models::ChatMessageContentPart]) -> u32 {
+    MESSAGE_OVERHEAD_TOKENS + content.iter().map(|part| match part {
+        models::ChatMessageContentPart::Text { text } => estimate_tokens(text),
+        models::ChatMessageContentPart::ImageUrl { .. } => IMAGE_TOKENS,
+        models::ChatMessageContentPart::File { file } => file.text_tokens.unwrap_or(0),
+        models::ChatMessageContentPart::Audio { transcript, .. } => transcript.as_deref().map_or(0, estimate_tokens),
+    }).sum::<u32>()
+}
+
+pub fn estimate_message_tokens(message: &models::ChatMessage) -> u32 {
+    estimate_content_tokens(&message.content)
+}
+
+/// Number of prompt tokens available for a model: its configured `maxTokens` (or the default
+/// context window) minus the reserve kept for the reply.
+pub fn context_budget(api_config: &models::ApiConfig, provider_id: &str, model_name: &str, settings: &models::ContextSettings) -> u32 {
+    let window = api_config.providers.iter()
+        .find(|p| p.id == provider_id)
+        .and_then(|p| p.models.iter().find(|m| m.name == model_name))
+        .and_then(|m| m.max_tokens)
+        .unwrap_or(settings.default_context_window);
+    window.saturating_sub(settings.response_reserve).max(window / 2)
+}
+
+/// Which messages of a history fit into the budget. `dropped` is always a run of the oldest
+/// unpinned messages, so it can be folded into a rolling summary.
+pub struct ContextPlan {
+    pub included: Vec<usize>,
+    pub dropped: Vec<usize>,
+    pub used_tokens: u32,
+}
+
+pub fn plan_context(history: &[models::ChatMessage], budget: u32, reserved_tokens: u32, min_recent_messages: usize) -> ContextPlan {
+    let tokens: Vec<u32> = history.iter().map(estimate_message_tokens).collect();
+    let recent_start = history.len().saturating_sub(min_recent_messages.max(1));
+    let is_protected = |i: usize| i >= recent_start || history[i].is_pinned;
+
+    let mut used = reserved_tokens + (0..history.len()).filter(|&i| is_protected(i)).map(|i| tokens[i]).sum::<u32>();
+    let mut boundary = 0;
+    for i in (0..recent_start).rev() {
+        if is_protected(i) {
+            continue;
+        }
+        if used + tokens[i] > budget {
+            boundary = i + 1;
+            break;
+        }
+        used += tokens[i];
+    }
+
+    let (dropped, included) = (0..history.len()).partition(|&i| i < boundary && !is_protected(i));
+    ContextPlan { included, dropped, used_tokens: used - reserved_tokens }
+}
+
+/// History trimmed to the model's context window, plus the summary of whatever was cut.
+pub struct PreparedContext {
+    pub messages: Vec<models::ChatMessage>,
+    pub summary: Option<String>,
+}
+
+/// Applies the configured context strategy to `history` before it is sent to the model.
+/// With the "summarize" strategy, messages that no longer fit are folded into a rolling summary;
+/// only messages not yet covered by a summary of the same branch are sent to the summarizer.
+pub async fn prepare_context(
+    state: &AppState,
+    conversation_id: &str,
+    history: Vec<models::ChatMessage>,
+    budget: u32,
+) -> Result<PreparedContext> {
+    let settings = queries::get_settings(&state.db.lock().unwrap())?.context;
+    if settings.strategy == "off" {
+        return Ok(PreparedContext { messages: history, summary: None });
+    }
+
+    let plan = plan_context(&history, budget, 0, settings.min_recent_messages);
+    if plan.dropped.is_empty() {
+        return Ok(PreparedContext { messages: history, summary: None });
+    }
+
+    if settings.strategy != "summarize" {
+        log::info!("[ChatService] Context window: dropped {} oldest message(s) from conversation {}", plan.dropped.len(), conversation_id);
+        return Ok(PreparedContext { messages: select(history, &plan.included), summary: None });
+    }
+
+    // Leave room for the summary itself before deciding what gets folded into it.
+    let plan = plan_context(&history, budget, budget / 5, settings.min_recent_messages);
+    let dropped: Vec<&models::ChatMessage> = plan.dropped.iter().map(|&i| &history[i]).collect();
+    let summaries = queries::list_context_summaries(&state.db.lock().unwrap(), conversation_id)?;
+    let (previous_summary, pending) = match latest_summary(&summaries, &dropped) {
+        Some((pos, existing)) => (Some(existing.summary.as_str()), &dropped[pos + 1..]),
+        None => (None, &dropped[..]),
+    };
+
+    let summary = if pending.is_empty() {
+        previous_summary.map(str::to_string)
+    } else {
+        log::info!("[ChatService] Summarizing {} message(s) of conversation {} that no longer fit the context window", pending.len(), conversation_id);
+        match llm_utils::summarize_messages(state, previous_summary, pending).await {
+            Ok(summary) => {
+                let stored = models::ContextSummary {
+                    summary: summary.clone(),
+                    covered_until_id: dropped.last().map(|m| m.id.clone()).unwrap_or_default(),
+                };
+                queries::save_context_summary(&state.db.lock().unwrap(), conversation_id, &stored)?;
+                Some(summary)
+            }
+            Err(e) => {
+                log::warn!("[ChatService] Failed to summarize conversation {}, falling back to sliding window: {}", conversation_id, e);
+                previous_summary.map(str::to_string)
+            }
+        }
+    };
+
+    Ok(PreparedContext { messages: select(history, &plan.included), summary })
+}
+
+/// Reports how the next request for `conversation_id` would be packed, without calling any model.
+pub fn context_usage(conn: &rusqlite::Connection, conversation_id: &str, budget: u32, settings: &models::ContextSettings) -> Result<models::ContextUsage> {
+    let history = queries::get_conversation_history(conn, conversation_id)?;
+    let pinned_messages = history.iter().filter(|m| m.is_pinned).count();
+
+    if settings.strategy == "off" {
+        return Ok(models::ContextUsage {
+            budget,
+            used_tokens: history.iter().map(estimate_message_tokens).sum(),
+            total_messages: history.len(),
+            included_messages: history.len(),
+            pinned_messages,
+            summarized: false,
+        });
+    }
+
+    let mut plan = plan_context(&history, budget, 0, settings.min_recent_messages);
+    let mut summarized = false;
+    if !plan.dropped.is_empty() && settings.strategy == "summarize" {
+        plan = plan_context(&history, budget, budget / 5, settings.min_recent_messages);
+        let dropped: Vec<&models::ChatMessage> = plan.dropped.iter().map(|&i| &history[i]).collect();
+        let summaries = queries::list_context_summaries(conn, conversation_id)?;
+        if let Some((_, summary)) = latest_summary(&summaries, &dropped) {
+            plan.used_tokens += estimate_tokens(&summary.summary) + MESSAGE_OVERHEAD_TOKENS;
+            summarized = true;
+        }
+    }
+
+    Ok(models::ContextUsage {
+        budget,
+        used_tokens: plan.used_tokens,
+        total_messages: history.len(),
+        included_messages: plan.included.len(),
+        pinned_messages,
+        summarized,
+    })
+}
+
+/// The stored summary that covers the longest run of `dropped`, with the position of the last
+/// message it covers. Summaries made on other branches end at messages that aren't in `dropped`.
+fn latest_summary<'a>(summaries: &'a [models::ContextSummary], dropped: &[&models::ChatMessage]) -> Option<(usize, &'a models::ContextSummary)> {
+    summaries.iter()
+        .filter_map(|s| dropped.iter().position(|m| m.id == s.covered_until_id).map(|pos| (pos, s)))
+        .max_by_key(|(pos, _)| *pos)
+}
+
+fn select(history: Vec<models::ChatMessage>, indices: &[usize]) -> Vec<models::ChatMessage> {
+    history.into_iter().enumerate()
+        .filter(|(i, _)| indices.binary_search(i).is_ok())
+        .map(|(_, m)| m)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_are_only_reused_on_their_own_branch() {
+        let message = |id: &str| models::ChatMessage { id: id.to_string(), ..Default::default() };
+        let summary = |until: &str| models::ContextSummary { summary: format!("up to {}", until), covered_until_id: until.to_string() };
+        let (a, b1, c1, b2) = (message("a"), message("b1"), message("c1"), message("b2"));
+        let summaries = [summary("a"), summary("b1"), summary("c1")];
+
+        let branch_one = [&a, &b1, &c1];
+        let (pos, found) = latest_summary(&summaries, &branch_one).unwrap();
+        assert_eq!((pos, found.covered_until_id.as_str()), (2, "c1"));
+
+        let branch_two = [&a, &b2];
+        let (pos, found) = latest_summary(&summaries, &branch_two).unwrap();
+        assert_eq!((pos, found.covered_until_id.as_str()), (0, "a"));
+
+        assert!(latest_summary(&summaries[1..], &branch_two).is_none());
+    }
+}
@@ -1,70 +1,113 @@
 // src-tauri/src/services/chat/llm_utils.rs
+use super::{context, templates};
 use crate::{
     database::{models, queries},
     error::{AppError, Result},
-    services::proxy_types::{ProxyChatPayload, ProxyMessage, ProxyResponse},
+    services::{
+        failover::{self, AttemptError},
+        proxy_types::{ProxyChatPayload, ProxyMessage, ProxyResponse, SamplingParams},
+        usage::{CallUsage, UsageCall},
+    },
     state::AppState,
 };
+use std::collections::HashMap;
 
-pub async fn generate_title_for_conversation(state: &AppState, user_query: &str, ai_response: &str) -> Result<String> {
-    let title_prompt = format!(
-        "Based on the following user query and AI response, generate a very short, concise title (5-10 words max) for this conversation. Do not use quotes. \n\nUSER: \"{}\"\nAI: \"{}\"\n\nTITLE:",
-        user_query,
-        ai_response.chars().take(200).collect::<String>()
-    );
+/// Renders the user's override for a built-in prompt, if one is saved in the prompt library.
+fn builtin_prompt_override(state: &AppState, builtin: &str, values: &[(&str, &str)]) -> Result<Option<String>> {
+    let prompt = queries::get_builtin_prompt_override(&state.db.lock().unwrap(), builtin)?;
+    Ok(prompt.map(|prompt| {
+        let values: HashMap<String, String> = values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        templates::fill(&prompt.content, &values)
+    }))
+}
+
+/// The backend proxy's chat completions endpoint, which every chat request goes through.
+pub fn chat_completions_url(backend_url: &str) -> String {
+    format!("{}/api/v1/proxy/chat/completions", backend_url)
+}
+
+/// Sends a single prompt to the suggestion model (or its fallbacks) and returns the first choice,
+/// recording each attempt under `purpose`.
+async fn complete_with_suggestion_model(state: &AppState, purpose: &str, prompt: String) -> Result<Option<String>> {
     let settings = queries::get_settings(&state.db.lock().unwrap())?;
     let model_endpoint = settings.api_config.assignments.suggestion.as_ref().ok_or_else(|| AppError::Config("Suggestion model not assigned".to_string()))?;
     let provider = settings.api_config.providers.iter().find(|p| p.id == model_endpoint.provider_id).cloned().ok_or_else(|| AppError::Config("Provider not found".to_string()))?;
 
-    let content_part = vec![models::ChatMessageContentPart::Text { text: title_prompt }];
-    let messages = vec![ProxyMessage { role: "user".to_string(), content: &content_part }];
-    
-    let request_body = ProxyChatPayload {
-        model: &model_endpoint.model_name,
-        messages,
-        stream: false,
-        provider_config: &provider,
-        knowledge_base_selection: None,
-        api_config: None,
-    };
+    let content_part = vec![models::ChatMessageContentPart::Text { text: prompt }];
+    let prompt_tokens = context::estimate_content_tokens(&content_part);
+    let url = chat_completions_url(&settings.execution.backend_url);
+
+    let candidates = failover::candidates(&settings, "suggestion", (provider, model_endpoint.model_name.clone()));
+    let (text, _, _) = failover::run(&settings.failover, candidates, |provider, model| {
+        let (content_part, url) = (&content_part, &url);
+        async move {
+            let request_body = ProxyChatPayload {
+                model: &model,
+                messages: vec![ProxyMessage { role: "user".to_string(), content: content_part }],
+                stream: false,
+                provider_config: &provider,
+                knowledge_base_selection: None,
+                api_config: None,
+                sampling: SamplingParams::default(),
+            };
 
-    let url = format!("{}/api/v1/proxy/chat/completions", settings.execution.backend_url);
-    let response = state.http_client.post(url).json(&request_body).send().await?.error_for_status()?;
-    let response_data: ProxyResponse = response.json().await?;
+            let call = UsageCall::start(&provider, &model, "chat", purpose);
+            let result: std::result::Result<ProxyResponse, AttemptError> = async {
+                let response = state.http_client.post(url).json(&request_body).send().await?.error_for_status()?;
+                Ok(response.json().await?)
+            }.await;
 
-    let title = response_data.choices.get(0).map_or_else(
+            match result {
+                Ok(response_data) => {
+                    let text = response_data.choices.into_iter().next().map(|choice| choice.message.content);
+                    let completion_tokens = text.as_deref().map_or(0, context::estimate_tokens);
+                    call.finish(state, CallUsage::reported_or(response_data.usage.as_ref(), || (prompt_tokens, completion_tokens)), None);
+                    Ok(text)
+                }
+                Err(e) => {
+                    call.finish(state, CallUsage::estimated(prompt_tokens, 0), Some(e.message.clone()));
+                    Err(e)
+                }
+            }
+        }
+    }).await?;
+    Ok(text)
+}
+
+/// Sends a free-form prompt to the suggestion model and returns its answer, for workflows and tools.
+pub async fn complete_prompt(state: &AppState, purpose: &str, prompt: String) -> Result<String> {
+    Ok(complete_with_suggestion_model(state, purpose, prompt).await?.unwrap_or_default())
+}
+
+pub async fn generate_title_for_conversation(state: &AppState, user_query: &str, ai_response: &str) -> Result<String> {
+    let response_preview = ai_response.chars().take(200).collect::<String>();
+    let title_prompt = match builtin_prompt_override(state, "title", &[("query", user_query), ("response", &response_preview)])? {
+        Some(prompt) => prompt,
+        None => format!(
+            "Based on the following user query and AI response, generate a very short, concise title (5-10 words max) for this conversation. Do not use quotes. \n\nUSER: \"{}\"\nAI: \"{}\"\n\nTITLE:",
+            user_query,
+            response_preview
+        ),
+    };
+    let response_text = complete_with_suggestion_model(state, "title", title_prompt).await?;
+
+    let title = response_text.map_or_else(
         || "Untitled Chat".to_string(),
-        |choice| choice.message.content.trim().trim_matches('"').to_string()
+        |text| text.trim().trim_matches('"').to_string()
     );
 
     Ok(title)
 }
 
 pub async fn generate_suggestions(state: &AppState, context: &str) -> Result<Vec<String>> {
-    let suggestion_prompt = format!(
-        "Based on the following text, suggest three concise, actionable next steps for the user. Output only a single, valid JSON array of strings, like [\"Suggestion 1\", \"Suggestion 2\", \"Suggestion 3\"]. Text: \"{}\"",
-        context
-    );
-    let settings = queries::get_settings(&state.db.lock().unwrap())?;
-    let model_endpoint = settings.api_config.assignments.suggestion.as_ref().ok_or_else(|| AppError::Config("Suggestion model not assigned".to_string()))?;
-    let provider = settings.api_config.providers.iter().find(|p| p.id == model_endpoint.provider_id).cloned().ok_or_else(|| AppError::Config("Provider not found".to_string()))?;
-
-    let content_part = vec![models::ChatMessageContentPart::Text { text: suggestion_prompt }];
-    let messages = vec![ProxyMessage { role: "user".to_string(), content: &content_part }];
-    
-    let request_body = ProxyChatPayload {
-        model: &model_endpoint.model_name,
-        messages,
-        stream: false,
-        provider_config: &provider,
-        knowledge_base_selection: None,
-        api_config: None,
+    let suggestion_prompt = match builtin_prompt_override(state, "suggestions", &[("context", context)])? {
+        Some(prompt) => prompt,
+        None => format!(
+            "Based on the following text, suggest three concise, actionable next steps for the user. Output only a single, valid JSON array of strings, like [\"Suggestion 1\", \"Suggestion 2\", \"Suggestion 3\"]. Text: \"{}\"",
+            context
+        ),
     };
-
-    let url = format!("{}/api/v1/proxy/chat/completions", settings.execution.backend_url);
-    let response = state.http_client.post(url).json(&request_body).send().await?.error_for_status()?;
-    let response_data: ProxyResponse = response.json().await?;
-    let response_text = response_data.choices.get(0).map_or("[]".to_string(), |c| c.message.content.clone());
+    let response_text = complete_with_suggestion_model(state, "suggestions", suggestion_prompt).await?.unwrap_or_else(|| "[]".to_string());
 
     if let (Some(start), Some(end)) = (response_text.find('['), response_text.rfind(']')) {
         let json_str = &response_text[start..=end];
@@ -76,4 +119,61 @@ pub async fn generate_suggestions(state: &AppState, context: &str) -> Result<Vec
         log::warn!("No JSON array found in suggestions response: {}", response_text);
         Ok(vec![])
     }
-}
\ No newline at end of file
+}
+
+/// Folds `messages` into a running summary of the conversation, extending `previous_summary` if given.
+pub async fn summarize_messages(state: &AppState, previous_summary: Option<&str>, messages: &[&models::ChatMessage]) -> Result<String> {
+    let transcript = messages.iter().map(|msg| {
+        let text = msg.content.iter()
+            .filter_map(|part| match part {
+                models::ChatMessageContentPart::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let speaker = if msg.role == "user" { "USER" } else { "AI" };
+        format!("{}: {}", speaker, text.chars().take(2000).collect::<String>())
+    }).collect::<Vec<String>>().join("\n\n");
+
+    let summary_prompt = format!(
+        "You maintain a running summary of a conversation so it can continue after older messages are removed. Keep facts, decisions, names, numbers, code identifiers and open questions; drop pleasantries. Write at most 300 words of plain prose. Output only the summary.\n\nEXISTING SUMMARY:\n{}\n\nNEW MESSAGES:\n{}\n\nUPDATED SUMMARY:",
+        previous_summary.unwrap_or("(none)"),
+        transcript
+    );
+    let response_text = complete_with_suggestion_model(state, "summary", summary_prompt).await?;
+
+    response_text
+        .map(|text| text.trim().to_string())
+        .filter(|summary| !summary.is_empty())
+        .ok_or_else(|| AppError::Internal("Summarizer returned an empty response".to_string()))
+}
+
+/// Asks the suggestion model for durable facts or preferences about the user revealed by one exchange.
+/// Returns `(content, category)` pairs; `known` memories are passed along so they aren't proposed again.
+pub async fn extract_user_memories(state: &AppState, user_query: &str, ai_response: &str, known: &[String]) -> Result<Vec<(String, String)>> {
+    let known_list = if known.is_empty() { "(none)".to_string() } else { known.iter().map(|m| format!("- {}", m)).collect::<Vec<_>>().join("\n") };
+    let extraction_prompt = format!(
+        "You decide what a personal assistant should remember long-term about its user. From the exchange below, extract only durable facts about the user (their projects, role, tools, environment) or lasting preferences (how they like answers). Ignore one-off requests, anything about third parties, and sensitive data such as passwords, health or finances. Do not repeat anything already known. Output only a JSON array of objects like [{{\"content\": \"Prefers Rust examples\", \"category\": \"preference\"}}] where category is \"preference\" or \"fact\"; output [] if there is nothing worth remembering.\n\nALREADY KNOWN:\n{}\n\nUSER: \"{}\"\nAI: \"{}\"",
+        known_list,
+        user_query,
+        ai_response.chars().take(1500).collect::<String>()
+    );
+    let response_text = complete_with_suggestion_model(state, "memory", extraction_prompt).await?.unwrap_or_else(|| "[]".to_string());
+
+    let (Some(start), Some(end)) = (response_text.find('['), response_text.rfind(']')) else {
+        log::warn!("No JSON array found in user memory extraction response: {}", response_text);
+        return Ok(vec![]);
+    };
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response_text[start..=end]).map_err(|e| {
+        log::error!("Failed to parse user memories from LLM response. Raw: '{}', Error: {}", response_text, e);
+        AppError::Internal("Failed to parse user memories from proxy".to_string())
+    })?;
+    Ok(items.into_iter().filter_map(|item| {
+        let content = item.get("content")?.as_str()?.trim().to_string();
+        let category = match item.get("category").and_then(|c| c.as_str()) {
+            Some("preference") => "preference",
+            _ => "fact",
+        };
+        (!content.is_empty()).then(|| (content, category.to_string()))
+    }).collect())
+}
@@ -0,0 +1,169 @@
+// src-tauri/src/services/chat/memory.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    knowledge_base::{models::KnowledgeSource, searcher},
+    services::vector_client,
+    state::AppState,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+const MEMORY_COLLECTION_NAME: &str = "conversation_memory";
+const CHUNK_CHARS: usize = 1200;
+const CHUNK_OVERLAP_CHARS: usize = 200;
+
+fn memory_base() -> vector_client::VectorBase<'static> {
+    vector_client::VectorBase { database: vector_client::VECTOR_DB_NAME, collection: MEMORY_COLLECTION_NAME }
+}
+
+fn embedding_config(settings: &models::Settings) -> Result<(models::ApiProvider, String)> {
+    let endpoint = settings.api_config.assignments.embedding.as_ref()
+        .ok_or_else(|| AppError::Config("Embedding model not assigned".to_string()))?;
+    let provider = settings.api_config.providers.iter().find(|p| p.id == endpoint.provider_id).cloned()
+        .ok_or_else(|| AppError::Config("Embedding provider not found".to_string()))?;
+    Ok((provider, endpoint.model_name.clone()))
+}
+
+fn chunk_text(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= CHUNK_CHARS {
+        return vec![text.to_string()];
+    }
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = (start + CHUNK_CHARS).min(chars.len());
+        chunks.push(chars[start..end].iter().collect());
+        if end == chars.len() {
+            break;
+        }
+        start = end - CHUNK_OVERLAP_CHARS;
+    }
+    chunks
+}
+
+/// Embeds a finished exchange into the conversation memory collection.
+/// Does nothing unless memory is enabled and the conversation isn't excluded.
+pub async fn remember_exchange(state: &AppState, user_message: &models::ChatMessage, ai_message: &models::ChatMessage) -> Result<()> {
+    let (settings, excluded) = {
+        let conn = state.db.lock().unwrap();
+        (queries::get_settings(&conn)?, queries::is_conversation_memory_excluded(&conn, &ai_message.conversation_id)?)
+    };
+    if !settings.conversation_memory.enabled || excluded {
+        return Ok(());
+    }
+
+    let question = queries::search_text_from_content(&user_message.content);
+    let answer = queries::search_text_from_content(&ai_message.content);
+    if answer.trim().is_empty() {
+        return Ok(());
+    }
+    let chunks = chunk_text(&format!("USER: {}\nAI: {}", question, answer));
+
+    let (provider, model_name) = embedding_config(&settings)?;
+    let backend_url = settings.execution.backend_url;
+    let embeddings = searcher::get_embeddings_from_proxy(state, &provider, &model_name, &chunks, "memory").await?;
+
+    vector_client::ensure_named_collection(state, &backend_url, MEMORY_COLLECTION_NAME).await?;
+    let payload = vector_client::AddPayload {
+        base: memory_base(),
+        ids: (0..chunks.len()).map(|i| format!("{}:{}", ai_message.id, i)).collect(),
+        embeddings,
+        documents: chunks.iter().map(String::as_str).collect(),
+        metadatas: (0..chunks.len()).map(|i| json!({
+            "conversation_id": ai_message.conversation_id,
+            "message_id": ai_message.id,
+            "user_message_id": user_message.id,
+            "timestamp": ai_message.timestamp,
+            "chunk_index": i,
+        })).collect(),
+    };
+    vector_client::add(state, &backend_url, &payload).await?;
+    log::info!("[ChatService] Stored {} memory chunk(s) for message {}", chunks.len(), ai_message.id);
+    Ok(())
+}
+
+/// Finds past exchanges relevant to `query` from conversations other than `current_conversation_id`.
+/// Sources point at `conversation://<conversation_id>/<message_id>`.
+pub async fn recall(state: &AppState, query: &str, current_conversation_id: &str) -> Result<Vec<KnowledgeSource>> {
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let memory_settings = settings.conversation_memory.clone();
+    if !memory_settings.enabled || !memory_settings.include_in_chat || query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let (provider, model_name) = embedding_config(&settings)?;
+    let query_embedding = searcher::get_embeddings_from_proxy(state, &provider, &model_name, &[query.to_string()], "memory")
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::Internal("Failed to generate query embedding".to_string()))?;
+
+    let payload = vector_client::QueryPayload {
+        base: memory_base(),
+        query_embeddings: vec![query_embedding],
+        // Over-fetch: several chunks of one message collapse into a single source below.
+        n_results: memory_settings.top_k * 3,
+        where_filter: Some(json!({ "conversation_id": { "$ne": current_conversation_id } })),
+        score_threshold: Some(memory_settings.score_threshold),
+    };
+    let query_result = vector_client::query(state, &settings.execution.backend_url, &payload).await?;
+
+    let documents = query_result.documents.into_iter().next().flatten().unwrap_or_default();
+    let metadatas = query_result.metadatas.into_iter().next().flatten().unwrap_or_default();
+    let distances = query_result.distances.into_iter().next().flatten().unwrap_or_default();
+
+    let mut best: HashMap<String, (String, String, f32)> = HashMap::new();
+    for ((doc, meta), dist) in documents.into_iter().zip(metadatas).zip(distances) {
+        let (Some(document), Some(meta)) = (doc, meta) else { continue };
+        let (Some(conversation_id), Some(message_id)) = (
+            meta.get("conversation_id").and_then(|v| v.as_str()),
+            meta.get("message_id").and_then(|v| v.as_str()),
+        ) else { continue };
+        let score = 1.0 / (1.0 + dist);
+        best.entry(message_id.to_string())
+            .and_modify(|existing| if score > existing.2 { *existing = (conversation_id.to_string(), document.clone(), score) })
+            .or_insert((conversation_id.to_string(), document, score));
+    }
+
+    let conn = state.db.lock().unwrap();
+    let mut sources = Vec::new();
+    for (message_id, (conversation_id, document, score)) in best {
+        // Skip chunks whose conversation was deleted or excluded after they were stored.
+        let Some(conversation) = queries::get_conversation_by_id(&conn, &conversation_id)? else { continue };
+        if queries::is_conversation_memory_excluded(&conn, &conversation_id)? {
+            continue;
+        }
+        sources.push(KnowledgeSource {
+            id: message_id.clone(),
+            file_path: format!("conversation://{}/{}", conversation_id, message_id),
+            source_name: conversation.title,
+            content_snippet: document,
+            score,
+        });
+    }
+    sources.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    sources.truncate(memory_settings.top_k as usize);
+    Ok(sources)
+}
+
+async fn forget_where(state: &AppState, where_metadata: serde_json::Value) -> Result<()> {
+    let backend_url = queries::get_settings(&state.db.lock().unwrap())?.execution.backend_url;
+    let payload = vector_client::DeletePayload { base: memory_base(), where_metadata };
+    vector_client::delete(state, &backend_url, &payload).await
+}
+
+pub async fn forget_conversation(state: &AppState, conversation_id: &str) -> Result<()> {
+    log::info!("[ChatService] Forgetting memory of conversation {}", conversation_id);
+    forget_where(state, json!({ "conversation_id": conversation_id })).await
+}
+
+pub async fn forget_message(state: &AppState, message_id: &str) -> Result<()> {
+    forget_where(state, json!({ "message_id": message_id })).await
+}
+
+pub async fn clear(state: &AppState) -> Result<()> {
+    log::info!("[ChatService] Clearing the whole conversation memory collection");
+    let backend_url = queries::get_settings(&state.db.lock().unwrap())?.execution.backend_url;
+    vector_client::clear_named_collection(state, &backend_url, MEMORY_COLLECTION_NAME).await
+}
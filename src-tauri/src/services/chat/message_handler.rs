@@ -1,19 +1,24 @@
 // src-tauri/src/services/chat/message_handler.rs
-use super::llm_utils;
+use super::{context, llm_utils, memory, templates, user_memory};
 use crate::{
     database::{models, queries},
     error::{AppError, Result},
     knowledge_base,
+    services::{attachments, prompts},
     services::proxy_types::{
-        ProxyChatPayload, ProxyMessage, ProxyStreamChunk,
+        ProxyChatPayload, ProxyMessage, ProxyStreamChunk, ProxyUsage, SamplingParams,
     },
+    services::failover::{self, AttemptError},
+    services::usage::{self, CallUsage, UsageCall},
     state::AppState,
 };
 use futures_util::StreamExt;
 use reqwest_eventsource::{Event, EventSource};
 use serde_json::json;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tauri::{AppHandle, Manager};
 use base64::{engine::general_purpose, Engine as _};
 use std::fs;
@@ -28,7 +33,9 @@ fn extract_text_from_content(content: &[models::ChatMessageContentPart]) -> Stri
         .join("\n")
 }
 
-async fn process_content_for_llm(content: &[models::ChatMessageContentPart]) -> Result<Vec<models::ChatMessageContentPart>> {
+/// Turns a stored message into what the model receives: local images become data URLs with
+/// their real MIME type, documents become their extracted text and audio its transcript.
+async fn process_content_for_llm(state: &AppState, content: &[models::ChatMessageContentPart]) -> Result<Vec<models::ChatMessageContentPart>> {
     let mut processed_parts = Vec::new();
     for part in content {
         match part {
@@ -39,7 +46,7 @@ async fn process_content_for_llm(content: &[models::ChatMessageContentPart]) ->
                     log::info!("Found local image path, converting to base64 data URL: {}", url);
                     let image_bytes = fs::read(url)?;
                     let base64_str = general_purpose::STANDARD.encode(&image_bytes);
-                    let data_url = format!("data:image/webp;base64,{}", base64_str);
+                    let data_url = format!("data:{};base64,{}", attachments::detect_mime(url, &image_bytes), base64_str);
                     processed_parts.push(models::ChatMessageContentPart::ImageUrl {
                         image_url: models::ImageUrl { url: data_url },
                     });
@@ -47,6 +54,18 @@ async fn process_content_for_llm(content: &[models::ChatMessageContentPart]) ->
                     processed_parts.push(part.clone());
                 }
             }
+            models::ChatMessageContentPart::File { file } => {
+                let text = attachments::document_prompt(state, file)?;
+                processed_parts.push(models::ChatMessageContentPart::Text { text });
+            }
+            models::ChatMessageContentPart::Audio { audio, transcript } => {
+                let transcript = match transcript {
+                    Some(transcript) => transcript.clone(),
+                    None => attachments::transcribe(state, audio).await?,
+                };
+                let text = format!("[Voice message: {}]\n{}", audio.name, transcript);
+                processed_parts.push(models::ChatMessageContentPart::Text { text });
+            }
             _ => {
                 processed_parts.push(part.clone());
             }
@@ -55,69 +74,118 @@ async fn process_content_for_llm(content: &[models::ChatMessageContentPart]) ->
     Ok(processed_parts)
 }
 
-pub async fn handle_message(
-    app: AppHandle,
-    state: AppState,
-    user_message: models::ChatMessage,
-    ai_message_id: String,
-    api_config: models::ApiConfig,
-) -> Result<()> {
-    log::info!("[ChatService] Handling user message ID: {}", user_message.id);
+/// What one streaming attempt produced before it finished, failed or was stopped.
+#[derive(Default)]
+struct StreamedReply {
+    thinking: String,
+    content: String,
+    sources: Option<Vec<knowledge_base::models::KnowledgeSource>>,
+    usage: Option<ProxyUsage>,
+    first_token_at: Option<Instant>,
+    error: Option<AttemptError>,
+}
 
-    {
-        let db_conn = state.db.lock().unwrap();
-        queries::ensure_conversation_exists(&db_conn, &user_message.conversation_id, "New Chat...", "chat")?;
-        queries::save_message(&db_conn, &user_message)?;
+impl StreamedReply {
+    fn full_response(&self) -> String {
+        if !self.thinking.is_empty() {
+            format!("<think>{}</think>{}", self.thinking, self.content)
+        } else {
+            self.content.clone()
+        }
     }
+}
 
-    let model_identifier = user_message.model.clone().ok_or_else(|| AppError::Config("No model specified in the request".to_string()))?;
-    let user_query = extract_text_from_content(&user_message.content);
-
-    let history = {
-        let db_conn = state.db.lock().unwrap();
-        queries::get_conversation_history(&db_conn, &user_message.conversation_id)?
-    };
+async fn classify_stream_error(e: reqwest_eventsource::Error) -> AttemptError {
+    match e {
+        reqwest_eventsource::Error::Transport(e) => e.into(),
+        reqwest_eventsource::Error::InvalidStatusCode(status, response) => {
+            let body = response.text().await.unwrap_or_default();
+            AttemptError::from_status(status.as_u16(), &body)
+        }
+        other => AttemptError::permanent(other.to_string()),
+    }
+}
 
-    let parts: Vec<&str> = model_identifier.split("::").collect();
-    if parts.len() != 2 { return Err(AppError::Config(format!("Invalid model identifier: {}", model_identifier))); }
-    let (provider_id, model_name) = (parts[0], parts[1]);
+/// The proxy reports provider failures that happen mid-stream as a chunk with an `error`
+/// field, either a string or an object with `message` and optionally `status`/`code`.
+fn stream_chunk_error(data: &str) -> Option<AttemptError> {
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    let error = value.get("error").filter(|e| !e.is_null())?;
+    let message = error.get("message").and_then(|m| m.as_str()).or(error.as_str()).unwrap_or("The provider reported an error");
+    let status = ["status", "code"].iter()
+        .find_map(|key| error.get(*key).and_then(|c| c.as_u64().or_else(|| c.as_str().and_then(|s| s.parse().ok()))))
+        .and_then(|c| u16::try_from(c).ok());
+    Some(AttemptError::from_message(message, status))
+}
 
-    let provider = api_config.providers.iter().find(|p| p.id == provider_id).cloned().ok_or_else(|| AppError::Config(format!("Provider with ID {} not found", provider_id)))?;
+/// Classifies an error that stopped a reply before anything was streamed.
+pub fn classify_app_error(e: &AppError) -> models::ChatError {
+    let kind = match e {
+        AppError::Config(_) => models::ChatErrorKind::Config,
+        // `From<reqwest::Error>` files refused connections and timeouts under `VectorService`.
+        AppError::VectorService(msg) if msg.starts_with("Connection error") => models::ChatErrorKind::BackendUnavailable,
+        _ => models::ChatErrorKind::Unknown,
+    };
+    models::ChatError::new(kind, e.to_string())
+}
 
-    let mut processed_history = Vec::new();
-    for msg in &history {
-        let processed_content = process_content_for_llm(&msg.content).await?;
-        processed_history.push((msg.role.clone(), processed_content));
-    }
+/// Tells the frontend that a reply failed before it could stream, and keeps a failed AI message
+/// in the conversation (when the user message was saved) so it can be retried later. An existing
+/// message, e.g. a partial reply being resumed, keeps its content and only gets the new error.
+pub(super) fn report_failure(app: &AppHandle, state: &AppState, user_message: &models::ChatMessage, ai_message_id: &str, error: &AppError) {
+    let mut chat_error = classify_app_error(error);
+    log::error!("[ChatService] Reply {} failed ({:?}): {}", ai_message_id, chat_error.kind, chat_error.message);
 
-    let proxy_messages: Vec<ProxyMessage> = processed_history.iter().map(|(role, content)| {
-        let proxy_role = if role == "ai" { "assistant" } else { role };
-        ProxyMessage {
-            role: proxy_role.to_string(),
-            content: content
+    let stored = {
+        let db_conn = state.db.lock().unwrap();
+        let existing = queries::get_message_by_id(&db_conn, ai_message_id).ok().flatten();
+        let failed = match existing {
+            Some(existing) => {
+                chat_error.partial = !extract_text_from_content(&existing.content).trim().is_empty();
+                models::ChatMessage { error: Some(chat_error.clone()), ..existing }
+            }
+            None => models::ChatMessage {
+                id: ai_message_id.to_string(),
+                conversation_id: user_message.conversation_id.clone(),
+                role: "ai".to_string(),
+                content: vec![models::ChatMessageContentPart::Text { text: String::new() }],
+                error: Some(chat_error.clone()),
+                model: user_message.model.clone(),
+                knowledge_base_selection: user_message.knowledge_base_selection.clone(),
+                parent_id: Some(user_message.id.clone()),
+                ..Default::default()
+            },
+        };
+        match queries::get_message_by_id(&db_conn, &user_message.id) {
+            Ok(Some(_)) => queries::save_message(&db_conn, &failed).map(|_| failed),
+            Ok(None) => Err(AppError::Database(format!("Message {} was never saved", user_message.id))),
+            Err(e) => Err(e),
+        }
+    };
+    let content = match &stored {
+        Ok(message) => extract_text_from_content(&message.content),
+        Err(e) => {
+            log::warn!("[ChatService] Failed reply {} was not stored: {}", ai_message_id, e);
+            String::new()
         }
-    }).collect();
-    
-    let settings = queries::get_settings(&state.db.lock().unwrap())?;
-    let request_body = ProxyChatPayload {
-        model: &model_name,
-        messages: proxy_messages,
-        stream: true,
-        provider_config: &provider,
-        knowledge_base_selection: user_message.knowledge_base_selection.clone(),
-        api_config: Some(&api_config),
     };
 
-    let url = format!("{}/api/v1/proxy/chat/completions", settings.execution.backend_url);
-    let request_builder = state.http_client.post(url).json(&request_body);
-    let mut es = EventSource::new(request_builder)?;
-
-    let mut thinking_parts = String::new();
-    let mut content_parts = String::new();
-    let mut sources: Option<Vec<knowledge_base::models::KnowledgeSource>> = None;
+    let _ = app.emit_all("stream-error", json!({ "messageId": ai_message_id, "error": chat_error }));
+    let _ = app.emit_all("stream-end", json!({ "messageId": ai_message_id, "finalMessage": { "content": content, "error": chat_error } }));
+}
 
-    let stop_flag = Arc::new(AtomicBool::new(false));
-    state.running_chat_tasks.lock().unwrap().insert(ai_message_id.clone(), stop_flag.clone());
+/// Streams one reply from the proxy, forwarding chunks to the frontend as `stream-chunk` events.
+async fn stream_reply(
+    app: &AppHandle,
+    state: &AppState,
+    url: &str,
+    request_body: &ProxyChatPayload<'_>,
+    ai_message_id: &str,
+    stop_flag: &AtomicBool,
+) -> Result<StreamedReply> {
+    let request_builder = state.http_client.post(url).json(request_body);
+    let mut es = EventSource::new(request_builder)?;
+    let mut reply = StreamedReply::default();
 
     while let Some(event) = es.next().await {
         if stop_flag.load(Ordering::SeqCst) {
@@ -130,8 +198,8 @@ pub async fn handle_message(
             Ok(Event::Open) => log::info!("[ChatService] SSE connection opened to proxy for message ID: {}", ai_message_id),
             Ok(Event::Message(message)) => {
                 if message.event == "sources" {
-                    sources = serde_json::from_str(&message.data).ok();
-                    log::info!("[ChatService] Received {} sources from backend.", sources.as_ref().map_or(0, |s| s.len()));
+                    reply.sources = serde_json::from_str(&message.data).ok();
+                    log::info!("[ChatService] Received {} sources from backend.", reply.sources.as_ref().map_or(0, |s| s.len()));
                     continue;
                 }
 
@@ -139,42 +207,324 @@ pub async fn handle_message(
                     log::info!("[ChatService] SSE stream [DONE] received from proxy.");
                     break;
                 }
+                if let Some(error) = stream_chunk_error(&message.data) {
+                    log::error!("[ChatService] Proxy reported an error mid-stream: {}", error.message);
+                    es.close();
+                    reply.error = Some(error);
+                    break;
+                }
                 app.emit_all("stream-chunk", json!({ "messageId": ai_message_id, "chunk": &message.data }))?;
 
                 if let Ok(parsed_chunk) = serde_json::from_str::<ProxyStreamChunk>(&message.data) {
-                    if let Some(choice) = parsed_chunk.choices.get(0) {
+                    if let Some(choice) = parsed_chunk.choices.first() {
+                        if reply.first_token_at.is_none() && (choice.delta.content.is_some() || choice.delta.reasoning_content.is_some()) {
+                            reply.first_token_at = Some(Instant::now());
+                        }
                         if let Some(reasoning) = &choice.delta.reasoning_content {
-                            thinking_parts.push_str(reasoning);
+                            reply.thinking.push_str(reasoning);
                         }
                         if let Some(content) = &choice.delta.content {
-                            content_parts.push_str(content);
+                            reply.content.push_str(content);
                         }
                     }
+                    if parsed_chunk.usage.is_some() {
+                        reply.usage = parsed_chunk.usage;
+                    }
                 }
             }
+            Err(reqwest_eventsource::Error::StreamEnded) => {
+                log::info!("[ChatService] SSE stream ended normally for message ID: {}", ai_message_id);
+                es.close();
+                break;
+            }
             Err(e) => {
-                let error_string = e.to_string();
-                if error_string.contains("Stream ended") {
-                    log::info!("[ChatService] SSE stream ended normally for message ID: {}", ai_message_id);
-                } else {
-                    log::error!("[ChatService] SSE error from proxy: {}", error_string);
-                    // We will still proceed to the end to let the frontend handle the partial data.
-                }
                 es.close();
-                break; // Break the loop on any error, including normal stream end.
+                let error = classify_stream_error(e).await;
+                log::error!("[ChatService] SSE error from proxy: {}", error.message);
+                reply.error = Some(error);
+                break;
+            }
+        }
+    }
+    Ok(reply)
+}
+
+pub async fn handle_message(
+    app: AppHandle,
+    state: AppState,
+    mut user_message: models::ChatMessage,
+    ai_message_id: String,
+    api_config: models::ApiConfig,
+    template_variables: HashMap<String, String>,
+) -> Result<()> {
+    log::info!("[ChatService] Handling user message ID: {}", user_message.id);
+    if let Err(e) = store_user_message(&app, &state, &mut user_message, &template_variables).await {
+        report_failure(&app, &state, &user_message, &ai_message_id, &e);
+        return Err(e);
+    }
+    generate_reply(app, state, user_message, ai_message_id, api_config, template_variables).await
+}
+
+/// Expands a slash command in `user_message`, transcribes its audio and saves it as the conversation's new active leaf.
+pub async fn store_user_message(
+    app: &AppHandle,
+    state: &AppState,
+    user_message: &mut models::ChatMessage,
+    template_variables: &HashMap<String, String>,
+) -> Result<()> {
+    let text = extract_text_from_content(&user_message.content);
+    if let Some(expanded) = prompts::expand_slash_command(app, state, &text, template_variables).await? {
+        user_message.content.retain(|part| !matches!(part, models::ChatMessageContentPart::Text { .. }));
+        user_message.content.insert(0, models::ChatMessageContentPart::Text { text: expanded });
+    }
+
+    // Saved with the message so the transcript can be shown and isn't requested again.
+    for part in user_message.content.iter_mut() {
+        if let models::ChatMessageContentPart::Audio { audio, transcript: transcript @ None } = part {
+            *transcript = Some(attachments::transcribe(state, audio).await?);
+        }
+    }
+
+    let db_conn = state.db.lock().unwrap();
+    queries::ensure_conversation_exists(&db_conn, &user_message.conversation_id, "New Chat...", "chat")?;
+    queries::save_message(&db_conn, user_message)
+}
+
+/// Streams a new AI reply to `user_message`, which must already be saved and be the
+/// conversation's active leaf. The reply is stored as a child of that message, so calling
+/// this again for the same message produces an alternative version rather than overwriting.
+/// If the conversation has a persona, its system prompt (with `template_variables` expanded),
+/// sampling parameters and defaults for model and knowledge base are applied.
+pub async fn generate_reply(
+    app: AppHandle,
+    state: AppState,
+    user_message: models::ChatMessage,
+    ai_message_id: String,
+    api_config: models::ApiConfig,
+    template_variables: HashMap<String, String>,
+) -> Result<()> {
+    generate_reply_with(app, state, user_message, ai_message_id, api_config, template_variables, ReplyOptions::default()).await
+}
+
+/// How `generate_reply_with` produces and stores a reply.
+pub struct ReplyOptions {
+    /// Update the conversation title, conversation memory and user memory proposals afterwards,
+    /// if still set once the reply completes. The reply clears it, so compare mode shares one
+    /// flag and only its first complete reply runs them.
+    pub follow_ups: Arc<AtomicBool>,
+    /// Text of an earlier attempt that broke off. The model is asked to continue it, and the new
+    /// text is appended to it under the same message ID.
+    pub resume_from: Option<String>,
+}
+
+impl Default for ReplyOptions {
+    fn default() -> Self {
+        Self { follow_ups: Arc::new(AtomicBool::new(true)), resume_from: None }
+    }
+}
+
+/// Like `generate_reply`, with `options`. If the reply can't be started at all, a failed AI
+/// message is stored and `stream-error` is emitted before the error is returned.
+pub async fn generate_reply_with(
+    app: AppHandle,
+    state: AppState,
+    user_message: models::ChatMessage,
+    ai_message_id: String,
+    api_config: models::ApiConfig,
+    template_variables: HashMap<String, String>,
+    options: ReplyOptions,
+) -> Result<()> {
+    let result = run_reply(app.clone(), state.clone(), user_message.clone(), ai_message_id.clone(), api_config, template_variables, options).await;
+    if let Err(e) = &result {
+        state.running_chat_tasks.lock().unwrap().remove(&ai_message_id);
+        report_failure(&app, &state, &user_message, &ai_message_id, e);
+    }
+    result
+}
+
+async fn run_reply(
+    app: AppHandle,
+    state: AppState,
+    mut user_message: models::ChatMessage,
+    ai_message_id: String,
+    api_config: models::ApiConfig,
+    template_variables: HashMap<String, String>,
+    options: ReplyOptions,
+) -> Result<()> {
+    let (mut history, persona) = {
+        let db_conn = state.db.lock().unwrap();
+        (
+            queries::get_conversation_history(&db_conn, &user_message.conversation_id)?,
+            queries::get_conversation_persona(&db_conn, &user_message.conversation_id)?,
+        )
+    };
+    // Another reply to the same message may already have become the active leaf (compare mode).
+    if let Some(pos) = history.iter().position(|m| m.id == user_message.id) {
+        history.truncate(pos + 1);
+    }
+
+    if let Some(persona) = &persona {
+        if user_message.model.is_none() {
+            user_message.model = persona.model.clone();
+        }
+        if user_message.knowledge_base_selection.is_none() {
+            user_message.knowledge_base_selection = persona.knowledge_base_selection.clone();
+        }
+    }
+
+    let model_identifier = user_message.model.clone().ok_or_else(|| AppError::Config("No model specified in the request".to_string()))?;
+    let user_query = extract_text_from_content(&user_message.content);
+
+    let parts: Vec<&str> = model_identifier.split("::").collect();
+    if parts.len() != 2 { return Err(AppError::Config(format!("Invalid model identifier: {}", model_identifier))); }
+    let (provider_id, model_name) = (parts[0], parts[1]);
+
+    let provider = api_config.providers.iter().find(|p| p.id == provider_id).cloned().ok_or_else(|| AppError::Config(format!("Provider with ID {} not found", provider_id)))?;
+
+    let persona_prompt = match persona.as_ref().filter(|p| !p.system_prompt.trim().is_empty()) {
+        Some(persona) => Some(templates::expand(&app, &persona.system_prompt, &template_variables).await),
+        None => None,
+    };
+    let user_memory_prompt = user_memory::system_prompt_section(&state, &user_query)?;
+    let system_prompt = match (persona_prompt, user_memory_prompt) {
+        (Some(persona_prompt), Some(memory_prompt)) => Some(format!("{}\n\n{}", persona_prompt, memory_prompt)),
+        (persona_prompt, memory_prompt) => persona_prompt.or(memory_prompt),
+    };
+
+    let recalled = memory::recall(&state, &user_query, &user_message.conversation_id).await.unwrap_or_else(|e| {
+        log::warn!("[ChatService] Conversation memory recall failed: {}", e);
+        vec![]
+    });
+    let memory_context = (!recalled.is_empty()).then(|| {
+        let excerpts = recalled.iter().enumerate()
+            .map(|(i, source)| format!("[{}] From \"{}\":\n{}", i + 1, source.source_name, source.content_snippet))
+            .collect::<Vec<String>>()
+            .join("\n\n");
+        format!("Possibly relevant excerpts from the user's earlier conversations. Use them only if they help answer the current question:\n\n{}", excerpts)
+    });
+
+    let context_settings = queries::get_settings(&state.db.lock().unwrap())?.context;
+    let budget = context::context_budget(&api_config, provider_id, model_name, &context_settings)
+        .saturating_sub(system_prompt.as_deref().map_or(0, context::estimate_tokens))
+        .saturating_sub(memory_context.as_deref().map_or(0, context::estimate_tokens));
+    let prepared = context::prepare_context(&state, &user_message.conversation_id, history, budget).await?;
+
+    let mut processed_history = Vec::new();
+    if let Some(text) = system_prompt {
+        processed_history.push(("system".to_string(), vec![models::ChatMessageContentPart::Text { text }]));
+    }
+    if let Some(text) = memory_context {
+        processed_history.push(("system".to_string(), vec![models::ChatMessageContentPart::Text { text }]));
+    }
+    if let Some(summary) = prepared.summary {
+        let text = format!("Summary of the earlier part of this conversation:\n{}", summary);
+        processed_history.push(("system".to_string(), vec![models::ChatMessageContentPart::Text { text }]));
+    }
+    for msg in &prepared.messages {
+        let processed_content = process_content_for_llm(&state, &msg.content).await?;
+        processed_history.push((msg.role.clone(), processed_content));
+    }
+    if let Some(partial) = &options.resume_from {
+        let partial = queries::search_text_from_content(&[models::ChatMessageContentPart::Text { text: partial.clone() }]);
+        processed_history.push(("ai".to_string(), vec![models::ChatMessageContentPart::Text { text: partial }]));
+        let text = "Your previous reply was cut off. Continue it exactly where it stopped, without repeating any of it or commenting on the interruption.".to_string();
+        processed_history.push(("system".to_string(), vec![models::ChatMessageContentPart::Text { text }]));
+    }
+
+    let build_proxy_messages = || processed_history.iter().map(|(role, content)| {
+        let proxy_role = if role == "ai" { "assistant" } else { role };
+        ProxyMessage {
+            role: proxy_role.to_string(),
+            content
+        }
+    }).collect::<Vec<ProxyMessage>>();
+    let prompt_tokens = processed_history.iter().map(|(_, content)| context::estimate_content_tokens(content)).sum::<u32>();
+
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let url = llm_utils::chat_completions_url(&settings.execution.backend_url);
+    let sampling = persona.map(|p| SamplingParams {
+        temperature: p.temperature,
+        top_p: p.top_p,
+        max_tokens: p.max_tokens,
+        stop: p.stop,
+    }).unwrap_or_default();
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    state.running_chat_tasks.lock().unwrap().insert(ai_message_id.clone(), stop_flag.clone());
+
+    // Try the selected model, retrying transient errors, then the chat fallback chain. The client
+    // is told through `stream-retry` to discard what it has received so far for this message.
+    let candidates = failover::candidates(&settings, "chat", (provider.clone(), model_name.to_string()));
+    let started = Instant::now();
+    let mut reply = StreamedReply::default();
+    let mut reply_usage = CallUsage::estimated(prompt_tokens, 0);
+    let mut answered_by = format!("{}::{}", provider.id, model_name);
+    'candidates: for (index, (candidate_provider, candidate_model)) in candidates.iter().enumerate() {
+        for retry in 0..=settings.failover.max_retries {
+            if stop_flag.load(Ordering::SeqCst) {
+                break 'candidates;
+            }
+            let request_body = ProxyChatPayload {
+                model: candidate_model,
+                messages: build_proxy_messages(),
+                stream: true,
+                provider_config: candidate_provider,
+                knowledge_base_selection: user_message.knowledge_base_selection.clone(),
+                api_config: Some(&api_config),
+                sampling: sampling.clone(),
+            };
+
+            let call = UsageCall::start(candidate_provider, candidate_model, "chat", "chat");
+            reply = stream_reply(&app, &state, &url, &request_body, &ai_message_id, &stop_flag).await?;
+            let completion_tokens = context::estimate_tokens(&reply.full_response());
+            let attempt_usage = CallUsage::reported_or(reply.usage.as_ref(), || (prompt_tokens, completion_tokens));
+            reply_usage = attempt_usage;
+            call.finish(&state, attempt_usage, reply.error.as_ref().map(|e| e.message.clone()));
+            answered_by = format!("{}::{}", candidate_provider.id, candidate_model);
+
+            let Some(error) = reply.error.clone() else {
+                failover::record_success(&candidate_provider.id);
+                break 'candidates;
+            };
+            failover::record_failure(&candidate_provider.id, &settings.failover);
+
+            let retry_same = error.transient && retry < settings.failover.max_retries && !failover::is_circuit_open(&candidate_provider.id);
+            let next = if retry_same { Some((candidate_provider, candidate_model)) } else { candidates.get(index + 1).map(|(p, m)| (p, m)) };
+            let Some((next_provider, next_model)) = next else { break 'candidates };
+            log::warn!("[ChatService] {} failed for message {}: {}. Trying {} next.", answered_by, ai_message_id, error.message, next_model);
+            app.emit_all("stream-retry", json!({
+                "messageId": ai_message_id,
+                "model": format!("{}::{}", next_provider.id, next_model),
+                "reason": error.message,
+            }))?;
+            if !retry_same {
+                continue 'candidates;
             }
+            tokio::time::sleep(failover::backoff_delay(&settings.failover, retry)).await;
         }
     }
 
     state.running_chat_tasks.lock().unwrap().remove(&ai_message_id);
 
-    let full_response = if !thinking_parts.is_empty() {
-        format!("<think>{}</think>{}", thinking_parts, content_parts)
-    } else {
-        content_parts
+    let full_response = format!("{}{}", options.resume_from.as_deref().unwrap_or_default(), reply.full_response());
+    let generation_stats = models::GenerationStats {
+        latency_ms: started.elapsed().as_millis() as i64,
+        first_token_ms: reply.first_token_at.map(|at| at.duration_since(started).as_millis() as i64),
+        prompt_tokens: reply_usage.prompt_tokens,
+        completion_tokens: reply_usage.completion_tokens,
+        estimated: reply_usage.estimated,
     };
+    let mut sources = reply.sources;
+    let reply_error = reply.error.map(|e| e.to_chat_error(!full_response.trim().is_empty()));
+
+    if !recalled.is_empty() {
+        sources.get_or_insert_with(Vec::new).extend(recalled);
+    }
 
-    let suggestions = llm_utils::generate_suggestions(&state, &full_response).await.unwrap_or_default();
+    let suggestions = match reply_error {
+        None => llm_utils::generate_suggestions(&state, &full_response).await.unwrap_or_default(),
+        Some(_) => vec![],
+    };
 
     let final_ai_message = models::ChatMessage {
         id: ai_message_id.clone(),
@@ -183,14 +533,18 @@ pub async fn handle_message(
         content: vec![models::ChatMessageContentPart::Text { text: full_response.clone() }],
         timestamp: chrono::Utc::now().timestamp_millis(),
         sources: sources.clone(),
-        error: None,
+        error: reply_error.clone(),
         suggestions: Some(suggestions.clone()),
-        model: user_message.model.clone(),
+        model: Some(answered_by.clone()),
         knowledge_base_selection: user_message.knowledge_base_selection.clone(),
         is_executing: None,
         execution_output: None,
         agent_task_id: None,
         agent_task: None,
+        parent_id: Some(user_message.id.clone()),
+        sibling_ids: None,
+        is_pinned: false,
+        generation_stats: Some(generation_stats.clone()),
     };
 
     {
@@ -198,14 +552,40 @@ pub async fn handle_message(
         queries::save_message(&db_conn, &final_ai_message)?;
     }
 
-    app.emit_all("stream-end", json!({ "messageId": ai_message_id, "finalMessage": { "content": full_response, "sources": sources, "suggestions": suggestions, "error": null } }))?;
+    // Follow-ups only make sense for complete replies; a failed one is usually retried.
+    let follow_ups = reply_error.is_none() && !full_response.trim().is_empty() && options.follow_ups.swap(false, Ordering::SeqCst);
+    if follow_ups {
+        let state_clone = state.clone();
+        let (user_message, ai_message) = (user_message.clone(), final_ai_message.clone());
+        tokio::spawn(async move {
+            if let Err(e) = memory::remember_exchange(&state_clone, &user_message, &ai_message).await {
+                log::warn!("[ChatService] Failed to store exchange in conversation memory: {}", e);
+            }
+        });
+    }
+
+    if let Some(error) = &reply_error {
+        log::error!("[ChatService] Reply {} ended with an error ({:?}): {}", ai_message_id, error.kind, error.message);
+        app.emit_all("stream-error", json!({ "messageId": ai_message_id, "error": error }))?;
+    }
+    app.emit_all("stream-end", json!({ "messageId": ai_message_id, "finalMessage": { "content": full_response, "sources": sources, "suggestions": suggestions, "error": reply_error, "model": answered_by, "generationStats": generation_stats } }))?;
 
     let convo_result = {
         let db_conn = state.db.lock().unwrap();
         queries::get_conversation_by_id(&db_conn, &user_message.conversation_id)?
     };
 
-    if let Some(convo) = convo_result {
+    if follow_ups {
+        let (app_clone, state_clone) = (app.clone(), state.clone());
+        let (conversation_id, query, response) = (user_message.conversation_id.clone(), user_query.clone(), queries::search_text_from_content(&final_ai_message.content));
+        tokio::spawn(async move {
+            if let Err(e) = user_memory::propose_from_exchange(&app_clone, &state_clone, &conversation_id, &query, &response).await {
+                log::warn!("[ChatService] Failed to propose user memories: {}", e);
+            }
+        });
+    }
+
+    if let Some(convo) = convo_result.filter(|_| follow_ups) {
         if convo.title == "New Chat..." {
             let title = llm_utils::generate_title_for_conversation(&state, &user_query, &full_response).await.unwrap_or_else(|_| "Untitled Chat".to_string());
             let updated_convo = {
@@ -217,5 +597,9 @@ pub async fn handle_message(
         }
     }
 
+    if let Err(e) = usage::check_budget(&app, &state) {
+        log::warn!("[ChatService] Failed to check the usage budget: {}", e);
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -1,6 +1,11 @@
 // src-tauri/src/services/chat/mod.rs
+pub mod compare;
+pub mod context;
 pub mod llm_utils;
+pub mod memory;
 pub mod message_handler;
+pub mod templates;
+pub mod user_memory;
 
 pub use llm_utils::generate_title_for_conversation;
-pub use message_handler::handle_message;
\ No newline at end of file
+pub use message_handler::{generate_reply, handle_message};
\ No newline at end of file
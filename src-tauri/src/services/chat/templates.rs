@@ -0,0 +1,94 @@
+// src-tauri/src/services/chat/templates.rs
+use crate::system::clipboard;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+static VARIABLE_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+/// `{{name}}` placeholders whose name is a dotted path into a JSON value, as webhook and workflow
+/// templates use them, e.g. `{{steps.fetch.json.items.0}}`. `{{secret:NAME}}` matches too.
+pub static PATH_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.:\-]+)\s*\}\}").unwrap());
+
+/// The value at a dotted `path` in `value`; numeric keys index into arrays.
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        other => other.get(key),
+    })
+}
+
+/// A value as template text: strings as they are, null as nothing, anything else as JSON.
+pub fn text_of(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Returns the names of all `{{variable}}` placeholders in `template`, in order of first appearance.
+pub fn find_variables(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in VARIABLE_PATTERN.captures_iter(template) {
+        let name = caps[1].to_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Expands `{{variable}}` placeholders in `template`.
+/// Caller-supplied `variables` take precedence; the built-ins `date`, `time`, `datetime`,
+/// `weekday` and `clipboard` are resolved at call time. `selection` is the text the copilot
+/// was opened on, which the copilot passes in, and empty anywhere else.
+/// Unknown placeholders are left untouched.
+pub async fn expand(app: &AppHandle, template: &str, variables: &HashMap<String, String>) -> String {
+    let names = find_variables(template);
+    if names.is_empty() {
+        return template.to_string();
+    }
+
+    let now = chrono::Local::now();
+    let mut values: HashMap<String, String> = HashMap::new();
+    for name in names {
+        let value = match variables.get(&name) {
+            Some(value) => Some(value.clone()),
+            None => match name.as_str() {
+                "date" => Some(now.format("%Y-%m-%d").to_string()),
+                "time" => Some(now.format("%H:%M").to_string()),
+                "datetime" => Some(now.format("%Y-%m-%d %H:%M").to_string()),
+                "weekday" => Some(now.format("%A").to_string()),
+                "clipboard" => Some(read_clipboard_text(app).await),
+                "selection" => Some(String::new()),
+                _ => None,
+            },
+        };
+        if let Some(value) = value {
+            values.insert(name, value);
+        }
+    }
+
+    fill(template, &values)
+}
+
+/// Replaces placeholders with the given values only, leaving unknown ones untouched.
+pub fn fill(template: &str, values: &HashMap<String, String>) -> String {
+    VARIABLE_PATTERN.replace_all(template, |caps: &regex::Captures| {
+        values.get(&caps[1]).cloned().unwrap_or_else(|| caps[0].to_string())
+    }).into_owned()
+}
+
+async fn read_clipboard_text(app: &AppHandle) -> String {
+    match clipboard::read_clipboard(app).await {
+        Ok(Some(payload)) if payload.content_type == "text" => payload.content.as_str().unwrap_or_default().to_string(),
+        Ok(_) => String::new(),
+        Err(e) => {
+            log::warn!("[ChatService] Could not read clipboard for template expansion: {}", e);
+            String::new()
+        }
+    }
+}
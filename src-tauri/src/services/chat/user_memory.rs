@@ -0,0 +1,100 @@
+// src-tauri/src/services/chat/user_memory.rs
+use super::llm_utils;
+use crate::{
+    database::{models, queries},
+    error::Result,
+    state::AppState,
+};
+use rusqlite::Connection;
+use serde_json::json;
+use std::collections::HashSet;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "that", "this", "from", "have", "has", "are", "was", "were", "you", "your",
+    "user", "uses", "use", "prefers", "likes", "works", "about", "into", "what", "how", "why", "when", "can",
+];
+
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.chars().count() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Picks the active memories worth sending with `query`: all preferences, plus facts that share
+/// at least one keyword with the query, most overlapping first, capped at `max`.
+pub fn relevant_memories(conn: &Connection, query: &str, max: usize) -> Result<Vec<models::UserMemory>> {
+    let query_words = keywords(query);
+    let mut scored: Vec<(usize, models::UserMemory)> = queries::list_user_memories(conn, Some("active"))?
+        .into_iter()
+        .filter_map(|memory| {
+            if memory.category == "preference" {
+                return Some((usize::MAX, memory));
+            }
+            let overlap = keywords(&memory.content).intersection(&query_words).count();
+            (overlap > 0).then_some((overlap, memory))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.updated_at.cmp(&a.1.updated_at)));
+    Ok(scored.into_iter().take(max).map(|(_, memory)| memory).collect())
+}
+
+/// System prompt section listing what the assistant knows about the user, or `None` if nothing applies.
+pub fn system_prompt_section(state: &AppState, query: &str) -> Result<Option<String>> {
+    let conn = state.db.lock().unwrap();
+    let settings = queries::get_settings(&conn)?.user_memory;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let memories = relevant_memories(&conn, query, settings.max_injected)?;
+    if memories.is_empty() {
+        return Ok(None);
+    }
+    let lines = memories.iter().map(|m| format!("- {}", m.content)).collect::<Vec<String>>().join("\n");
+    Ok(Some(format!("What you know about the user from earlier conversations (use it when relevant, don't mention it otherwise):\n{}", lines)))
+}
+
+/// Post-reply step: asks the model whether the exchange revealed something worth remembering and
+/// stores the results as "proposed" memories for the user to review. Emits `user-memory-proposed`.
+pub async fn propose_from_exchange(app: &AppHandle, state: &AppState, conversation_id: &str, user_query: &str, ai_response: &str) -> Result<()> {
+    let (settings, known) = {
+        let conn = state.db.lock().unwrap();
+        let settings = queries::get_settings(&conn)?.user_memory;
+        let known: Vec<String> = queries::list_user_memories(&conn, None)?.into_iter().map(|m| m.content).collect();
+        (settings, known)
+    };
+    if !settings.enabled || !settings.propose_after_reply || user_query.trim().is_empty() {
+        return Ok(());
+    }
+
+    let candidates = llm_utils::extract_user_memories(state, user_query, ai_response, &known).await?;
+    let known_lower: HashSet<String> = known.iter().map(|k| k.to_lowercase()).collect();
+    let now = chrono::Utc::now().timestamp_millis();
+    let proposed: Vec<models::UserMemory> = candidates.into_iter()
+        .filter(|(content, _)| !known_lower.contains(&content.to_lowercase()))
+        .map(|(content, category)| models::UserMemory {
+            id: Uuid::new_v4().to_string(),
+            content,
+            category,
+            status: "proposed".to_string(),
+            source_conversation_id: Some(conversation_id.to_string()),
+            created_at: now,
+            updated_at: now,
+        })
+        .collect();
+    if proposed.is_empty() {
+        return Ok(());
+    }
+
+    {
+        let conn = state.db.lock().unwrap();
+        for memory in &proposed {
+            queries::save_user_memory(&conn, memory)?;
+        }
+    }
+    log::info!("[ChatService] Proposed {} user memory item(s) from conversation {}", proposed.len(), conversation_id);
+    app.emit_all("user-memory-proposed", json!({ "conversationId": conversation_id, "memories": proposed }))?;
+    Ok(())
+}
@@ -0,0 +1,381 @@
+// src-tauri/src/services/export.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services::attachments,
+    state::AppState,
+};
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+
+static THINK_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<think>(.*?)(?:</think>|$)").unwrap());
+
+const PDF_RENDERERS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "microsoft-edge",
+    "msedge",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe",
+    "C:\\Program Files (x86)\\Microsoft\\Edge\\Application\\msedge.exe",
+];
+
+/// How long the headless browser may take to print one conversation.
+const PDF_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The only raw HTML an export emits itself, around thinking blocks.
+const THINKING_TAGS: &[&str] = &["<details><summary>Thinking</summary>", "</details>"];
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,'Noto Sans','PingFang SC','Microsoft YaHei',sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;line-height:1.6;color:#1f2328}\
+h1{border-bottom:1px solid #d0d7de;padding-bottom:.3em}\
+h2{font-size:1.1rem;margin-top:2rem;color:#57606a}\
+pre{background:#f6f8fa;padding:1em;overflow:auto;border-radius:6px}\
+code{font-family:ui-monospace,SFMono-Regular,Menlo,monospace;font-size:.9em}\
+blockquote{color:#57606a;border-left:.25em solid #d0d7de;margin:0;padding:0 1em}\
+details{background:#f6f8fa;border-radius:6px;padding:.5em 1em;margin:.5em 0}\
+img{max-width:100%}\
+table{border-collapse:collapse}td,th{border:1px solid #d0d7de;padding:.3em .6em}\
+.footnote-definition{font-size:.9em;color:#57606a}";
+
+/// Everything exported for one conversation; also the shape of the JSON export.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConversationDocument {
+    conversation: models::Conversation,
+    messages: Vec<models::ChatMessage>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    agent_tasks: HashMap<String, Value>,
+    exported_at: i64,
+}
+
+async fn load_document(state: &AppState, conversation_id: &str, options: &models::ConversationExportOptions) -> Result<ConversationDocument> {
+    let (mut conversation, messages, backend_url) = {
+        let conn = state.db.lock().unwrap();
+        let conversation = queries::get_conversation_by_id(&conn, conversation_id)?
+            .ok_or_else(|| AppError::Database(format!("Conversation {} not found", conversation_id)))?;
+        let messages = queries::get_conversation_history(&conn, conversation_id)?;
+        (conversation, messages, queries::get_settings(&conn)?.execution.backend_url)
+    };
+    if options.include_artifacts {
+        conversation.artifacts = queries::get_artifacts_for_conversation(&state.db.lock().unwrap(), conversation_id)?;
+    }
+
+    // Agent tasks live in the backend; an unavailable backend only drops the reports.
+    let mut agent_tasks = HashMap::new();
+    if options.include_agent_reports {
+        for task_id in messages.iter().filter_map(|m| m.agent_task_id.as_ref()) {
+            let url = format!("{}/api/v1/agent/get-task-status/{}", backend_url, task_id);
+            match state.http_client.get(url).send().await.and_then(|r| r.error_for_status()) {
+                Ok(response) => match response.json::<Value>().await {
+                    Ok(task) => { agent_tasks.insert(task_id.clone(), task); }
+                    Err(e) => log::warn!("[Export] Invalid agent task {} payload: {}", task_id, e),
+                },
+                Err(e) => log::warn!("[Export] Could not fetch agent task {}: {}", task_id, e),
+            }
+        }
+    }
+
+    Ok(ConversationDocument { conversation, messages, agent_tasks, exported_at: chrono::Utc::now().timestamp_millis() })
+}
+
+/// Returns a URL usable in the exported file: local images become data URIs when embedding.
+fn image_src(url: &str, embed: bool) -> String {
+    if !embed || url.starts_with("http") || url.starts_with("data:") {
+        return url.to_string();
+    }
+    match fs::read(url) {
+        Ok(bytes) => format!("data:{};base64,{}", attachments::detect_mime(url, &bytes), general_purpose::STANDARD.encode(&bytes)),
+        Err(e) => {
+            log::warn!("[Export] Could not embed image {}: {}", url, e);
+            url.to_string()
+        }
+    }
+}
+
+/// `url` as a Markdown link destination. The angle brackets keep spaces and parentheses in local
+/// paths from ending the link early.
+fn link_destination(url: &str) -> String {
+    let escaped = url.replace(['\n', '\r'], "").replace('\\', "\\\\").replace('<', "\\<").replace('>', "\\>");
+    format!("<{}>", escaped)
+}
+
+fn format_timestamp(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Renders `<think>` blocks as quoted "Thinking" sections (or as `<details>` for HTML) or drops them.
+fn render_thinking(text: &str, include: bool, for_html: bool) -> String {
+    THINK_BLOCK.replace_all(text, |caps: &regex::Captures| {
+        let thought = caps[1].trim();
+        if !include || thought.is_empty() {
+            String::new()
+        } else if for_html {
+            format!("<details><summary>Thinking</summary>\n\n{}\n\n</details>\n\n", thought)
+        } else {
+            let quoted = thought.lines().map(|l| format!("> {}", l)).collect::<Vec<_>>().join("\n");
+            format!("> **Thinking**\n>\n{}\n\n", quoted)
+        }
+    }).trim().to_string()
+}
+
+fn render_markdown(doc: &ConversationDocument, options: &models::ConversationExportOptions, for_html: bool) -> String {
+    let mut out = format!("# {}\n\n", doc.conversation.title);
+    out.push_str(&format!("*Created {} · exported {}*\n\n", format_timestamp(doc.conversation.created_at), format_timestamp(doc.exported_at)));
+
+    let mut footnotes: Vec<String> = Vec::new();
+    for message in &doc.messages {
+        let speaker = if message.role == "user" { "You" } else { "Assistant" };
+        let model = message.model.as_deref().and_then(|m| m.split("::").last()).map(|m| format!(" · {}", m)).unwrap_or_default();
+        out.push_str(&format!("## {} — {}{}\n\n", speaker, format_timestamp(message.timestamp), model));
+
+        for part in &message.content {
+            match part {
+                models::ChatMessageContentPart::Text { text } => {
+                    let text = render_thinking(text, options.include_thinking, for_html);
+                    if !text.is_empty() {
+                        out.push_str(&text);
+                        out.push_str("\n\n");
+                    }
+                }
+                models::ChatMessageContentPart::ImageUrl { image_url } => {
+                    out.push_str(&format!("![image]({})\n\n", link_destination(&image_src(&image_url.url, options.embed_images))));
+                }
+                models::ChatMessageContentPart::File { file } => {
+                    out.push_str(&format!("📎 {} ({})\n\n", file.name, file.mime_type));
+                }
+                models::ChatMessageContentPart::Audio { audio, transcript } => {
+                    out.push_str(&format!("🎤 {}\n\n", audio.name));
+                    if let Some(transcript) = transcript.as_deref().filter(|t| !t.trim().is_empty()) {
+                        out.push_str(&format!("> {}\n\n", transcript.trim().replace('\n', "\n> ")));
+                    }
+                }
+            }
+        }
+
+        if options.include_sources {
+            if let Some(sources) = message.sources.as_ref().filter(|s| !s.is_empty()) {
+                let refs: Vec<String> = sources.iter().map(|source| {
+                    footnotes.push(format!("{} — `{}`\n    > {}", source.source_name, source.file_path, source.content_snippet.replace('\n', " ")));
+                    format!("[^{}]", footnotes.len())
+                }).collect();
+                out.push_str(&format!("Sources: {}\n\n", refs.join(" ")));
+            }
+        }
+
+        if let Some(report) = message.agent_task_id.as_ref()
+            .and_then(|id| doc.agent_tasks.get(id))
+            .and_then(|task| task.get("finalReport").or_else(|| task.get("final_report")))
+            .and_then(|r| r.as_str())
+        {
+            out.push_str(&format!("### Agent report\n\n{}\n\n", report.trim()));
+        }
+    }
+
+    if !doc.conversation.artifacts.is_empty() {
+        out.push_str("## Artifacts\n\n");
+        for artifact in &doc.conversation.artifacts {
+            out.push_str(&format!("### {} — {}\n\n**Prompt:** {}\n\n", artifact.artifact_type, artifact.model_used, artifact.prompt));
+            if artifact.artifact_type == "image" {
+                out.push_str(&format!("![{}]({})\n\n", artifact.artifact_type, image_src(&artifact.file_path, options.embed_images)));
+            } else {
+                out.push_str(&format!("File: `{}`\n\n", artifact.file_path));
+            }
+        }
+    }
+
+    for (i, note) in footnotes.iter().enumerate() {
+        out.push_str(&format!("[^{}]: {}\n", i + 1, note));
+    }
+    out
+}
+
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn is_script_url(url: &str) -> bool {
+    let scheme = url.trim_start().split(':').next().unwrap_or_default().to_ascii_lowercase();
+    url.contains(':') && matches!(scheme.as_str(), "javascript" | "vbscript" | "data")
+}
+
+fn render_html(doc: &ConversationDocument, options: &models::ConversationExportOptions) -> String {
+    let markdown = render_markdown(doc, options, true);
+    // Raw HTML in messages is shown as text: a conversation can contain anything a model or a
+    // pasted page produced, and the file is opened in a browser.
+    let events = Parser::new_ext(&markdown, Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS)
+        .map(|event| match event {
+            Event::Html(raw) if THINKING_TAGS.contains(&raw.trim()) => Event::Html(raw),
+            Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(CowStr::from(raw.into_string())),
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) if is_script_url(&dest_url) => {
+                Event::Start(Tag::Link { link_type, dest_url: CowStr::Borrowed("#"), title, id })
+            }
+            other => other,
+        });
+    let mut body = String::new();
+    html::push_html(&mut body, events);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        html_escape(&doc.conversation.title), HTML_STYLE, body
+    )
+}
+
+async fn find_pdf_renderer() -> Option<&'static str> {
+    for candidate in PDF_RENDERERS {
+        let probe = Command::new(candidate).arg("--version").kill_on_drop(true).output();
+        if let Ok(Ok(output)) = tokio::time::timeout(Duration::from_secs(10), probe).await {
+            if output.status.success() {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// Prints HTML to PDF with a locally installed Chromium-based browser, which handles any script
+/// and embedded images the same way the app's own webview does. The browser is stopped after
+/// `PDF_TIMEOUT`.
+async fn html_to_pdf(html: &str, output: &Path) -> Result<()> {
+    let renderer = find_pdf_renderer().await
+        .ok_or_else(|| AppError::Config("PDF export needs Chrome, Chromium or Edge installed; export to HTML instead".to_string()))?;
+
+    let html_path = std::env::temp_dir().join(format!("nexus-export-{}.html", uuid::Uuid::new_v4()));
+    fs::write(&html_path, html)?;
+    let output_abs = if output.is_absolute() { output.to_path_buf() } else { std::env::current_dir()?.join(output) };
+    let result = Command::new(renderer)
+        .args(["--headless", "--disable-gpu", "--no-pdf-header-footer"])
+        .arg(format!("--print-to-pdf={}", output_abs.display()))
+        .arg(url::Url::from_file_path(&html_path).map(|u| u.to_string()).unwrap_or_else(|_| html_path.display().to_string()))
+        .kill_on_drop(true)
+        .output();
+    let result = tokio::time::timeout(PDF_TIMEOUT, result).await;
+    let _ = fs::remove_file(&html_path);
+
+    let result = result.map_err(|_| AppError::Timeout(format!("PDF rendering took longer than {}s", PDF_TIMEOUT.as_secs())))??;
+    if !result.status.success() || !output_abs.exists() {
+        return Err(AppError::Internal(format!("PDF rendering failed: {}", String::from_utf8_lossy(&result.stderr))));
+    }
+    Ok(())
+}
+
+fn extension_for(format: &str) -> Result<&'static str> {
+    match format {
+        "markdown" | "md" => Ok("md"),
+        "html" => Ok("html"),
+        "json" => Ok("json"),
+        "pdf" => Ok("pdf"),
+        _ => Err(AppError::Config(format!("Unsupported export format: {}", format))),
+    }
+}
+
+async fn render_to_bytes(doc: &ConversationDocument, format: &str, options: &models::ConversationExportOptions) -> Result<Vec<u8>> {
+    Ok(match extension_for(format)? {
+        "md" => render_markdown(doc, options, false).into_bytes(),
+        "html" => render_html(doc, options).into_bytes(),
+        "json" => serde_json::to_vec_pretty(doc)?,
+        _ => {
+            let tmp = std::env::temp_dir().join(format!("nexus-export-{}.pdf", uuid::Uuid::new_v4()));
+            html_to_pdf(&render_html(doc, options), &tmp).await?;
+            let bytes = fs::read(&tmp)?;
+            let _ = fs::remove_file(&tmp);
+            bytes
+        }
+    })
+}
+
+/// Exports the active branch of one conversation to `path` as "markdown", "html", "json" or "pdf".
+pub async fn export_conversation(state: &AppState, conversation_id: &str, format: &str, path: &Path, options: &models::ConversationExportOptions) -> Result<()> {
+    extension_for(format)?;
+    let doc = load_document(state, conversation_id, options).await?;
+    if format == "pdf" {
+        html_to_pdf(&render_html(&doc, options), path).await?;
+    } else {
+        fs::write(path, render_to_bytes(&doc, format, options).await?)?;
+    }
+    log::info!("[Export] Exported conversation {} as {} to {}", conversation_id, format, path.display());
+    Ok(())
+}
+
+fn file_name_for(conversation: &models::Conversation, ext: &str) -> String {
+    let title: String = conversation.title.chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' })
+        .collect::<String>()
+        .trim()
+        .chars()
+        .take(60)
+        .collect();
+    let title = if title.is_empty() { "conversation".to_string() } else { title };
+    format!("{} ({}).{}", title, conversation.id.chars().take(8).collect::<String>(), ext)
+}
+
+/// Exports every conversation into the directory `path`, or into a zip archive if `path` ends in `.zip`.
+/// Returns the number of conversations written.
+pub async fn export_all_conversations(state: &AppState, format: &str, path: &Path, options: &models::ConversationExportOptions) -> Result<usize> {
+    let ext = extension_for(format)?;
+    let conversations = queries::list_conversations(&state.db.lock().unwrap())?;
+    let as_zip = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+
+    let mut zip = if as_zip {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Some(zip::ZipWriter::new(fs::File::create(path)?))
+    } else {
+        fs::create_dir_all(path)?;
+        None
+    };
+
+    let mut exported = 0;
+    for conversation in &conversations {
+        let doc = load_document(state, &conversation.id, options).await?;
+        let bytes = render_to_bytes(&doc, format, options).await?;
+        let name = file_name_for(conversation, ext);
+        match zip.as_mut() {
+            Some(zip) => {
+                zip.start_file(name, zip::write::SimpleFileOptions::default())
+                    .map_err(|e| AppError::Internal(format!("Failed to write zip entry: {}", e)))?;
+                zip.write_all(&bytes)?;
+            }
+            None => fs::write(PathBuf::from(path).join(name), bytes)?,
+        }
+        exported += 1;
+    }
+
+    if let Some(zip) = zip {
+        zip.finish().map_err(|e| AppError::Internal(format!("Failed to finish zip archive: {}", e)))?;
+    }
+    log::info!("[Export] Exported {} conversation(s) as {} to {}", exported, format, path.display());
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_image_paths_survive_as_link_destinations() {
+        for path in [r"C:\My Pictures\cat (1).png", "/home/me/a <b>.png", "/tmp/x_y*z.png"] {
+            let markdown = format!("![image]({})", link_destination(path));
+            let destination = Parser::new(&markdown).find_map(|event| match event {
+                Event::Start(Tag::Image { dest_url, .. }) => Some(dest_url.to_string()),
+                _ => None,
+            });
+            assert_eq!(destination.as_deref(), Some(path));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// src-tauri/src/services/failover.rs
+use crate::{
+    database::models,
+    error::{AppError, Result},
+};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Why one attempt failed, and whether trying the same model again may help.
+#[derive(Debug, Clone)]
+pub struct AttemptError {
+    pub message: String,
+    pub transient: bool,
+    pub kind: models::ChatErrorKind,
+    pub status: Option<u16>,
+}
+
+impl AttemptError {
+    pub fn permanent(message: impl Into<String>) -> Self {
+        Self { message: message.into(), transient: false, kind: models::ChatErrorKind::Unknown, status: None }
+    }
+
+    /// Classifies an error response relayed by the backend proxy from its status and body.
+    pub fn from_status(status: u16, body: &str) -> Self {
+        let kind = match status {
+            401 | 403 => models::ChatErrorKind::Auth,
+            429 => models::ChatErrorKind::RateLimit,
+            _ if is_context_length_error(body) => models::ChatErrorKind::ContextLength,
+            400..=499 => models::ChatErrorKind::ProviderClient,
+            _ => models::ChatErrorKind::ProviderServer,
+        };
+        Self {
+            message: format!("Proxy returned {}: {}", status, body.chars().take(500).collect::<String>()),
+            transient: kind != models::ChatErrorKind::ContextLength && is_transient_status(status),
+            kind,
+            status: Some(status),
+        }
+    }
+
+    /// Classifies an error the proxy reported inside an otherwise successful stream.
+    pub fn from_message(message: impl Into<String>, status: Option<u16>) -> Self {
+        let message = message.into();
+        match status {
+            Some(status) => Self::from_status(status, &message),
+            None if is_context_length_error(&message) => Self { kind: models::ChatErrorKind::ContextLength, ..Self::permanent(message) },
+            None => Self { transient: true, kind: models::ChatErrorKind::ProviderServer, ..Self::permanent(message) },
+        }
+    }
+
+    /// The error as stored on the AI message; `partial` says whether any text arrived first.
+    pub fn to_chat_error(&self, partial: bool) -> models::ChatError {
+        models::ChatError {
+            status: self.status,
+            partial,
+            ..models::ChatError::new(self.kind, self.message.clone())
+        }
+    }
+}
+
+pub fn is_transient_status(status: u16) -> bool {
+    status == 408 || status == 429 || status >= 500
+}
+
+/// Providers word this differently; these cover OpenAI, Anthropic, Gemini, Mistral and llama.cpp.
+fn is_context_length_error(text: &str) -> bool {
+    let text = text.to_lowercase();
+    ["context_length_exceeded", "context length", "context window", "maximum context", "too many tokens", "prompt is too long", "exceeds the context"]
+        .iter()
+        .any(|needle| text.contains(needle))
+}
+
+impl From<reqwest::Error> for AttemptError {
+    fn from(e: reqwest::Error) -> Self {
+        if let Some(status) = e.status() {
+            return Self::from_status(status.as_u16(), &e.to_string());
+        }
+        // Every model call goes through the local backend, so a refused connection means it is down.
+        let kind = if e.is_connect() { models::ChatErrorKind::BackendUnavailable } else { models::ChatErrorKind::Network };
+        let transient = e.is_timeout() || e.is_connect() || e.is_body();
+        Self { message: e.to_string(), transient, kind, status: None }
+    }
+}
+
+#[derive(Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+static BREAKERS: Lazy<Mutex<HashMap<String, Breaker>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// True while the provider's circuit is open, i.e. it failed too often recently and is being skipped.
+pub fn is_circuit_open(provider_id: &str) -> bool {
+    BREAKERS.lock().unwrap().get(provider_id)
+        .and_then(|b| b.open_until)
+        .is_some_and(|until| until > Instant::now())
+}
+
+pub fn record_success(provider_id: &str) {
+    BREAKERS.lock().unwrap().remove(provider_id);
+}
+
+pub fn record_failure(provider_id: &str, settings: &models::FailoverSettings) {
+    let mut breakers = BREAKERS.lock().unwrap();
+    let breaker = breakers.entry(provider_id.to_string()).or_default();
+    breaker.consecutive_failures += 1;
+    if settings.breaker_failure_threshold > 0 && breaker.consecutive_failures >= settings.breaker_failure_threshold {
+        log::warn!(
+            "[Failover] Provider {} failed {} times in a row; skipping it for {}s",
+            provider_id, breaker.consecutive_failures, settings.breaker_cooldown_secs
+        );
+        breaker.open_until = Some(Instant::now() + Duration::from_secs(settings.breaker_cooldown_secs));
+        breaker.consecutive_failures = 0;
+    }
+}
+
+/// Delay before retry number `attempt` (0-based): doubles from the initial backoff up to the maximum.
+pub fn backoff_delay(settings: &models::FailoverSettings, attempt: u32) -> Duration {
+    let delay = settings.initial_backoff_ms.saturating_mul(1u64 << attempt.min(16));
+    Duration::from_millis(delay.min(settings.max_backoff_ms))
+}
+
+/// The primary model followed by the assignment's fallback chain, without duplicates or entries
+/// whose provider no longer exists. Providers with an open circuit are moved to the end, so they
+/// are only tried when nothing else is left.
+pub fn candidates(settings: &models::Settings, assignment: &str, primary: (models::ApiProvider, String)) -> Vec<(models::ApiProvider, String)> {
+    let mut chain = vec![primary];
+    for endpoint in settings.failover.fallbacks.get(assignment).into_iter().flatten() {
+        if chain.iter().any(|(p, m)| p.id == endpoint.provider_id && *m == endpoint.model_name) {
+            continue;
+        }
+        match settings.api_config.providers.iter().find(|p| p.id == endpoint.provider_id) {
+            Some(provider) => chain.push((provider.clone(), endpoint.model_name.clone())),
+            None => log::warn!("[Failover] Fallback provider {} for '{}' not found", endpoint.provider_id, assignment),
+        }
+    }
+    let (closed, open): (Vec<_>, Vec<_>) = chain.into_iter().partition(|(p, _)| !is_circuit_open(&p.id));
+    closed.into_iter().chain(open).collect()
+}
+
+/// Runs `attempt` against each candidate in turn, retrying transient errors with backoff.
+/// Returns the result together with the provider and model that produced it.
+pub async fn run<T, F, Fut>(
+    settings: &models::FailoverSettings,
+    candidates: Vec<(models::ApiProvider, String)>,
+    mut attempt: F,
+) -> Result<(T, models::ApiProvider, String)>
+where
+    F: FnMut(models::ApiProvider, String) -> Fut,
+    Fut: Future<Output = std::result::Result<T, AttemptError>>,
+{
+    let mut last_error: Option<AttemptError> = None;
+    for (provider, model) in candidates {
+        for retry in 0..=settings.max_retries {
+            match attempt(provider.clone(), model.clone()).await {
+                Ok(value) => {
+                    record_success(&provider.id);
+                    return Ok((value, provider, model));
+                }
+                Err(e) => {
+                    record_failure(&provider.id, settings);
+                    let give_up = !e.transient || retry == settings.max_retries || is_circuit_open(&provider.id);
+                    log::warn!(
+                        "[Failover] {} on {} failed (attempt {}): {}{}",
+                        model, provider.name, retry + 1, e.message, if give_up { "" } else { "; retrying" }
+                    );
+                    last_error = Some(e);
+                    if give_up {
+                        break;
+                    }
+                    tokio::time::sleep(backoff_delay(settings, retry)).await;
+                }
+            }
+        }
+    }
+    Err(AppError::ApiClient(last_error.map_or_else(|| "No model available".to_string(), |e| e.message)))
+}
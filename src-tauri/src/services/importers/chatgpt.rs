@@ -0,0 +1,118 @@
+// src-tauri/src/services/importers/chatgpt.rs
+// ChatGPT data export (`conversations.json`). Each conversation is a tree in `mapping`;
+// `current_node` is the leaf of the branch that was last shown.
+use super::{ExportArchive, ImportedConversation, ImportedImage, ImportedMessage};
+use crate::error::{AppError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn seconds_to_millis(value: Option<&Value>) -> Option<i64> {
+    value.and_then(Value::as_f64).map(|secs| (secs * 1000.0) as i64)
+}
+
+/// `file-service://file-abc123` and `sediment://file_abc123` both point at an exported file
+/// whose name starts with the file ID.
+fn asset_file_id(pointer: &str) -> &str {
+    pointer.rsplit("://").next().unwrap_or(pointer)
+}
+
+fn parse_content(content: &Value, archive: &mut ExportArchive) -> Result<(String, Vec<ImportedImage>)> {
+    let mut texts = Vec::new();
+    let mut images = Vec::new();
+    match content.get("content_type").and_then(Value::as_str).unwrap_or("text") {
+        "text" | "multimodal_text" => {
+            for part in content.get("parts").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(text) = part.as_str() {
+                    texts.push(text.to_string());
+                } else if part.get("content_type").and_then(Value::as_str) == Some("image_asset_pointer") {
+                    let pointer = part.get("asset_pointer").and_then(Value::as_str).unwrap_or_default();
+                    let file_id = asset_file_id(pointer);
+                    if file_id.is_empty() {
+                        // An empty prefix would match whatever file comes first in the export.
+                        images.push(ImportedImage::Missing("image without a file reference".to_string()));
+                        continue;
+                    }
+                    match archive.find_by_prefix(file_id)? {
+                        Some((name, data)) => images.push(ImportedImage::File { name, data }),
+                        None => images.push(ImportedImage::Missing(file_id.to_string())),
+                    }
+                }
+            }
+        }
+        "code" => {
+            let language = content.get("language").and_then(Value::as_str).filter(|l| *l != "unknown").unwrap_or("");
+            let text = content.get("text").and_then(Value::as_str).unwrap_or_default();
+            texts.push(format!("```{}\n{}\n```", language, text));
+        }
+        "thoughts" => {
+            let thoughts: Vec<&str> = content.get("thoughts").and_then(Value::as_array).into_iter().flatten()
+                .filter_map(|t| t.get("content").and_then(Value::as_str))
+                .collect();
+            if !thoughts.is_empty() {
+                texts.push(format!("<think>\n{}\n</think>", thoughts.join("\n\n")));
+            }
+        }
+        _ => {}
+    }
+    Ok((texts.join("\n\n"), images))
+}
+
+pub fn parse(data: &[u8], archive: &mut ExportArchive, errors: &mut Vec<String>) -> Result<Vec<ImportedConversation>> {
+    let raw: Vec<Value> = serde_json::from_slice(data)
+        .map_err(|e| AppError::Parse(format!("Invalid ChatGPT export: {}", e)))?;
+
+    let mut conversations = Vec::new();
+    for conversation in raw {
+        let Some(source_id) = conversation.get("conversation_id").or_else(|| conversation.get("id")).and_then(Value::as_str) else {
+            errors.push("Skipped a ChatGPT conversation without an ID".to_string());
+            continue;
+        };
+        let Some(mapping) = conversation.get("mapping").and_then(Value::as_object) else {
+            errors.push(format!("ChatGPT conversation {} has no messages", source_id));
+            continue;
+        };
+        let created_at = seconds_to_millis(conversation.get("create_time")).unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+
+        let mut messages = Vec::new();
+        let mut skipped_parents = HashMap::new();
+        for (node_id, node) in mapping {
+            let parent = node.get("parent").and_then(Value::as_str).map(str::to_string);
+            let message = node.get("message").filter(|m| !m.is_null());
+            let role = message.and_then(|m| m.pointer("/author/role")).and_then(Value::as_str);
+            let hidden = message.and_then(|m| m.pointer("/metadata/is_visually_hidden_from_conversation")).and_then(Value::as_bool).unwrap_or(false);
+
+            let (Some(message), Some(role @ ("user" | "assistant")), false) = (message, role, hidden) else {
+                skipped_parents.insert(node_id.clone(), parent);
+                continue;
+            };
+            let (text, images) = match message.get("content") {
+                Some(content) => parse_content(content, archive)?,
+                None => (String::new(), vec![]),
+            };
+            if text.trim().is_empty() && images.is_empty() {
+                skipped_parents.insert(node_id.clone(), parent);
+                continue;
+            }
+
+            messages.push(ImportedMessage {
+                source_id: node_id.clone(),
+                parent_source_id: parent,
+                role: if role == "user" { "user" } else { "ai" }.to_string(),
+                text,
+                images,
+                timestamp: seconds_to_millis(message.get("create_time")).unwrap_or(created_at),
+                model: message.pointer("/metadata/model_slug").and_then(Value::as_str).map(str::to_string),
+            });
+        }
+
+        conversations.push(ImportedConversation {
+            source_id: source_id.to_string(),
+            title: conversation.get("title").and_then(Value::as_str).filter(|t| !t.trim().is_empty()).unwrap_or("Imported chat").to_string(),
+            created_at,
+            messages,
+            skipped_parents,
+            current_leaf: conversation.get("current_node").and_then(Value::as_str).map(str::to_string),
+        });
+    }
+    Ok(conversations)
+}
@@ -0,0 +1,101 @@
+// src-tauri/src/services/importers/claude.rs
+// Claude data export (`conversations.json`). Messages are listed in order; newer exports link
+// edits and retries through `parent_message_uuid`. Uploaded images are not part of the export.
+use super::{ImportedConversation, ImportedImage, ImportedMessage};
+use crate::error::{AppError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn iso_to_millis(value: Option<&Value>) -> Option<i64> {
+    value.and_then(Value::as_str)
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.timestamp_millis())
+}
+
+fn message_text(message: &Value) -> String {
+    let mut texts: Vec<String> = match message.get("content").and_then(Value::as_array) {
+        Some(blocks) => blocks.iter()
+            .filter_map(|block| match block.get("type").and_then(Value::as_str) {
+                Some("text") => block.get("text").and_then(Value::as_str).map(str::to_string),
+                Some("thinking") => block.get("thinking").and_then(Value::as_str).map(|t| format!("<think>\n{}\n</think>", t)),
+                _ => None,
+            })
+            .collect(),
+        None => message.get("text").and_then(Value::as_str).map(str::to_string).into_iter().collect(),
+    };
+
+    // Text extracted from uploaded documents is included in the export; keep it with the message.
+    for attachment in message.get("attachments").and_then(Value::as_array).into_iter().flatten() {
+        let name = attachment.get("file_name").and_then(Value::as_str).unwrap_or("attachment");
+        if let Some(extracted) = attachment.get("extracted_content").and_then(Value::as_str).filter(|c| !c.trim().is_empty()) {
+            texts.push(format!("[Attachment: {}]\n```\n{}\n```", name, extracted));
+        }
+    }
+    texts.join("\n\n")
+}
+
+pub fn parse(data: &[u8], errors: &mut Vec<String>) -> Result<Vec<ImportedConversation>> {
+    let raw: Vec<Value> = serde_json::from_slice(data)
+        .map_err(|e| AppError::Parse(format!("Invalid Claude export: {}", e)))?;
+
+    let mut conversations = Vec::new();
+    for conversation in raw {
+        let Some(source_id) = conversation.get("uuid").and_then(Value::as_str) else {
+            errors.push("Skipped a Claude conversation without a uuid".to_string());
+            continue;
+        };
+        let created_at = iso_to_millis(conversation.get("created_at")).unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+        let chat_messages = conversation.get("chat_messages").and_then(Value::as_array).cloned().unwrap_or_default();
+
+        let mut messages = Vec::new();
+        let mut skipped_parents = HashMap::new();
+        let mut previous: Option<String> = None;
+        for message in &chat_messages {
+            let Some(message_id) = message.get("uuid").and_then(Value::as_str).map(str::to_string) else { continue };
+            // Older exports have no parent links; the list order is then the conversation.
+            let parent = match message.get("parent_message_uuid") {
+                Some(parent) => parent.as_str().map(str::to_string),
+                None => previous.clone(),
+            };
+            previous = Some(message_id.clone());
+
+            let role = match message.get("sender").and_then(Value::as_str) {
+                Some("human") => "user",
+                Some("assistant") => "ai",
+                _ => {
+                    skipped_parents.insert(message_id, parent);
+                    continue;
+                }
+            };
+            let images: Vec<ImportedImage> = message.get("files").and_then(Value::as_array).into_iter().flatten()
+                .filter_map(|file| file.get("file_name").and_then(Value::as_str))
+                .map(|name| ImportedImage::Missing(name.to_string()))
+                .collect();
+            let text = message_text(message);
+            if text.trim().is_empty() && images.is_empty() {
+                skipped_parents.insert(message_id, parent);
+                continue;
+            }
+
+            messages.push(ImportedMessage {
+                source_id: message_id,
+                parent_source_id: parent,
+                role: role.to_string(),
+                text,
+                images,
+                timestamp: iso_to_millis(message.get("created_at")).unwrap_or(created_at),
+                model: None,
+            });
+        }
+
+        conversations.push(ImportedConversation {
+            source_id: source_id.to_string(),
+            title: conversation.get("name").and_then(Value::as_str).filter(|t| !t.trim().is_empty()).unwrap_or("Imported chat").to_string(),
+            created_at,
+            messages,
+            skipped_parents,
+            current_leaf: conversation.get("current_leaf_message_uuid").and_then(Value::as_str).map(str::to_string),
+        });
+    }
+    Ok(conversations)
+}
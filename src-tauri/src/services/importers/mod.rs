@@ -0,0 +1,330 @@
+// src-tauri/src/services/importers/mod.rs
+pub mod chatgpt;
+pub mod claude;
+pub mod openai_jsonl;
+
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    state::AppState,
+};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// An image referenced by an imported message.
+pub enum ImportedImage {
+    /// Remote or data URL, kept as is.
+    Url(String),
+    /// Image bytes found in the export.
+    File { name: String, data: Vec<u8> },
+    /// Referenced by the export but not included in it.
+    Missing(String),
+}
+
+pub struct ImportedMessage {
+    pub source_id: String,
+    pub parent_source_id: Option<String>,
+    /// "user" or "ai".
+    pub role: String,
+    pub text: String,
+    pub images: Vec<ImportedImage>,
+    pub timestamp: i64,
+    pub model: Option<String>,
+}
+
+pub struct ImportedConversation {
+    pub source_id: String,
+    pub title: String,
+    pub created_at: i64,
+    pub messages: Vec<ImportedMessage>,
+    /// Parent links of nodes that were not imported (system prompts, tool calls, hidden nodes),
+    /// so children can be re-attached to their nearest imported ancestor.
+    pub skipped_parents: HashMap<String, Option<String>>,
+    /// The branch the source app was showing, if it records one.
+    pub current_leaf: Option<String>,
+}
+
+/// An export given as a directory, a zip archive, or a single file.
+pub enum ExportArchive {
+    Dir(PathBuf),
+    Zip(zip::ZipArchive<fs::File>),
+}
+
+impl ExportArchive {
+    /// Opens `path`; for a single file, sibling files (e.g. images next to `conversations.json`) are visible too.
+    pub fn open(path: &Path) -> Result<(Self, Option<String>)> {
+        if path.is_dir() {
+            return Ok((ExportArchive::Dir(path.to_path_buf()), None));
+        }
+        let is_zip = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+        if is_zip {
+            let archive = zip::ZipArchive::new(fs::File::open(path)?)
+                .map_err(|e| AppError::Parse(format!("Invalid zip archive: {}", e)))?;
+            return Ok((ExportArchive::Zip(archive), None));
+        }
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let name = path.file_name().and_then(|n| n.to_str()).map(str::to_string);
+        Ok((ExportArchive::Dir(dir), name))
+    }
+
+    pub fn read(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            ExportArchive::Dir(dir) => {
+                let path = dir.join(name);
+                Ok(if path.is_file() { Some(fs::read(path)?) } else { None })
+            }
+            ExportArchive::Zip(archive) => {
+                let Some(index) = (0..archive.len()).find(|&i| {
+                    archive.name_for_index(i).is_some_and(|n| n == name || n.ends_with(&format!("/{}", name)))
+                }) else { return Ok(None) };
+                let mut file = archive.by_index(index).map_err(|e| AppError::Parse(format!("Invalid zip entry: {}", e)))?;
+                let mut data = Vec::new();
+                file.read_to_end(&mut data)?;
+                Ok(Some(data))
+            }
+        }
+    }
+
+    /// Finds a file whose name starts with `prefix`, anywhere in the export. Returns its name and bytes.
+    pub fn find_by_prefix(&mut self, prefix: &str) -> Result<Option<(String, Vec<u8>)>> {
+        let name = match self {
+            ExportArchive::Dir(dir) => walkdir::WalkDir::new(&*dir).max_depth(3).into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .find(|e| e.file_name().to_str().is_some_and(|n| n.starts_with(prefix)))
+                .and_then(|e| e.path().strip_prefix(&*dir).ok().and_then(|p| p.to_str()).map(str::to_string)),
+            ExportArchive::Zip(archive) => (0..archive.len())
+                .filter_map(|i| archive.name_for_index(i).map(str::to_string))
+                .find(|n| n.rsplit('/').next().is_some_and(|base| base.starts_with(prefix))),
+        };
+        match name {
+            Some(name) => Ok(self.read(&name)?.map(|data| (name.rsplit('/').next().unwrap_or(&name).to_string(), data))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Detects the export format from the file name and the shape of its first conversation.
+pub fn detect_format(file_name: &str, data: &[u8]) -> Option<&'static str> {
+    if file_name.ends_with(".jsonl") {
+        return Some("jsonl");
+    }
+    let value: serde_json::Value = serde_json::from_slice(data).ok()?;
+    let first = value.as_array()?.first()?;
+    if first.get("mapping").is_some() {
+        Some("chatgpt")
+    } else if first.get("chat_messages").is_some() {
+        Some("claude")
+    } else {
+        None
+    }
+}
+
+fn import_id(format: &str, conversation_source_id: &str, message_source_id: Option<&str>) -> String {
+    match message_source_id {
+        Some(message_id) => format!("import-{}-{}-{}", format, conversation_source_id, message_id),
+        None => format!("import-{}-{}", format, conversation_source_id),
+    }
+}
+
+fn image_extension(name: &str, data: &[u8]) -> &'static str {
+    match image::guess_format(data) {
+        Ok(image::ImageFormat::Png) => "png",
+        Ok(image::ImageFormat::Jpeg) => "jpg",
+        Ok(image::ImageFormat::Gif) => "gif",
+        Ok(image::ImageFormat::WebP) => "webp",
+        _ => match Path::new(name).extension().and_then(|e| e.to_str()) {
+            Some("png") => "png",
+            Some("jpg") | Some("jpeg") => "jpg",
+            Some("gif") => "gif",
+            _ => "webp",
+        },
+    }
+}
+
+/// Where an imported image is stored. The name comes from a hash of the message ID, which is
+/// built from IDs in the export and must not steer the path.
+fn image_path(images_dir: &Path, message_id: &str, index: usize, extension: &str) -> Result<PathBuf> {
+    let hash = format!("{:x}", Sha256::digest(message_id.as_bytes()));
+    let path = images_dir.join(format!("{}-{}.{}", &hash[..32], index, extension));
+    if !path.starts_with(images_dir) {
+        return Err(AppError::Internal(format!("Image path {:?} is outside the imports directory", path)));
+    }
+    Ok(path)
+}
+
+/// Stores parsed conversations. Conversations and messages are keyed by their IDs in the source
+/// export, so importing the same export again only adds what is new. Messages keep their tree
+/// structure; the active branch is the one the source app was showing, else the newest leaf.
+/// Each conversation is written in its own transaction, so other commands can use the database
+/// between them.
+pub fn write_conversations(
+    state: &AppState,
+    format: &str,
+    conversations: Vec<ImportedConversation>,
+    dry_run: bool,
+    stats: &mut models::ImportStats,
+) -> Result<()> {
+    let images_dir = state.context.app_data_dir.join("imports").join(format);
+    if !dry_run {
+        fs::create_dir_all(&images_dir)?;
+    }
+
+    for conversation in conversations {
+        let mut conn = state.db.lock().unwrap();
+        stats.conversations_found += 1;
+        stats.messages_found += conversation.messages.len();
+        let conversation_id = import_id(format, &conversation.source_id, None);
+        let exists = queries::get_conversation_by_id(&conn, &conversation_id)?.is_some();
+
+        let kept: HashSet<&str> = conversation.messages.iter().map(|m| m.source_id.as_str()).collect();
+        let resolve_parent = |mut parent: Option<String>| {
+            let mut hops = 0;
+            while let Some(id) = parent.clone() {
+                if kept.contains(id.as_str()) || hops > conversation.skipped_parents.len() {
+                    break;
+                }
+                parent = conversation.skipped_parents.get(&id).cloned().flatten();
+                hops += 1;
+            }
+            parent.filter(|id| kept.contains(id.as_str()))
+        };
+
+        let tx = conn.transaction()?;
+        if !dry_run {
+            queries::insert_conversation_if_missing(&tx, &conversation_id, &conversation.title, conversation.created_at)?;
+        }
+
+        let mut new_messages = 0;
+        let mut sorted: Vec<&ImportedMessage> = conversation.messages.iter().collect();
+        sorted.sort_by_key(|m| m.timestamp);
+        for message in sorted {
+            let message_id = import_id(format, &conversation.source_id, Some(&message.source_id));
+            if queries::message_exists(&tx, &message_id)? {
+                stats.messages_skipped += 1;
+                continue;
+            }
+            new_messages += 1;
+
+            let mut content = Vec::new();
+            let mut text = message.text.clone();
+            for (i, image) in message.images.iter().enumerate() {
+                match image {
+                    ImportedImage::Url(url) => content.push(models::ChatMessageContentPart::ImageUrl { image_url: models::ImageUrl { url: url.clone() } }),
+                    ImportedImage::File { name, data } => {
+                        stats.images_imported += 1;
+                        let path = image_path(&images_dir, &message_id, i, image_extension(name, data))?;
+                        if !dry_run {
+                            fs::write(&path, data)?;
+                        }
+                        content.push(models::ChatMessageContentPart::ImageUrl { image_url: models::ImageUrl { url: path.to_string_lossy().to_string() } });
+                    }
+                    ImportedImage::Missing(name) => {
+                        stats.images_missing += 1;
+                        text.push_str(&format!("\n\n[Image not included in the export: {}]", name));
+                    }
+                }
+            }
+            if !text.trim().is_empty() {
+                content.insert(0, models::ChatMessageContentPart::Text { text: text.trim().to_string() });
+            }
+
+            if !dry_run {
+                let chat_message = models::ChatMessage {
+                    id: message_id,
+                    conversation_id: conversation_id.clone(),
+                    role: message.role.clone(),
+                    content,
+                    timestamp: message.timestamp,
+                    model: message.model.clone(),
+                    parent_id: resolve_parent(message.parent_source_id.clone())
+                        .map(|parent| import_id(format, &conversation.source_id, Some(&parent))),
+                    ..Default::default()
+                };
+                match chat_message.parent_id {
+                    Some(_) => queries::save_message(&tx, &chat_message)?,
+                    None => queries::save_root_message(&tx, &chat_message)?,
+                }
+            }
+        }
+        stats.messages_new += new_messages;
+
+        if !dry_run && new_messages > 0 {
+            let leaf = match conversation.current_leaf.clone().and_then(|leaf| resolve_parent(Some(leaf))) {
+                Some(leaf) => import_id(format, &conversation.source_id, Some(&leaf)),
+                None => tx.query_row(
+                    "SELECT id FROM messages WHERE conversation_id = ?1 AND id NOT IN (SELECT parent_id FROM messages WHERE parent_id IS NOT NULL) ORDER BY timestamp DESC LIMIT 1",
+                    rusqlite::params![conversation_id],
+                    |row| row.get::<_, String>(0),
+                )?,
+            };
+            queries::set_active_leaf(&tx, &conversation_id, &leaf)?;
+        }
+        tx.commit()?;
+
+        if !exists {
+            stats.conversations_new += 1;
+        } else if new_messages > 0 {
+            stats.conversations_updated += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Imports chat history exported from another assistant. `format` is "chatgpt", "claude" or
+/// "jsonl"; when omitted it is detected from the file. `path` may be the exported JSON/JSONL file,
+/// the unpacked export directory, or the export zip.
+pub fn import_history(state: &AppState, path: &Path, format: Option<&str>, dry_run: bool) -> Result<models::ImportStats> {
+    let (mut archive, file_name) = ExportArchive::open(path)?;
+    let file_name = match file_name {
+        Some(name) => name,
+        None => ["conversations.json", "conversations.jsonl"].into_iter()
+            .find(|name| archive.read(name).ok().flatten().is_some())
+            .ok_or_else(|| AppError::Parse("No conversations.json found in the export".to_string()))?
+            .to_string(),
+    };
+    let data = archive.read(&file_name)?
+        .ok_or_else(|| AppError::Parse(format!("{} not found in the export", file_name)))?;
+
+    let format = match format {
+        Some(format) => format.to_string(),
+        None => detect_format(&file_name, &data)
+            .ok_or_else(|| AppError::Parse("Could not detect the export format; choose it explicitly".to_string()))?
+            .to_string(),
+    };
+
+    let mut stats = models::ImportStats { format: format.clone(), dry_run, ..Default::default() };
+    let conversations = match format.as_str() {
+        "chatgpt" => chatgpt::parse(&data, &mut archive, &mut stats.errors)?,
+        "claude" => claude::parse(&data, &mut stats.errors)?,
+        "jsonl" => openai_jsonl::parse(&data, &mut stats.errors)?,
+        _ => return Err(AppError::Config(format!("Unsupported import format: {}", format))),
+    };
+    write_conversations(state, &format, conversations, dry_run, &mut stats)?;
+
+    log::info!(
+        "[Import] {} import of {}: {} conversations ({} new, {} updated), {} new messages{}",
+        format, path.display(), stats.conversations_found, stats.conversations_new, stats.conversations_updated,
+        stats.messages_new, if dry_run { " (dry run)" } else { "" }
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_paths_stay_in_the_imports_directory() {
+        let dir = Path::new("/data/imports/chatgpt");
+        for source_id in ["../../../../etc/passwd", "/abs/path", "a/b\\c"] {
+            let message_id = import_id("chatgpt", source_id, Some(".."));
+            let path = image_path(dir, &message_id, 0, "png").unwrap();
+            assert_eq!(path.parent(), Some(dir));
+        }
+        assert_ne!(image_path(dir, "a", 0, "png").unwrap(), image_path(dir, "b", 0, "png").unwrap());
+    }
+}
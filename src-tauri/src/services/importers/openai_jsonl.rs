@@ -0,0 +1,97 @@
+// src-tauri/src/services/importers/openai_jsonl.rs
+// One conversation per line in the OpenAI chat format: `{"messages": [{"role", "content"}, ...]}`,
+// optionally with `id` and `title`. `content` is a string or a list of `text`/`image_url` parts.
+use super::{ImportedConversation, ImportedImage, ImportedMessage};
+use crate::error::Result;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+fn parse_content(content: Option<&Value>) -> (String, Vec<ImportedImage>) {
+    match content {
+        Some(Value::String(text)) => (text.clone(), vec![]),
+        Some(Value::Array(parts)) => {
+            let mut texts = Vec::new();
+            let mut images = Vec::new();
+            for part in parts {
+                match part.get("type").and_then(Value::as_str) {
+                    Some("text") => texts.extend(part.get("text").and_then(Value::as_str).map(str::to_string)),
+                    Some("image_url") => images.extend(
+                        part.pointer("/image_url/url").and_then(Value::as_str).map(|url| ImportedImage::Url(url.to_string())),
+                    ),
+                    _ => {}
+                }
+            }
+            (texts.join("\n\n"), images)
+        }
+        _ => (String::new(), vec![]),
+    }
+}
+
+pub fn parse(data: &[u8], errors: &mut Vec<String>) -> Result<Vec<ImportedConversation>> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut conversations = Vec::new();
+
+    for (line_no, line) in String::from_utf8_lossy(data).lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => {
+                errors.push(format!("Line {}: {}", line_no + 1, e));
+                continue;
+            }
+        };
+        let Some(raw_messages) = value.get("messages").and_then(Value::as_array) else {
+            errors.push(format!("Line {}: no \"messages\" array", line_no + 1));
+            continue;
+        };
+
+        // Lines without an ID are keyed by their content, so re-importing the same file is a no-op.
+        let source_id = value.get("id").and_then(Value::as_str).map(str::to_string).unwrap_or_else(|| {
+            let digest = Sha256::digest(line.trim().as_bytes());
+            digest.iter().take(8).map(|b| format!("{:02x}", b)).collect()
+        });
+        let created_at = value.get("created_at").and_then(Value::as_i64).unwrap_or(now);
+
+        let mut messages = Vec::new();
+        let mut previous: Option<String> = None;
+        for (i, message) in raw_messages.iter().enumerate() {
+            let role = match message.get("role").and_then(Value::as_str) {
+                Some("user") => "user",
+                Some("assistant") => "ai",
+                _ => continue,
+            };
+            let (text, images) = parse_content(message.get("content"));
+            if text.trim().is_empty() && images.is_empty() {
+                continue;
+            }
+            let message_id = i.to_string();
+            messages.push(ImportedMessage {
+                source_id: message_id.clone(),
+                parent_source_id: previous.replace(message_id),
+                role: role.to_string(),
+                text,
+                images,
+                // Keep the original order when the source has no timestamps.
+                timestamp: created_at + i as i64,
+                model: None,
+            });
+        }
+
+        let title = value.get("title").and_then(Value::as_str).map(str::to_string).unwrap_or_else(|| {
+            let first = messages.iter().find(|m| m.role == "user").map(|m| m.text.as_str()).unwrap_or("Imported chat");
+            first.chars().take(60).collect::<String>().trim().to_string()
+        });
+        conversations.push(ImportedConversation {
+            source_id,
+            title,
+            created_at,
+            messages,
+            skipped_parents: HashMap::new(),
+            current_leaf: None,
+        });
+    }
+    Ok(conversations)
+}
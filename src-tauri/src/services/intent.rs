@@ -2,6 +2,7 @@
 use crate::{
     database::{models, queries},
     error::Result,
+    services::{prompts, workflows},
     state::AppState,
 };
 use tauri::State;
@@ -45,6 +46,26 @@ pub async fn get_suggestions_for_context(state: State<'_, AppState>, context: mo
         }
     }
 
+    if let Ok(copilot_workflows) = queries::get_copilot_workflows(&conn) {
+        for workflow in copilot_workflows {
+            suggestions.push(models::IntentSuggestion {
+                action: format!("{}{}", workflows::TOOL_PREFIX, workflow.id),
+                label: workflow.name,
+                icon: "Workflow".to_string(),
+            });
+        }
+    }
+
+    if let Ok(copilot_prompts) = queries::get_copilot_prompts(&conn) {
+        for prompt in copilot_prompts {
+            suggestions.push(models::IntentSuggestion {
+                action: format!("{}{}", prompts::TOOL_PREFIX, prompt.id),
+                label: prompt.name,
+                icon: "BookText".to_string(),
+            });
+        }
+    }
+
     suggestions.push(models::IntentSuggestion {
         action: "built_in::save_to_kb".to_string(),
         label: "Save to KB".to_string(),
@@ -1,9 +1,18 @@
 // src-tauri/src/services/mod.rs
+pub mod api_server;
+pub mod attachments;
 pub mod chat;
 pub mod execution;
+pub mod export;
+pub mod failover;
+pub mod importers;
 pub mod intent;
+pub mod prompts;
 pub mod proxy_types;
+pub mod secrets;
 pub mod shortcuts;
 pub mod tools;
+pub mod usage;
 pub mod vector_client;
-pub mod window_manager;
\ No newline at end of file
+pub mod window_manager;
+pub mod workflows;
\ No newline at end of file
@@ -0,0 +1,222 @@
+// src-tauri/src/services/prompts.rs
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services::chat::{llm_utils, templates},
+    state::AppState,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Tool ID prefix under which library prompts run, e.g. from copilot suggestions.
+pub const TOOL_PREFIX: &str = "prompt::";
+
+/// Checks `values` against the prompt's declared variables, filling in defaults.
+/// Values for undeclared names are passed through so built-ins like `{{selection}}` can be set.
+pub fn resolve_variables(prompt: &models::PromptTemplate, values: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    let mut resolved = values.clone();
+    for variable in &prompt.variables {
+        let value = values.get(&variable.name)
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .unwrap_or_else(|| variable.default_value.clone());
+
+        if value.is_empty() {
+            if variable.required {
+                return Err(AppError::Config(format!("Prompt variable '{}' is required", variable.name)));
+            }
+            resolved.insert(variable.name.clone(), value);
+            continue;
+        }
+
+        let value = match variable.var_type {
+            models::PromptVariableType::Number => {
+                value.trim().parse::<f64>()
+                    .map_err(|_| AppError::Parse(format!("Prompt variable '{}' must be a number, got '{}'", variable.name, value)))?;
+                value.trim().to_string()
+            }
+            models::PromptVariableType::Boolean => match value.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => "true".to_string(),
+                "false" | "no" | "0" => "false".to_string(),
+                _ => return Err(AppError::Parse(format!("Prompt variable '{}' must be true or false, got '{}'", variable.name, value))),
+            },
+            models::PromptVariableType::Select if !variable.options.is_empty() && !variable.options.contains(&value) => {
+                return Err(AppError::Config(format!("'{}' is not an allowed value for prompt variable '{}'", value, variable.name)));
+            }
+            _ => value,
+        };
+        resolved.insert(variable.name.clone(), value);
+    }
+    Ok(resolved)
+}
+
+pub async fn render(app: &AppHandle, prompt: &models::PromptTemplate, values: &HashMap<String, String>) -> Result<String> {
+    let resolved = resolve_variables(prompt, values)?;
+    Ok(templates::expand(app, &prompt.content, &resolved).await)
+}
+
+/// Runs a library prompt as a tool and returns the model's answer. String params fill the
+/// prompt's variables; the tool input (`stdin`, the copilot's text) fills `{{input}}` and
+/// `{{selection}}`, or is appended when the prompt uses neither.
+pub async fn run_as_tool(app: &AppHandle, state: &AppState, prompt_id: &str, params: Value) -> Result<String> {
+    let prompt = queries::get_prompt_by_id(&state.db.lock().unwrap(), prompt_id)?
+        .ok_or_else(|| AppError::Internal(format!("Prompt {} not found", prompt_id)))?;
+    let mut values: HashMap<String, String> = params.as_object()
+        .map(|map| map.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+        .unwrap_or_default();
+    let input = values.remove("stdin").unwrap_or_default();
+    let placeholders = templates::find_variables(&prompt.content);
+    let input_used = placeholders.iter().any(|p| p == "input" || p == "selection");
+    values.entry("input".to_string()).or_insert_with(|| input.clone());
+    values.entry("selection".to_string()).or_insert_with(|| input.clone());
+
+    let rendered = render(app, &prompt, &values).await?;
+    let rendered = if input_used || input.trim().is_empty() { rendered } else { format!("{}\n\n{}", rendered, input) };
+    log::info!("[Prompts] Running prompt '{}' as a tool", prompt.name);
+    Ok(llm_utils::complete_prompt(state, "prompt", rendered).await?.trim().to_string())
+}
+
+/// Expands a chat message of the form `/command rest of text` using the prompt bound to `command`.
+/// The text after the command fills `{{input}}`, or the first declared variable without a value;
+/// if the prompt has no slot for it, it is appended after the rendered prompt.
+/// Returns `None` when the message is not a known slash command.
+pub async fn expand_slash_command(
+    app: &AppHandle,
+    state: &AppState,
+    text: &str,
+    values: &HashMap<String, String>,
+) -> Result<Option<String>> {
+    let Some(invocation) = text.trim_start().strip_prefix('/') else { return Ok(None) };
+    let (command, rest) = match invocation.split_once(char::is_whitespace) {
+        Some((command, rest)) => (command, rest.trim()),
+        None => (invocation, ""),
+    };
+    if command.is_empty() {
+        return Ok(None);
+    }
+
+    let prompt = {
+        let conn = state.db.lock().unwrap();
+        queries::get_prompt_by_slash_command(&conn, command)?
+    };
+    let Some(prompt) = prompt else { return Ok(None) };
+    log::info!("[Prompts] Expanding slash command '/{}' with prompt '{}'", command, prompt.name);
+
+    let mut values = values.clone();
+    let placeholders = templates::find_variables(&prompt.content);
+    let mut rest_consumed = rest.is_empty();
+    if !rest.is_empty() {
+        let slot = if placeholders.iter().any(|p| p == "input") {
+            Some("input".to_string())
+        } else {
+            prompt.variables.iter()
+                .find(|v| !values.contains_key(&v.name) && placeholders.contains(&v.name))
+                .map(|v| v.name.clone())
+        };
+        if let Some(slot) = slot {
+            values.entry(slot).or_insert_with(|| rest.to_string());
+            rest_consumed = true;
+        }
+    }
+
+    let rendered = render(app, &prompt, &values).await?;
+    Ok(Some(if rest_consumed { rendered } else { format!("{}\n\n{}", rendered, rest) }))
+}
+
+/// Renders a prompt as Markdown with a front-matter header of `key: <json value>` lines.
+pub fn to_markdown(prompt: &models::PromptTemplate) -> Result<String> {
+    let mut header = match serde_json::to_value(prompt)? {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    header.remove("content");
+    header.remove("createdAt");
+    header.remove("updatedAt");
+
+    let mut out = String::from("---\n");
+    for (key, value) in header {
+        out.push_str(&format!("{}: {}\n", key, value));
+    }
+    out.push_str("---\n");
+    out.push_str(&prompt.content);
+    if !prompt.content.ends_with('\n') {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parses a prompt written by `to_markdown`. Files without front matter become a prompt named
+/// `fallback_name` whose content is the whole file. Plain (unquoted) header values are read as strings.
+pub fn from_markdown(text: &str, fallback_name: &str) -> Result<models::PromptTemplate> {
+    let normalized = text.replace("\r\n", "\n");
+    let (header, body) = match normalized.strip_prefix("---\n").and_then(|rest| rest.split_once("\n---\n")) {
+        Some((header, body)) => (header.to_string(), body.to_string()),
+        None => (String::new(), normalized.clone()),
+    };
+
+    let mut map = Map::new();
+    for line in header.lines().filter(|l| !l.trim().is_empty()) {
+        let (key, raw) = line.split_once(':')
+            .ok_or_else(|| AppError::Parse(format!("Invalid front matter line: '{}'", line)))?;
+        let raw = raw.trim();
+        let value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+        map.insert(key.trim().to_string(), value);
+    }
+    map.entry("name").or_insert_with(|| Value::String(fallback_name.to_string()));
+    map.insert("content".to_string(), Value::String(body.trim_end_matches('\n').to_string()));
+
+    serde_json::from_value(Value::Object(map)).map_err(|e| AppError::Parse(format!("Invalid prompt front matter: {}", e)))
+}
+
+/// Writes prompts to `path`: a single JSON array for "json", or one `.md` file per prompt
+/// inside the directory `path` for "markdown".
+pub fn export_prompts(prompts: &[models::PromptTemplate], format: &str, path: &Path) -> Result<()> {
+    match format {
+        "json" => fs::write(path, serde_json::to_string_pretty(prompts)?)?,
+        "markdown" => {
+            fs::create_dir_all(path)?;
+            for prompt in prompts {
+                let slug: String = prompt.name.chars()
+                    .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+                    .collect::<String>()
+                    .split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-");
+                let file_name = format!("{}-{}.md", if slug.is_empty() { "prompt" } else { &slug }, prompt.id.chars().take(8).collect::<String>());
+                fs::write(path.join(file_name), to_markdown(prompt)?)?;
+            }
+        }
+        _ => return Err(AppError::Config(format!("Unsupported prompt export format: {}", format))),
+    }
+    Ok(())
+}
+
+/// Reads prompts from a JSON file (an array or a single object), a Markdown file,
+/// or every `.md`/`.json` file in a directory.
+pub fn import_prompts(path: &Path) -> Result<Vec<models::PromptTemplate>> {
+    if path.is_dir() {
+        let mut prompts = Vec::new();
+        let mut entries: Vec<_> = fs::read_dir(path)?.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        entries.sort();
+        for entry in entries.into_iter().filter(|p| p.is_file()) {
+            let ext = entry.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            if ext == "md" || ext == "json" {
+                prompts.extend(import_prompts(&entry)?);
+            }
+        }
+        return Ok(prompts);
+    }
+
+    let text = fs::read_to_string(path)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    if ext == "json" {
+        let value: Value = serde_json::from_str(&text)?;
+        return match value {
+            Value::Array(_) => serde_json::from_value(value).map_err(|e| AppError::Parse(format!("Invalid prompt file: {}", e))),
+            _ => Ok(vec![serde_json::from_value(value).map_err(|e| AppError::Parse(format!("Invalid prompt file: {}", e)))?]),
+        };
+    }
+    let fallback_name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Imported prompt");
+    Ok(vec![from_markdown(&text, fallback_name)?])
+}
@@ -13,6 +13,21 @@ pub struct ProxyChatPayload<'a> {
     pub knowledge_base_selection: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_config: Option<&'a ApiConfig>,
+    #[serde(flatten)]
+    pub sampling: SamplingParams,
+}
+
+/// Optional OpenAI-style sampling parameters; unset fields are left to the provider's defaults.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SamplingParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -28,11 +43,30 @@ pub struct ProxyEmbeddingPayload<'a> {
     pub provider_config: &'a ApiProvider,
 }
 
+/// Audio to transcribe, base64-encoded.
+#[derive(Serialize)]
+pub struct ProxyTranscriptionPayload<'a> {
+    pub model: &'a str,
+    pub audio: String,
+    pub file_name: &'a str,
+    pub mime_type: &'a str,
+    pub provider_config: &'a ApiProvider,
+}
+
+#[derive(Deserialize)]
+pub struct TranscriptionResponse {
+    pub text: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ProxyStreamChunk {
+    #[serde(default)]
     pub choices: Vec<StreamChoice>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sources: Option<Vec<KnowledgeSource>>,
+    /// Sent by providers on the last chunk when usage reporting is enabled.
+    #[serde(default)]
+    pub usage: Option<ProxyUsage>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -50,6 +84,17 @@ pub struct StreamDelta {
 #[derive(Deserialize)]
 pub struct ProxyResponse {
     pub choices: Vec<ResponseChoice>,
+    #[serde(default)]
+    pub usage: Option<ProxyUsage>,
+}
+
+/// OpenAI-style `usage` block; embedding responses only carry `prompt_tokens`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ProxyUsage {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
 }
 
 #[derive(Deserialize)]
@@ -71,4 +116,6 @@ pub struct EmbeddingData {
 #[derive(Deserialize)]
 pub struct EmbeddingResponse {
     pub data: Vec<EmbeddingData>,
+    #[serde(default)]
+    pub usage: Option<ProxyUsage>,
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+// src-tauri/src/services/secrets.rs
+// Named credentials for tools. Values are kept in the OS keychain (Keychain, Credential
+// Manager, Secret Service); the database only records which names exist. Tool settings refer
+// to a secret as `{{secret:NAME}}`, so the value never appears in the tool's configuration.
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    state::AppState,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+const KEYRING_SERVICE: &str = "com.nexus.copilot";
+
+static SECRET_REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*secret:([A-Za-z0-9_.\-]+)\s*\}\}").unwrap());
+
+fn entry(name: &str) -> Result<keyring::Entry> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+        return Err(AppError::Config(format!("Invalid secret name '{}': use letters, digits, '_', '.' and '-'", name)));
+    }
+    keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| AppError::Config(format!("Secret store unavailable: {}", e)))
+}
+
+pub fn list(state: &AppState) -> Result<Vec<models::SecretInfo>> {
+    queries::list_secrets(&state.db.lock().unwrap())
+}
+
+pub fn set(state: &AppState, name: &str, value: &str) -> Result<()> {
+    entry(name)?.set_password(value).map_err(|e| AppError::Config(format!("Could not store secret '{}': {}", name, e)))?;
+    queries::upsert_secret(&state.db.lock().unwrap(), name, chrono::Utc::now().timestamp_millis())?;
+    log::info!("[Secrets] Stored secret {}", name);
+    Ok(())
+}
+
+pub fn delete(state: &AppState, name: &str) -> Result<()> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(AppError::Config(format!("Could not delete secret '{}': {}", name, e))),
+    }
+    queries::delete_secret(&state.db.lock().unwrap(), name)?;
+    log::info!("[Secrets] Deleted secret {}", name);
+    Ok(())
+}
+
+pub fn get(name: &str) -> Result<String> {
+    entry(name)?.get_password().map_err(|e| match e {
+        keyring::Error::NoEntry => AppError::Config(format!("Secret '{}' is not set", name)),
+        e => AppError::Config(format!("Could not read secret '{}': {}", name, e)),
+    })
+}
+
+/// A secret's value, if `allowed` lists it. Tools only get the secrets the user allowed them.
+pub fn get_allowed(name: &str, allowed: &[String]) -> Result<String> {
+    if !allowed.iter().any(|a| a == name) {
+        return Err(AppError::Config(format!("Secret '{}' isn't allowed for this tool; allow it in the tool's settings", name)));
+    }
+    get(name)
+}
+
+/// `text` with every `{{secret:NAME}}` replaced by the secret's value. Each must be in `allowed`.
+pub fn resolve(text: &str, allowed: &[String]) -> Result<String> {
+    let mut resolved = String::with_capacity(text.len());
+    let mut last = 0;
+    for captures in SECRET_REFERENCE.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        resolved.push_str(&text[last..whole.start()]);
+        resolved.push_str(&get_allowed(&captures[1], allowed)?);
+        last = whole.end();
+    }
+    resolved.push_str(&text[last..]);
+    Ok(resolved)
+}
+
+/// Names of the secrets `text` references, in order of appearance.
+pub fn references(text: &str) -> Vec<String> {
+    SECRET_REFERENCE.captures_iter(text).map(|captures| captures[1].to_string()).collect()
+}
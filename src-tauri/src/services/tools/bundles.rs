@@ -0,0 +1,796 @@
+// src-tauri/src/services/tools/bundles.rs
+// Portable tool bundles: a zip (`.nexustool`) holding a `manifest.json` with the configured
+// tool and the script it runs. The manifest lists each file's SHA-256 and carries a hash of
+// itself, signed with the user's ed25519 key from the secret store when one is set up; on
+// import the signature is checked against the trusted publishers.
+// Importing is two steps: `preview` shows how the bundle differs from the installed tool,
+// `import` applies exactly the bundle that was reviewed. Bundles can also be offered from
+// shared folders and from git repositories cloned under `<app data>/tool_repos`.
+use super::webhook;
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services::secrets,
+    state::AppState,
+};
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+pub const EXTENSION: &str = "nexustool";
+const MANIFEST: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 1;
+const SCRIPTS_DIR: &str = "scripts";
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024;
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+const MAX_FILES: usize = 32;
+/// Scripts longer than this are reported as changed without a line diff.
+const MAX_DIFF_LINES: usize = 1000;
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Fields that are the user's own and survive an update.
+const LOCAL_FIELDS: &[&str] = &["id", "scriptPath", "sandbox", "showInCopilot", "isFavorite", "allowedSecrets"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleFile {
+    /// Relative to the bundle root, e.g. "scripts/convert.py".
+    pub path: String,
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u32,
+    /// `script_path` points into the bundle. The sandbox policy and the copilot and favorite
+    /// flags aren't exported; they stay as the importing user set them.
+    pub tool: models::ConfiguredTool,
+    pub files: Vec<BundleFile>,
+    pub exported_at: i64,
+    /// SHA-256 of the manifest with `hash` and `signature` left empty.
+    #[serde(default)]
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<BundleSignature>,
+}
+
+/// An ed25519 signature of the manifest's `hash`, both fields base64.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSignature {
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// Signed by a trusted publisher or the user's own key.
+    Trusted,
+    /// Correctly signed, by a key that isn't trusted.
+    Untrusted,
+    Invalid,
+    Unsigned,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// What importing a bundle would do.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePreview {
+    pub path: String,
+    /// Pass back to `import` so that exactly this bundle is applied.
+    pub hash: String,
+    pub signature: SignatureStatus,
+    /// Name of the trusted publisher that signed the bundle.
+    pub publisher: Option<String>,
+    pub exported_at: i64,
+    /// The tool as it would be saved.
+    pub tool: models::ConfiguredTool,
+    /// The tool with the bundle's ID, which the import updates.
+    pub existing: Option<models::ConfiguredTool>,
+    /// Hash of the bundle the existing tool was imported from.
+    pub installed_hash: Option<String>,
+    pub changes: Vec<FieldChange>,
+    /// Lines prefixed with "+", "-" or " "; empty when the script is unchanged.
+    pub script_diff: Vec<String>,
+    /// Host the webhook sends its requests, and so the secrets below, to.
+    pub webhook_host: Option<String>,
+    /// Every secret the tool references. Each has to be approved for the import to go ahead.
+    pub secrets: Vec<SecretUse>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretUse {
+    pub name: String,
+    /// Whether the secret store has it.
+    pub stored: bool,
+    /// Whether the installed tool is already allowed to use it.
+    pub allowed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleStatus {
+    New,
+    Installed,
+    UpdateAvailable,
+}
+
+/// A bundle found in a shared folder or repository.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableBundle {
+    pub path: String,
+    /// The folder or repository URL it was found in.
+    pub source: String,
+    pub tool_id: String,
+    pub name: String,
+    pub description: String,
+    pub hash: String,
+    pub exported_at: i64,
+    pub signature: SignatureStatus,
+    pub publisher: Option<String>,
+    pub status: BundleStatus,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositorySync {
+    pub url: String,
+    pub error: Option<String>,
+}
+
+struct Bundle {
+    manifest: BundleManifest,
+    files: Vec<(String, Vec<u8>)>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn manifest_hash(manifest: &BundleManifest) -> Result<String> {
+    let unsigned = BundleManifest { hash: String::new(), signature: None, ..manifest.clone() };
+    Ok(sha256_hex(&serde_json::to_vec(&unsigned)?))
+}
+
+/// The user's signing key, if the sharing settings name one.
+fn signing_key(settings: &models::Settings) -> Result<Option<SigningKey>> {
+    let name = settings.execution.sharing.signing_secret.trim();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let bytes = base64::engine::general_purpose::STANDARD.decode(secrets::get(name)?.trim()).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| AppError::Config(format!("Secret '{}' doesn't hold an ed25519 signing key", name)))?;
+    Ok(Some(SigningKey::from_bytes(&bytes)))
+}
+
+fn encode_key(key: &VerifyingKey) -> String {
+    base64::engine::general_purpose::STANDARD.encode(key.as_bytes())
+}
+
+fn sign(key: &SigningKey, hash: &str) -> BundleSignature {
+    BundleSignature {
+        public_key: encode_key(&key.verifying_key()),
+        signature: base64::engine::general_purpose::STANDARD.encode(key.sign(hash.as_bytes()).to_bytes()),
+    }
+}
+
+/// Public keys whose signatures are trusted, with the publisher's name.
+fn trusted_keys(settings: &models::Settings) -> Result<Vec<(String, String)>> {
+    let mut keys: Vec<(String, String)> = settings.execution.sharing.trusted_publishers.iter()
+        .map(|p| (p.name.clone(), p.public_key.trim().to_string()))
+        .collect();
+    if let Some(own) = signing_key(settings)? {
+        keys.push(("You".to_string(), encode_key(&own.verifying_key())));
+    }
+    Ok(keys)
+}
+
+/// Checks the signature against the manifest's hash. Returns the status and the trusted publisher's name.
+fn signature_status(manifest: &BundleManifest, trusted: &[(String, String)]) -> (SignatureStatus, Option<String>) {
+    let Some(signed) = &manifest.signature else { return (SignatureStatus::Unsigned, None) };
+    let engine = base64::engine::general_purpose::STANDARD;
+    let key = engine.decode(&signed.public_key).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok());
+    let signature = engine.decode(&signed.signature).ok().and_then(|bytes| Signature::from_slice(&bytes).ok());
+    let verified = matches!((key, signature), (Some(key), Some(signature)) if key.verify_strict(manifest.hash.as_bytes(), &signature).is_ok());
+    if !verified {
+        return (SignatureStatus::Invalid, None);
+    }
+    match trusted.iter().find(|(_, key)| *key == signed.public_key) {
+        Some((name, _)) => (SignatureStatus::Trusted, Some(name.clone())),
+        None => (SignatureStatus::Untrusted, None),
+    }
+}
+
+/// Why a bundle with this signature can't be imported: an invalid signature never can, a
+/// missing or untrusted one not where signatures are required.
+fn signature_problem(status: SignatureStatus, required: bool) -> Option<&'static str> {
+    match status {
+        SignatureStatus::Invalid => Some("The bundle's signature is invalid; it was changed after signing"),
+        SignatureStatus::Unsigned | SignatureStatus::Untrusted if required => Some("Only bundles signed by a trusted publisher can be imported from here"),
+        _ => None,
+    }
+}
+
+/// Creates a signing key in the secret store, under the configured name or `tool-signing-key`,
+/// and returns its public key for teammates to trust.
+pub fn create_signing_key(state: &AppState) -> Result<String> {
+    let mut settings = queries::get_settings(&state.db.lock().unwrap())?;
+    if settings.execution.sharing.signing_secret.trim().is_empty() {
+        settings.execution.sharing.signing_secret = "tool-signing-key".to_string();
+    }
+    let key = SigningKey::generate(&mut rand_core::OsRng);
+    secrets::set(state, &settings.execution.sharing.signing_secret, &base64::engine::general_purpose::STANDARD.encode(key.to_bytes()))?;
+    queries::save_settings(&state.db.lock().unwrap(), &settings)?;
+    log::info!("[Tool Service] Created a tool signing key");
+    Ok(encode_key(&key.verifying_key()))
+}
+
+/// The public half of the user's signing key, if there is one.
+pub fn public_key(state: &AppState) -> Result<Option<String>> {
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    Ok(signing_key(&settings)?.map(|key| encode_key(&key.verifying_key())))
+}
+
+/// A file name that can't leave the directory it's written to.
+fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':'])
+}
+
+/// The credential field that holds a value instead of a `{{secret:NAME}}` reference, if any.
+fn inline_credential(auth: &models::WebhookAuth) -> Option<&'static str> {
+    let (field, value) = match auth {
+        models::WebhookAuth::Bearer { token } => ("token", token),
+        models::WebhookAuth::Basic { password, .. } => ("password", password),
+        models::WebhookAuth::ApiKey { value, .. } => ("value", value),
+        models::WebhookAuth::Hmac { secret, .. } => ("secret", secret),
+    };
+    (!value.trim().is_empty() && secrets::references(value).is_empty()).then_some(field)
+}
+
+/// Writes `tool_id` as a bundle to `path`.
+pub fn export(state: &AppState, tool_id: &str, path: &Path) -> Result<BundleManifest> {
+    let (tool, settings) = {
+        let conn = state.db.lock().unwrap();
+        let tool = queries::get_configured_tool_by_id(&conn, tool_id)?
+            .ok_or_else(|| AppError::Internal(format!("Tool with ID {} not found", tool_id)))?;
+        (tool, queries::get_settings(&conn)?)
+    };
+    if let Some(field) = tool.webhook_auth.as_ref().and_then(inline_credential) {
+        return Err(AppError::Config(format!(
+            "'{}' has its {} in the tool itself; move it to the secret store and reference it as {{{{secret:NAME}}}} before sharing the tool",
+            tool.name, field,
+        )));
+    }
+
+    let mut files = Vec::new();
+    let mut exported = models::ConfiguredTool { sandbox: None, show_in_copilot: false, is_favorite: false, allowed_secrets: vec![], ..tool.clone() };
+    if let Some(script_path) = tool.script_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        let script = Path::new(script_path);
+        let name = script.file_name().and_then(|n| n.to_str()).filter(|n| is_plain_file_name(n))
+            .ok_or_else(|| AppError::Config(format!("Invalid script path: {}", script_path)))?;
+        let data = fs::read(script).map_err(|e| AppError::Config(format!("Could not read the script {}: {}", script_path, e)))?;
+        let bundle_path = format!("{}/{}", SCRIPTS_DIR, name);
+        exported.script_path = Some(bundle_path.clone());
+        files.push((bundle_path, data));
+    }
+
+    let mut manifest = BundleManifest {
+        format_version: FORMAT_VERSION,
+        tool: exported,
+        files: files.iter().map(|(path, data)| BundleFile { path: path.clone(), sha256: sha256_hex(data) }).collect(),
+        exported_at: chrono::Utc::now().timestamp_millis(),
+        hash: String::new(),
+        signature: None,
+    };
+    manifest.hash = manifest_hash(&manifest)?;
+    manifest.signature = signing_key(&settings)?.map(|key| sign(&key, &manifest.hash));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut zip = zip::ZipWriter::new(fs::File::create(path)?);
+    let entries = std::iter::once((MANIFEST.to_string(), serde_json::to_vec_pretty(&manifest)?)).chain(files);
+    for (name, data) in entries {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .map_err(|e| AppError::Internal(format!("Failed to write zip entry: {}", e)))?;
+        zip.write_all(&data)?;
+    }
+    zip.finish().map_err(|e| AppError::Internal(format!("Failed to finish zip archive: {}", e)))?;
+    log::info!("[Tool Service] Exported '{}' to {}", tool.name, path.display());
+    Ok(manifest)
+}
+
+/// Reads a bundle and checks its hashes. The signature is checked separately.
+fn read_bundle(path: &Path) -> Result<Bundle> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)
+        .map_err(|e| AppError::Parse(format!("{} is not a tool bundle: {}", path.display(), e)))?;
+    // The declared size can't be trusted, so reading stops one byte past the limit.
+    let mut read_entry = |name: &str, limit: u64| -> Result<Vec<u8>> {
+        let entry = archive.by_name(name).map_err(|e| AppError::Parse(format!("Bundle entry {}: {}", name, e)))?;
+        let mut data = Vec::new();
+        entry.take(limit + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > limit {
+            return Err(AppError::Parse(format!("Bundle entry {} is larger than {} MB", name, limit / (1024 * 1024))));
+        }
+        Ok(data)
+    };
+
+    let manifest: BundleManifest = serde_json::from_slice(&read_entry(MANIFEST, MAX_MANIFEST_BYTES)?)
+        .map_err(|e| AppError::Parse(format!("Invalid bundle manifest: {}", e)))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(AppError::Parse(format!("The bundle uses format {}, which this version of the app can't read", manifest.format_version)));
+    }
+    if manifest.hash != manifest_hash(&manifest)? {
+        return Err(AppError::Parse("The bundle manifest doesn't match its hash; it was modified or damaged".to_string()));
+    }
+
+    if manifest.files.len() > MAX_FILES {
+        return Err(AppError::Parse(format!("The bundle lists {} files; at most {} are allowed", manifest.files.len(), MAX_FILES)));
+    }
+    let mut files = Vec::new();
+    for file in &manifest.files {
+        let valid_path = file.path.strip_prefix(&format!("{}/", SCRIPTS_DIR)).is_some_and(is_plain_file_name);
+        if !valid_path {
+            return Err(AppError::Parse(format!("Invalid file path in bundle: {}", file.path)));
+        }
+        let data = read_entry(&file.path, MAX_FILE_BYTES)?;
+        if sha256_hex(&data) != file.sha256 {
+            return Err(AppError::Parse(format!("{} doesn't match its hash in the manifest; the bundle was modified or damaged", file.path)));
+        }
+        files.push((file.path.clone(), data));
+    }
+    Ok(Bundle { manifest, files })
+}
+
+/// Imported scripts live here rather than in a scripts directory, so they don't also show up as dynamic tools.
+fn install_dir(state: &AppState, tool_id: &str) -> Result<PathBuf> {
+    if !is_plain_file_name(tool_id) {
+        return Err(AppError::Parse(format!("Invalid tool ID in bundle: {}", tool_id)));
+    }
+    Ok(state.context.app_data_dir.join("tools").join(tool_id))
+}
+
+/// The bundle's tool with its script path set to the install location. The local fields come
+/// from `existing`, or are reset for a new tool, so it runs under the default sandbox policy.
+fn tool_to_install(state: &AppState, manifest: &BundleManifest, existing: Option<&models::ConfiguredTool>) -> Result<models::ConfiguredTool> {
+    let mut tool = manifest.tool.clone();
+    if let Some(bundle_path) = &tool.script_path {
+        let name = bundle_path.rsplit('/').next().unwrap_or(bundle_path);
+        tool.script_path = Some(install_dir(state, &tool.id)?.join(name).to_string_lossy().to_string());
+    }
+    tool.sandbox = existing.and_then(|e| e.sandbox.clone());
+    tool.show_in_copilot = existing.is_some_and(|e| e.show_in_copilot);
+    tool.is_favorite = existing.is_some_and(|e| e.is_favorite);
+    tool.allowed_secrets = existing.map(|e| e.allowed_secrets.clone()).unwrap_or_default();
+    Ok(tool)
+}
+
+fn field_changes(before: &models::ConfiguredTool, after: &models::ConfiguredTool) -> Result<Vec<FieldChange>> {
+    let (Value::Object(before), Value::Object(after)) = (serde_json::to_value(before)?, serde_json::to_value(after)?) else {
+        return Ok(vec![]);
+    };
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).filter(|f| !LOCAL_FIELDS.contains(&f.as_str())).collect();
+    fields.sort();
+    fields.dedup();
+    Ok(fields.into_iter()
+        .map(|field| FieldChange {
+            field: field.clone(),
+            before: before.get(field).cloned().unwrap_or(Value::Null),
+            after: after.get(field).cloned().unwrap_or(Value::Null),
+        })
+        .filter(|change| change.before != change.after)
+        .collect())
+}
+
+/// Line diff from the longest common subsequence, after trimming the lines both sides share
+/// at the start and end.
+fn line_diff(before: &str, after: &str) -> Vec<String> {
+    let (a, b): (Vec<&str>, Vec<&str>) = (before.lines().collect(), after.lines().collect());
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if a_mid.is_empty() && b_mid.is_empty() {
+        return vec![];
+    }
+    if a_mid.len().max(b_mid.len()) > MAX_DIFF_LINES {
+        return vec![format!("~ {} lines changed; too long to compare line by line", a_mid.len().max(b_mid.len()))];
+    }
+
+    let mut lcs = vec![vec![0u32; b_mid.len() + 1]; a_mid.len() + 1];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let mut diff: Vec<String> = a[..prefix].iter().map(|line| format!(" {}", line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a_mid.len() || j < b_mid.len() {
+        if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+            diff.push(format!(" {}", a_mid[i]));
+            i += 1;
+            j += 1;
+        } else if j < b_mid.len() && (i == a_mid.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            diff.push(format!("+{}", b_mid[j]));
+            j += 1;
+        } else {
+            diff.push(format!("-{}", a_mid[i]));
+            i += 1;
+        }
+    }
+    diff.extend(a[a.len() - suffix..].iter().map(|line| format!(" {}", line)));
+    diff
+}
+
+fn referenced_secrets(tool: &models::ConfiguredTool) -> Result<Vec<String>> {
+    let mut texts = vec![serde_json::to_string(&tool.webhook_auth)?];
+    texts.extend([&tool.webhook_url, &tool.webhook_headers, &tool.webhook_body_template].into_iter().flatten().cloned());
+    let mut names: Vec<String> = texts.iter().flat_map(|text| secrets::references(text)).collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// How importing the bundle at `path` would change the tools.
+pub fn preview(state: &AppState, path: &Path) -> Result<BundlePreview> {
+    let bundle = read_bundle(path)?;
+    let manifest = &bundle.manifest;
+    let (settings, existing, installed) = {
+        let conn = state.db.lock().unwrap();
+        (
+            queries::get_settings(&conn)?,
+            queries::get_configured_tool_by_id(&conn, &manifest.tool.id)?,
+            queries::get_installed_tool_bundle(&conn, &manifest.tool.id)?,
+        )
+    };
+    let (signature, publisher) = signature_status(manifest, &trusted_keys(&settings)?);
+    let tool = tool_to_install(state, manifest, existing.as_ref())?;
+
+    let new_script = bundle.files.first().map(|(_, data)| String::from_utf8_lossy(data).to_string()).unwrap_or_default();
+    let old_script = existing.as_ref()
+        .and_then(|e| e.script_path.as_deref())
+        .and_then(|p| fs::read(p).ok())
+        .map(|data| String::from_utf8_lossy(&data).to_string())
+        .unwrap_or_default();
+
+    let mut warnings = Vec::new();
+    if let Some(problem) = signature_problem(signature, signature_required(state, &settings, path)) {
+        warnings.push(format!("{}; it can't be imported", problem));
+    }
+    if existing.is_some() && installed.is_none() {
+        warnings.push(format!("'{}' wasn't imported from a bundle; importing replaces it", manifest.tool.name));
+    }
+    if !tool.dependencies.is_empty() {
+        warnings.push(format!("Installs on first run: {}", tool.dependencies.join(", ")));
+    }
+    let webhook_host = tool.webhook_url.as_deref()
+        .filter(|_| tool.runtime == models::ToolRuntime::Webhook)
+        .map(|url| webhook::url_authority(url).rsplit('@').next().unwrap_or_default().to_string());
+    if webhook_host.as_deref().is_some_and(|host| host.contains("{{")) {
+        warnings.push("The webhook host comes from the params, so the tool can't be given secrets".to_string());
+    }
+    let secrets = {
+        let conn = state.db.lock().unwrap();
+        referenced_secrets(&tool)?.into_iter()
+            .map(|name| SecretUse {
+                stored: queries::secret_exists(&conn, &name).unwrap_or(false),
+                allowed: existing.as_ref().is_some_and(|e| e.allowed_secrets.contains(&name)),
+                name,
+            })
+            .collect()
+    };
+
+    Ok(BundlePreview {
+        path: path.to_string_lossy().to_string(),
+        hash: manifest.hash.clone(),
+        signature,
+        publisher,
+        exported_at: manifest.exported_at,
+        changes: match &existing {
+            Some(existing) => field_changes(existing, &tool)?,
+            None => vec![],
+        },
+        script_diff: line_diff(&old_script, &new_script),
+        installed_hash: installed.map(|i| i.hash),
+        existing,
+        tool,
+        webhook_host,
+        secrets,
+        warnings,
+    })
+}
+
+/// Imports the bundle at `path`, adding its tool or updating the one with the same ID.
+/// `expected_hash` is the hash from the preview; a bundle that changed since is refused.
+/// `approved_secrets` must name every secret the tool references, and becomes its allow-list.
+pub fn import(state: &AppState, path: &Path, expected_hash: &str, approved_secrets: &[String]) -> Result<models::ConfiguredTool> {
+    let bundle = read_bundle(path)?;
+    let manifest = &bundle.manifest;
+    if manifest.hash != expected_hash {
+        return Err(AppError::Config("The bundle changed since it was reviewed; preview it again".to_string()));
+    }
+    let (settings, existing) = {
+        let conn = state.db.lock().unwrap();
+        (queries::get_settings(&conn)?, queries::get_configured_tool_by_id(&conn, &manifest.tool.id)?)
+    };
+    let (signature, _) = signature_status(manifest, &trusted_keys(&settings)?);
+    if let Some(problem) = signature_problem(signature, signature_required(state, &settings, path)) {
+        return Err(AppError::Config(problem.to_string()));
+    }
+
+    let mut tool = tool_to_install(state, manifest, existing.as_ref())?;
+    let referenced = referenced_secrets(&tool)?;
+    if let Some(name) = referenced.iter().find(|name| !approved_secrets.contains(name)) {
+        return Err(AppError::Config(format!("'{}' uses the secret '{}', which wasn't approved", tool.name, name)));
+    }
+    tool.allowed_secrets = referenced;
+    let dir = install_dir(state, &tool.id)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    for (bundle_path, data) in &bundle.files {
+        let name = bundle_path.rsplit('/').next().unwrap_or(bundle_path);
+        fs::write(dir.join(name), data)?;
+    }
+
+    let conn = state.db.lock().unwrap();
+    queries::save_configured_tool(&conn, &tool)?;
+    queries::save_installed_tool_bundle(&conn, &models::InstalledToolBundle {
+        tool_id: tool.id.clone(),
+        hash: manifest.hash.clone(),
+        source: path.to_string_lossy().to_string(),
+        installed_at: chrono::Utc::now().timestamp_millis(),
+    })?;
+    log::info!("[Tool Service] {} '{}' from {}", if existing.is_some() { "Updated" } else { "Imported" }, tool.name, path.display());
+    Ok(tool)
+}
+
+fn repositories_dir(state: &AppState) -> PathBuf {
+    state.context.app_data_dir.join("tool_repos")
+}
+
+fn repository_dir(state: &AppState, url: &str) -> PathBuf {
+    let hash = sha256_hex(url.trim().as_bytes());
+    repositories_dir(state).join(&hash[..16])
+}
+
+/// Whether a bundle at `path` needs a trusted signature: as its repository says for a cloned
+/// repository, as the sharing settings say otherwise.
+fn signature_required(state: &AppState, settings: &models::Settings, path: &Path) -> bool {
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let path = canonical(path);
+    if !path.starts_with(canonical(&repositories_dir(state))) {
+        return settings.execution.sharing.require_signature;
+    }
+    let repository = settings.execution.sharing.repositories.iter()
+        .find(|repo| path.starts_with(canonical(&repository_dir(state, &repo.url))));
+    match repository {
+        Some(repo) => repo.require_signature,
+        // A clone of a repository that has since been removed from the settings.
+        None => true,
+    }
+}
+
+fn bundle_files(dir: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(dir).max_depth(4).into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().and_then(|x| x.to_str()).is_some_and(|x| x.eq_ignore_ascii_case(EXTENSION)))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Bundles in the shared folders and the cloned repositories, with how they relate to the installed tools.
+pub fn list_available(state: &AppState) -> Result<Vec<AvailableBundle>> {
+    let (settings, tools, installed) = {
+        let conn = state.db.lock().unwrap();
+        (queries::get_settings(&conn)?, queries::list_configured_tools(&conn)?, queries::list_installed_tool_bundles(&conn)?)
+    };
+    let trusted = trusted_keys(&settings)?;
+    let installed: HashMap<String, String> = installed.into_iter().map(|b| (b.tool_id, b.hash)).collect();
+    let sharing = &settings.execution.sharing;
+    let sources = sharing.bundle_directories.iter()
+        .map(|dir| (dir.clone(), PathBuf::from(dir)))
+        .chain(sharing.repositories.iter().map(|repo| (repo.url.clone(), repository_dir(state, &repo.url))));
+
+    let mut available = Vec::new();
+    for (source, dir) in sources {
+        for path in bundle_files(&dir) {
+            let manifest = match read_bundle(&path) {
+                Ok(bundle) => bundle.manifest,
+                Err(e) => {
+                    log::warn!("[Tool Service] Skipping bundle {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            let status = match installed.get(&manifest.tool.id) {
+                Some(hash) if *hash == manifest.hash => BundleStatus::Installed,
+                Some(_) => BundleStatus::UpdateAvailable,
+                None if tools.iter().any(|t| t.id == manifest.tool.id) => BundleStatus::UpdateAvailable,
+                None => BundleStatus::New,
+            };
+            let (signature, publisher) = signature_status(&manifest, &trusted);
+            available.push(AvailableBundle {
+                path: path.to_string_lossy().to_string(),
+                source: source.clone(),
+                signature,
+                publisher,
+                tool_id: manifest.tool.id,
+                name: manifest.tool.name,
+                description: manifest.tool.description,
+                hash: manifest.hash,
+                exported_at: manifest.exported_at,
+                status,
+            });
+        }
+    }
+    available.sort_by_key(|bundle| bundle.name.to_lowercase());
+    Ok(available)
+}
+
+async fn git(args: &[&str]) -> Result<()> {
+    let mut command = Command::new("git");
+    command.args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(GIT_TIMEOUT, command.output()).await
+        .map_err(|_| AppError::Timeout(format!("git {} took longer than {} seconds", args[0], GIT_TIMEOUT.as_secs())))?
+        .map_err(|e| AppError::Config(format!("Could not run git: {}", e)))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Config(format!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim())))
+    }
+}
+
+/// Clones a repository, or brings an existing clone to the latest commit of its branch.
+async fn sync_repository(state: &AppState, repo: &models::ToolRepository) -> Result<()> {
+    let url = repo.url.trim();
+    let branch = repo.branch.as_deref().map(str::trim).filter(|b| !b.is_empty());
+    if url.is_empty() || url.starts_with('-') || branch.is_some_and(|b| b.starts_with('-')) {
+        return Err(AppError::Config(format!("Invalid repository: {}", repo.url)));
+    }
+    let dir = repository_dir(state, url);
+    let dir_str = dir.to_string_lossy().to_string();
+    if dir.join(".git").is_dir() {
+        git(&["-C", &dir_str, "fetch", "--depth", "1", "origin", branch.unwrap_or("HEAD")]).await?;
+        git(&["-C", &dir_str, "reset", "--hard", "FETCH_HEAD"]).await?;
+    } else {
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(repositories_dir(state))?;
+        let mut args = vec!["clone", "--depth", "1"];
+        if let Some(branch) = branch {
+            args.extend(["--branch", branch]);
+        }
+        args.extend(["--", url, &dir_str]);
+        git(&args).await?;
+    }
+    log::info!("[Tool Service] Synced tool repository {}", url);
+    Ok(())
+}
+
+/// Clones or updates every configured repository. A failing one doesn't stop the others.
+pub async fn sync_repositories(state: &AppState) -> Result<Vec<RepositorySync>> {
+    let repositories = queries::get_settings(&state.db.lock().unwrap())?.execution.sharing.repositories;
+    let mut results = Vec::new();
+    for repo in &repositories {
+        let error = sync_repository(state, repo).await.err().map(|e| {
+            log::warn!("[Tool Service] Syncing {} failed: {}", repo.url, e);
+            e.to_string()
+        });
+        results.push(RepositorySync { url: repo.url.clone(), error });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool() -> models::ConfiguredTool {
+        serde_json::from_value(json!({
+            "id": "tool-1",
+            "name": "Convert",
+            "description": "Converts files",
+            "scriptPath": "scripts/convert.py",
+            "runtime": "python",
+            "parameters": [],
+        }))
+        .unwrap()
+    }
+
+    fn manifest() -> BundleManifest {
+        BundleManifest {
+            format_version: FORMAT_VERSION,
+            tool: tool(),
+            files: vec![BundleFile { path: "scripts/convert.py".to_string(), sha256: sha256_hex(b"print('hi')") }],
+            exported_at: 1_700_000_000,
+            hash: String::new(),
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn manifest_hash_ignores_hash_and_signature() {
+        let unsigned = manifest();
+        let mut signed = manifest();
+        signed.hash = manifest_hash(&unsigned).unwrap();
+        signed.signature = Some(BundleSignature { public_key: "key".to_string(), signature: "sig".to_string() });
+        assert_eq!(manifest_hash(&signed).unwrap(), signed.hash);
+    }
+
+    #[test]
+    fn manifest_hash_covers_the_tool_and_files() {
+        let original = manifest_hash(&manifest()).unwrap();
+        let mut renamed = manifest();
+        renamed.tool.name = "Convert everything".to_string();
+        assert_ne!(manifest_hash(&renamed).unwrap(), original);
+        let mut changed = manifest();
+        changed.files[0].sha256 = sha256_hex(b"print('bye')");
+        assert_ne!(manifest_hash(&changed).unwrap(), original);
+    }
+
+    #[test]
+    fn field_changes_skip_local_fields() {
+        let before = tool();
+        let mut after = tool();
+        after.description = "Converts more files".to_string();
+        after.timeout_secs = Some(30);
+        after.is_favorite = true;
+        after.script_path = Some("/elsewhere/convert.py".to_string());
+        let changes = field_changes(&before, &after).unwrap();
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["description", "timeoutSecs"]);
+        assert_eq!(changes[1].before, Value::Null);
+        assert_eq!(changes[1].after, json!(30));
+        assert!(field_changes(&before, &tool()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn signature_status_checks_the_key_and_trust() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut signed = manifest();
+        signed.hash = manifest_hash(&signed).unwrap();
+        signed.signature = Some(sign(&key, &signed.hash));
+        let public_key = encode_key(&key.verifying_key());
+
+        assert_eq!(signature_status(&manifest(), &[]).0, SignatureStatus::Unsigned);
+        assert_eq!(signature_status(&signed, &[]).0, SignatureStatus::Untrusted);
+        let trusted = [("Ann".to_string(), public_key)];
+        assert_eq!(signature_status(&signed, &trusted), (SignatureStatus::Trusted, Some("Ann".to_string())));
+
+        signed.hash = sha256_hex(b"something else");
+        assert_eq!(signature_status(&signed, &trusted).0, SignatureStatus::Invalid);
+    }
+}
@@ -0,0 +1,330 @@
+// src-tauri/src/services/tools/environments.rs
+// Isolated environments for tools that declare dependencies. A Python tool gets a virtualenv
+// and a Node tool a `node_modules` directory, created and installed on its first run. Both
+// are kept under `<app data>/tool_envs` and keyed by interpreter and dependency list, so
+// tools with the same dependencies share an environment and tools with conflicting ones
+// don't interfere. Installer output is streamed as `tool-env-progress` events.
+use super::{cancelled_error, timeout_error, ProcessRegistration};
+use crate::{
+    database::models,
+    error::{AppError, Result},
+    state::AppState,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+/// Written once installation succeeded; an environment without it is rebuilt.
+const MANIFEST: &str = "nexus-env.json";
+/// Installer output kept for the error message when an install fails.
+const ERROR_TAIL_LINES: usize = 20;
+/// How long one installer step may run; large packages can take minutes to build.
+const STEP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// One lock per environment, so concurrent first runs of a tool install only once.
+static INSTALL_LOCKS: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentInfo {
+    pub key: String,
+    pub runtime: models::ToolRuntime,
+    pub dependencies: Vec<String>,
+    /// "uv", "pip" or "npm".
+    pub installer: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub size_bytes: u64,
+}
+
+/// What a tool runs with instead of the global interpreter.
+pub struct ToolEnvironment {
+    pub dir: PathBuf,
+    /// The environment's own interpreter, for Python.
+    pub program: Option<String>,
+    pub env: Vec<(String, String)>,
+}
+
+fn environments_dir(state: &AppState) -> PathBuf {
+    state.context.app_data_dir.join("tool_envs")
+}
+
+fn runtime_prefix(runtime: &models::ToolRuntime) -> &'static str {
+    match runtime {
+        models::ToolRuntime::Node => "node",
+        _ => "python",
+    }
+}
+
+/// Same interpreter and same dependencies, in any order, give the same environment.
+fn environment_key(runtime: &models::ToolRuntime, interpreter: &str, dependencies: &[String]) -> String {
+    let mut sorted: Vec<&str> = dependencies.iter().map(|d| d.trim()).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let digest = Sha256::digest(format!("{}\n{}", interpreter, sorted.join("\n")).as_bytes());
+    let hash: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}", runtime_prefix(runtime), hash)
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let names = if cfg!(target_os = "windows") { vec![format!("{}.exe", name), format!("{}.cmd", name)] } else { vec![name.to_string()] };
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+fn venv_python(dir: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") { dir.join("Scripts").join("python.exe") } else { dir.join("bin").join("python") }
+}
+
+/// npm next to the configured Node, or the one on the PATH.
+fn npm_program(node: &str) -> String {
+    let npm = if cfg!(target_os = "windows") { "npm.cmd" } else { "npm" };
+    Path::new(node).parent()
+        .map(|dir| dir.join(npm))
+        .filter(|candidate| candidate.is_file())
+        .map(|candidate| candidate.to_string_lossy().to_string())
+        .unwrap_or_else(|| npm.to_string())
+}
+
+fn installer_for(settings: &models::Settings) -> Result<Option<PathBuf>> {
+    match settings.execution.package_installer {
+        models::PackageInstaller::Pip => Ok(None),
+        models::PackageInstaller::Auto => Ok(find_in_path("uv")),
+        models::PackageInstaller::Uv => find_in_path("uv")
+            .map(Some)
+            .ok_or_else(|| AppError::Config("uv is selected as the package installer but is not on the PATH".to_string())),
+    }
+}
+
+fn emit_progress(app: &AppHandle, task_id: &str, key: &str, stage: &str, line: Option<&str>) {
+    app.emit_all("tool-env-progress", json!({ "taskId": task_id, "environment": key, "stage": stage, "line": line })).ok();
+}
+
+/// A dependency spec such as `-r requirements.txt` or `--index-url=…` would be read as an
+/// installer option rather than a package.
+pub fn validate_dependencies(dependencies: &[String]) -> Result<()> {
+    match dependencies.iter().find(|d| d.trim().is_empty() || d.trim_start().starts_with('-')) {
+        Some(spec) => Err(AppError::Config(format!("Invalid dependency '{}': dependencies must be package names or specifiers, not installer options", spec))),
+        None => Ok(()),
+    }
+}
+
+/// The run an environment is installed for, whose task ID its installer steps report under.
+struct Install<'a> {
+    app: &'a AppHandle,
+    state: &'a AppState,
+    task_id: &'a str,
+    key: &'a str,
+}
+
+/// Runs an installer step, streaming its output. The step is registered under the task ID,
+/// so cancelling the task stops it, and is stopped after `STEP_TIMEOUT`. On failure the error
+/// carries the last lines.
+async fn run_step(install: &Install<'_>, mut command: Command) -> Result<()> {
+    let Install { app, state, task_id, key } = *install;
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::Config(format!("Could not start the installer: {}", e)))?;
+    let registration = ProcessRegistration::new(state, task_id, child.id());
+
+    let status = match tokio::time::timeout(STEP_TIMEOUT, stream_step(app, task_id, key, &mut child)).await {
+        Ok(status) => status,
+        Err(_) => {
+            registration.kill();
+            child.wait().await.ok();
+            return Err(timeout_error(STEP_TIMEOUT));
+        }
+    };
+    if registration.cancelled() {
+        return Err(cancelled_error());
+    }
+    let (status, tail) = status?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Internal(format!("Installing dependencies failed ({}):\n{}", status, Vec::from(tail).join("\n"))))
+    }
+}
+
+async fn stream_step(app: &AppHandle, task_id: &str, key: &str, child: &mut tokio::process::Child) -> Result<(std::process::ExitStatus, VecDeque<String>)> {
+    let mut stdout = BufReader::new(child.stdout.take().ok_or_else(|| AppError::Internal("Failed to capture stdout".to_string()))?).lines();
+    let mut stderr = BufReader::new(child.stderr.take().ok_or_else(|| AppError::Internal("Failed to capture stderr".to_string()))?).lines();
+    let mut tail = VecDeque::with_capacity(ERROR_TAIL_LINES);
+    let (mut stdout_open, mut stderr_open) = (true, true);
+    while stdout_open || stderr_open {
+        let line = tokio::select! {
+            line = stdout.next_line(), if stdout_open => line.ok().flatten().or_else(|| { stdout_open = false; None }),
+            line = stderr.next_line(), if stderr_open => line.ok().flatten().or_else(|| { stderr_open = false; None }),
+        };
+        if let Some(line) = line {
+            emit_progress(app, task_id, key, "installing", Some(&line));
+            if tail.len() == ERROR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    }
+
+    Ok((child.wait().await?, tail))
+}
+
+async fn create_python(install: &Install<'_>, dir: &Path, python: &str, uv: Option<&Path>, dependencies: &[String]) -> Result<String> {
+    let target = venv_python(dir);
+    match uv {
+        Some(uv) => {
+            let mut venv = Command::new(uv);
+            venv.arg("venv").arg(dir).arg("--python").arg(python);
+            run_step(install, venv).await?;
+            let mut pip = Command::new(uv);
+            pip.args(["pip", "install", "--python"]).arg(&target).arg("--").args(dependencies);
+            run_step(install, pip).await?;
+            Ok("uv".to_string())
+        }
+        None => {
+            let mut venv = Command::new(python);
+            venv.args(["-m", "venv"]).arg(dir);
+            run_step(install, venv).await?;
+            let mut pip = Command::new(&target);
+            pip.args(["-m", "pip", "install", "--disable-pip-version-check", "--"]).args(dependencies);
+            run_step(install, pip).await?;
+            Ok("pip".to_string())
+        }
+    }
+}
+
+async fn create_node(install: &Install<'_>, dir: &Path, node: &str, dependencies: &[String]) -> Result<String> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("package.json"), serde_json::to_string_pretty(&json!({ "name": install.key, "private": true }))?)?;
+    let mut npm = Command::new(npm_program(node));
+    npm.args(["install", "--no-audit", "--no-fund", "--"]).args(dependencies).current_dir(dir);
+    run_step(install, npm).await?;
+    Ok("npm".to_string())
+}
+
+/// The environment for a tool's dependencies, created and installed if this is its first run.
+/// `interpreter` is the global Python or Node the environment is based on.
+pub async fn prepare(
+    app: &AppHandle,
+    state: &AppState,
+    settings: &models::Settings,
+    runtime: &models::ToolRuntime,
+    interpreter: &str,
+    dependencies: &[String],
+    task_id: &str,
+) -> Result<ToolEnvironment> {
+    validate_dependencies(dependencies)?;
+    let key = environment_key(runtime, interpreter, dependencies);
+    let dir = environments_dir(state).join(&key);
+
+    let lock = INSTALL_LOCKS.lock().unwrap().entry(key.clone()).or_default().clone();
+    let _guard = lock.lock().await;
+
+    if !dir.join(MANIFEST).is_file() {
+        log::info!("[Tool Service] Creating environment {} for {:?}", key, dependencies);
+        emit_progress(app, task_id, &key, "creating", None);
+        if dir.exists() {
+            // Left over from an install that failed or was interrupted.
+            fs::remove_dir_all(&dir)?;
+        }
+        let install = Install { app, state, task_id, key: &key };
+        let created = match runtime {
+            models::ToolRuntime::Node => create_node(&install, &dir, interpreter, dependencies).await,
+            _ => {
+                let uv = installer_for(settings)?;
+                create_python(&install, &dir, interpreter, uv.as_deref(), dependencies).await
+            }
+        };
+        let installer = match created {
+            Ok(installer) => installer,
+            Err(e) => {
+                emit_progress(app, task_id, &key, "failed", Some(&e.to_string()));
+                fs::remove_dir_all(&dir).ok();
+                return Err(e);
+            }
+        };
+        let info = EnvironmentInfo {
+            key: key.clone(),
+            runtime: runtime.clone(),
+            dependencies: dependencies.to_vec(),
+            installer,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            size_bytes: 0,
+        };
+        fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(&info)?)?;
+        emit_progress(app, task_id, &key, "ready", None);
+    }
+
+    Ok(match runtime {
+        models::ToolRuntime::Node => ToolEnvironment {
+            env: vec![("NODE_PATH".to_string(), dir.join("node_modules").to_string_lossy().to_string())],
+            program: None,
+            dir,
+        },
+        _ => ToolEnvironment {
+            program: Some(venv_python(&dir).to_string_lossy().to_string()),
+            env: vec![("VIRTUAL_ENV".to_string(), dir.to_string_lossy().to_string())],
+            dir,
+        },
+    })
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir).into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+pub fn list(state: &AppState) -> Result<Vec<EnvironmentInfo>> {
+    let dir = environments_dir(state);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut environments = Vec::new();
+    for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+        let Ok(manifest) = fs::read_to_string(entry.path().join(MANIFEST)) else { continue };
+        match serde_json::from_str::<EnvironmentInfo>(&manifest) {
+            Ok(info) => environments.push(EnvironmentInfo { size_bytes: dir_size(&entry.path()), ..info }),
+            Err(e) => log::warn!("[Tool Service] Unreadable environment manifest in {:?}: {}", entry.path(), e),
+        }
+    }
+    environments.sort_by_key(|info| std::cmp::Reverse(info.created_at));
+    Ok(environments)
+}
+
+/// Deletes an environment; the next run of a tool that needs it installs it again.
+pub async fn remove(state: &AppState, key: &str) -> Result<()> {
+    if key.is_empty() || key.contains(['/', '\\']) || key.contains("..") {
+        return Err(AppError::Config(format!("Invalid environment key: {}", key)));
+    }
+    let lock = INSTALL_LOCKS.lock().unwrap().entry(key.to_string()).or_default().clone();
+    let _guard = lock.lock().await;
+    let dir = environments_dir(state).join(key);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+        log::info!("[Tool Service] Removed environment {}", key);
+    }
+    Ok(())
+}
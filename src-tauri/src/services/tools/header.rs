@@ -0,0 +1,397 @@
+// src-tauri/src/services/tools/header.rs
+// Reads the metadata block that turns a script in a scripts directory into a tool. The block
+// is JSON between a `### NEXUS-TOOL ###` and a `### NEXUS-TOOL-END ###` line, each line
+// behind the script's comment marker (`#` or `//`):
+//
+//     # ### NEXUS-TOOL ###
+//     # {
+//     #   "name": "Resize images",
+//     #   "description": "Resizes every image in a folder",
+//     #   "parameters": [
+//     #     { "name": "folder", "type": "directory", "required": true },
+//     #     { "name": "width", "type": "number", "default": "1024" }
+//     #   ],
+//     #   "timeoutSecs": 120,
+//     #   "interpreterVersion": ">=3.10",
+//     #   "dependencies": ["pillow>=10"]
+//     # }
+//     # ### NEXUS-TOOL-END ###
+//
+// Keys are those of a configured tool: `name`, `description`, `runtime` (taken from the file
+// extension when left out), `parameters`, `inputSchema`, `inputSource`, `outputHandling`,
+// `timeoutSecs`, `argumentMapping`, `interpreterVersion` and `dependencies`.
+use super::params;
+use crate::{
+    commands::tools::DynamicTool,
+    database::models::{self, ToolArgumentMapping, ToolInputSource, ToolOutputHandling, ToolParameter, ToolParameterType, ToolRuntime},
+    error::{AppError, Result},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+const KNOWN_KEYS: &[&str] = &[
+    "name", "description", "runtime", "parameters", "inputSchema", "inputSource", "outputHandling",
+    "timeoutSecs", "argumentMapping", "interpreterVersion", "dependencies",
+];
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ToolHeader {
+    name: String,
+    description: String,
+    #[serde(default)]
+    runtime: Option<ToolRuntime>,
+    #[serde(default)]
+    parameters: Vec<ToolParameter>,
+    #[serde(default)]
+    input_schema: Option<Value>,
+    #[serde(default)]
+    input_source: Option<ToolInputSource>,
+    #[serde(default)]
+    output_handling: Option<ToolOutputHandling>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    #[serde(default)]
+    argument_mapping: ToolArgumentMapping,
+    #[serde(default)]
+    interpreter_version: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+/// A problem in a script's metadata block. Scripts with errors aren't listed as tools.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderIssue {
+    pub path: String,
+    /// 1-based line in the script, when the problem can be pinned to one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+pub fn runtime_from_extension(path: &Path) -> ToolRuntime {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("py") => ToolRuntime::Python,
+        Some("js") | Some("mjs") | Some("cjs") => ToolRuntime::Node,
+        _ => ToolRuntime::Shell,
+    }
+}
+
+fn uncomment(line: &str) -> &str {
+    let line = line.trim();
+    line.strip_prefix("//").unwrap_or(line).trim_start_matches('#')
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    uncomment(line).trim().trim_matches('#').trim() == marker
+}
+
+/// The block's JSON, and the line it starts on.
+fn find_block(content: &str) -> std::result::Result<Option<(String, usize)>, (usize, String)> {
+    let mut lines = content.lines().enumerate();
+    let Some((start, _)) = lines.by_ref().find(|(_, line)| is_marker(line, "NEXUS-TOOL")) else {
+        return Ok(None);
+    };
+    let mut json = String::new();
+    for (_, line) in lines {
+        if is_marker(line, "NEXUS-TOOL-END") {
+            return Ok(Some((json, start + 2)));
+        }
+        json.push_str(uncomment(line));
+        json.push('\n');
+    }
+    Err((start + 1, "The block has no ### NEXUS-TOOL-END ### line".to_string()))
+}
+
+/// Reads the tool a script declares, with everything wrong about its block. No tool is
+/// returned when there is no block or an issue is an error.
+pub fn read(path: &Path, content: &str) -> (Option<DynamicTool>, Vec<HeaderIssue>) {
+    let path_str = path.to_string_lossy().to_string();
+    let mut issues = Vec::new();
+    let mut issue = |line: Option<usize>, severity: IssueSeverity, message: String| {
+        issues.push(HeaderIssue { path: path_str.clone(), line, severity, message });
+    };
+
+    let (json, first_line) = match find_block(content) {
+        Ok(Some(block)) => block,
+        Ok(None) => return (None, vec![]),
+        Err((line, message)) => {
+            issue(Some(line), IssueSeverity::Error, message);
+            return (None, issues);
+        }
+    };
+
+    let raw: Value = match serde_json::from_str(&json) {
+        Ok(raw) => raw,
+        Err(e) => {
+            issue(Some(first_line + e.line().saturating_sub(1)), IssueSeverity::Error, format!("Invalid JSON: {}", e));
+            return (None, issues);
+        }
+    };
+    let Some(keys) = raw.as_object() else {
+        issue(Some(first_line), IssueSeverity::Error, "The block must be a JSON object".to_string());
+        return (None, issues);
+    };
+    for key in keys.keys().filter(|key| !KNOWN_KEYS.contains(&key.as_str())) {
+        issue(None, IssueSeverity::Warning, format!("Unknown key '{}' is ignored", key));
+    }
+
+    let header: ToolHeader = match serde_json::from_value(raw.clone()) {
+        Ok(header) => header,
+        Err(e) => {
+            issue(None, IssueSeverity::Error, e.to_string());
+            return (None, issues);
+        }
+    };
+
+    let runtime = header.runtime.clone().unwrap_or_else(|| runtime_from_extension(path));
+    if header.name.trim().is_empty() {
+        issue(None, IssueSeverity::Error, "'name' is empty".to_string());
+    }
+    if runtime == ToolRuntime::Webhook {
+        issue(None, IssueSeverity::Error, "A script can't use the webhook runtime".to_string());
+    }
+    if !matches!(runtime, ToolRuntime::Python | ToolRuntime::Node) {
+        if !header.dependencies.is_empty() {
+            issue(None, IssueSeverity::Warning, "'dependencies' are only installed for Python and Node scripts".to_string());
+        }
+        if header.interpreter_version.is_some() {
+            issue(None, IssueSeverity::Warning, "'interpreterVersion' is only checked for Python and Node scripts".to_string());
+        }
+    }
+    if let Err(e) = super::environments::validate_dependencies(&header.dependencies) {
+        issue(None, IssueSeverity::Error, e.to_string());
+    }
+    if let Some(requirement) = &header.interpreter_version {
+        if let Err(e) = parse_requirement(requirement) {
+            issue(None, IssueSeverity::Error, e.to_string());
+        }
+    }
+    if header.input_schema.as_ref().is_some_and(|schema| !schema.is_object()) {
+        issue(None, IssueSeverity::Error, "'inputSchema' must be a JSON Schema object".to_string());
+    }
+
+    let mut names = HashSet::new();
+    for p in &header.parameters {
+        if p.name.trim().is_empty() {
+            issue(None, IssueSeverity::Error, "A parameter has no name".to_string());
+        } else if !names.insert(p.name.as_str()) {
+            issue(None, IssueSeverity::Error, format!("Parameter '{}' is declared twice", p.name));
+        }
+        if p.param_type == ToolParameterType::Select && p.options.is_empty() {
+            issue(None, IssueSeverity::Error, format!("Select parameter '{}' has no options", p.name));
+        }
+    }
+    let tool = DynamicTool {
+        id: format!("dynamic::{}", path_str),
+        name: header.name,
+        description: header.description,
+        script_path: path_str.clone(),
+        runtime,
+        parameters: header.parameters,
+        input_schema: header.input_schema.map(|schema| schema.to_string()),
+        input_source: header.input_source.unwrap_or(ToolInputSource::UserInput),
+        output_handling: header.output_handling.unwrap_or(ToolOutputHandling::RawText),
+        timeout_secs: header.timeout_secs,
+        argument_mapping: header.argument_mapping,
+        interpreter_version: header.interpreter_version,
+        dependencies: header.dependencies,
+    };
+
+    // Defaults are checked like any other input, against the schema derived from the parameters.
+    let defaults: serde_json::Map<String, Value> = tool.parameters.iter()
+        .filter(|p| !p.default_value.is_empty())
+        .map(|p| (p.name.clone(), Value::String(p.default_value.clone())))
+        .collect();
+    let declared = models::ConfiguredTool { input_schema: None, ..to_configured_tool(&tool) };
+    if let Ok(Some(schema)) = params::input_schema(&declared) {
+        let normalized = params::normalize(&tool.parameters, Value::Object(defaults));
+        for error in params::validate(&schema, &normalized).into_iter().filter(|e| e.message != "is required") {
+            issue(None, IssueSeverity::Warning, format!("Default of {} {}", error.field.trim_start_matches('/'), error.message));
+        }
+    }
+
+    if issues.iter().any(|i| i.severity == IssueSeverity::Error) {
+        return (None, issues);
+    }
+    (Some(tool), issues)
+}
+
+/// The tool a script declares, or `None` when it has no metadata block. Warnings are logged;
+/// the first error fails the parse.
+pub fn parse_script_for_tool(path: &Path) -> Result<Option<DynamicTool>> {
+    let content = std::fs::read_to_string(path)?;
+    let (tool, issues) = read(path, &content);
+    for issue in &issues {
+        match issue.severity {
+            IssueSeverity::Warning => log::warn!("[Tool Service] {:?}: {}", path, issue.message),
+            IssueSeverity::Error => {
+                let at = issue.line.map(|line| format!(" (line {})", line)).unwrap_or_default();
+                return Err(AppError::Parse(format!("Invalid tool metadata in {:?}{}: {}", path, at, issue.message)));
+            }
+        }
+    }
+    Ok(tool)
+}
+
+/// A discovered tool as a `ConfiguredTool`, so it runs through the same validation and execution.
+pub fn to_configured_tool(tool: &DynamicTool) -> models::ConfiguredTool {
+    models::ConfiguredTool {
+        id: tool.id.clone(),
+        name: tool.name.clone(),
+        description: tool.description.clone(),
+        script_path: Some(tool.script_path.clone()),
+        webhook_url: None,
+        webhook_method: None,
+        webhook_headers: None,
+        webhook_body_template: None,
+        input_schema: tool.input_schema.clone(),
+        runtime: tool.runtime.clone(),
+        parameters: tool.parameters.clone(),
+        show_in_copilot: false,
+        is_favorite: false,
+        input_source: tool.input_source.clone(),
+        requires_ai_pre_processing: false,
+        pre_processing_prompt: String::new(),
+        output_handling: tool.output_handling.clone(),
+        requires_ai_post_processing: false,
+        post_processing_prompt: String::new(),
+        sandbox: None,
+        timeout_secs: tool.timeout_secs,
+        argument_mapping: tool.argument_mapping,
+        interpreter_version: tool.interpreter_version.clone(),
+        dependencies: tool.dependencies.clone(),
+        webhook_auth: None,
+        webhook_response_path: None,
+        webhook_retries: 0,
+        allowed_secrets: vec![],
+    }
+}
+
+/// One comparison of an interpreter version requirement such as ">=3.10, <4".
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionBound {
+    op: String,
+    version: Vec<u64>,
+}
+
+pub fn parse_version(text: &str) -> Option<Vec<u64>> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let version: String = text[start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    version.trim_end_matches('.').split('.').map(|part| part.parse().ok()).collect()
+}
+
+pub fn parse_requirement(requirement: &str) -> Result<Vec<VersionBound>> {
+    requirement.split(',').map(str::trim).filter(|part| !part.is_empty())
+        .map(|part| {
+            let op_len = part.find(|c: char| c.is_ascii_digit()).unwrap_or(part.len());
+            let (op, version) = part.split_at(op_len);
+            let op = op.trim();
+            let version = parse_version(version).filter(|_| matches!(op, "" | "=" | "==" | ">=" | "<=" | ">" | "<"));
+            version
+                .map(|version| VersionBound { op: op.to_string(), version })
+                .ok_or_else(|| AppError::Config(format!("Invalid interpreter version requirement '{}'", part)))
+        })
+        .collect()
+}
+
+/// Whether `version` satisfies every bound. A bare or `==` bound matches by prefix, so "3.11"
+/// accepts 3.11.4; the other comparisons pad missing components with zeros.
+pub fn satisfies(version: &[u64], bounds: &[VersionBound]) -> bool {
+    bounds.iter().all(|bound| {
+        let width = version.len().max(bound.version.len());
+        let pad = |v: &[u64]| (0..width).map(|i| v.get(i).copied().unwrap_or(0)).collect::<Vec<_>>();
+        let (actual, wanted) = (pad(version), pad(&bound.version));
+        match bound.op.as_str() {
+            ">=" => actual >= wanted,
+            "<=" => actual <= wanted,
+            ">" => actual > wanted,
+            "<" => actual < wanted,
+            _ => version.starts_with(&bound.version),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(block: &str) -> String {
+        let body: String = block.lines().map(|line| format!("# {}\n", line)).collect();
+        format!("#!/usr/bin/env python3\n# ### NEXUS-TOOL ###\n{}# ### NEXUS-TOOL-END ###\nprint('hi')\n", body)
+    }
+
+    #[test]
+    fn reads_a_valid_block() {
+        let content = script(r#"{
+  "name": "Resize images",
+  "description": "Resizes every image in a folder",
+  "parameters": [{ "name": "width", "type": "number", "default": "1024" }],
+  "interpreterVersion": ">=3.10"
+}"#);
+        let (tool, issues) = read(Path::new("/scripts/resize.py"), &content);
+        assert!(issues.is_empty(), "{:?}", issues);
+        let tool = tool.unwrap();
+        assert_eq!(tool.name, "Resize images");
+        assert_eq!(tool.runtime, ToolRuntime::Python);
+        assert_eq!(tool.id, "dynamic::/scripts/resize.py");
+    }
+
+    #[test]
+    fn scripts_without_a_block_are_not_tools() {
+        let (tool, issues) = read(Path::new("plain.sh"), "echo hello\n");
+        assert!(tool.is_none());
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn reports_the_script_line_of_invalid_json() {
+        let content = script("{\n  \"name\": \"Broken\",\n  \"description\": \n}");
+        let (tool, issues) = read(Path::new("broken.sh"), &content);
+        assert!(tool.is_none());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, IssueSeverity::Error);
+        assert_eq!(issues[0].line, Some(6));
+    }
+
+    #[test]
+    fn reports_an_unterminated_block_at_its_marker() {
+        let (tool, issues) = read(Path::new("open.sh"), "# ### NEXUS-TOOL ###\n# {}\n");
+        assert!(tool.is_none());
+        assert_eq!(issues[0].line, Some(1));
+    }
+
+    #[test]
+    fn warnings_keep_the_tool_and_errors_drop_it() {
+        let content = script(r#"{ "name": "Echo", "description": "Echoes", "colour": "red" }"#);
+        let (tool, issues) = read(Path::new("echo.sh"), &content);
+        assert!(tool.is_some());
+        assert_eq!(issues[0].severity, IssueSeverity::Warning);
+
+        let content = script(r#"{ "name": "Pick", "description": "Picks", "parameters": [{ "name": "x", "type": "select" }] }"#);
+        let (tool, issues) = read(Path::new("pick.sh"), &content);
+        assert!(tool.is_none());
+        assert!(issues.iter().any(|i| i.severity == IssueSeverity::Error));
+    }
+
+    #[test]
+    fn version_requirements() {
+        let bounds = parse_requirement(">=3.10, <4").unwrap();
+        assert!(satisfies(&parse_version("Python 3.11.4").unwrap(), &bounds));
+        assert!(!satisfies(&[3, 9, 18], &bounds));
+        assert!(!satisfies(&[4, 0], &bounds));
+        assert!(satisfies(&[3, 11, 4], &parse_requirement("3.11").unwrap()));
+        assert!(parse_requirement("~3.10").is_err());
+    }
+}
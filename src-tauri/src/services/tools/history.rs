@@ -0,0 +1,179 @@
+// src-tauri/src/services/tools/history.rs
+// The execution log: what each tool or code run executed, with which input, what it printed
+// and how it ended. Runs are stored when they end; retention is applied on every insert.
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    state::AppState,
+};
+use serde_json::{json, Value};
+use std::time::Instant;
+use tauri::AppHandle;
+
+const TRUNCATED: &str = "\n[… truncated]";
+
+/// Collects the details of a run while it executes.
+pub struct ExecutionLog {
+    record: models::ExecutionRecord,
+    settings: models::ExecutionHistorySettings,
+    started: Instant,
+}
+
+impl ExecutionLog {
+    pub fn start(settings: &models::Settings, id: &str, source: &str, tool_id: Option<&str>, runtime: &str, params: Value) -> Self {
+        Self {
+            record: models::ExecutionRecord {
+                id: id.to_string(),
+                started_at: chrono::Utc::now().timestamp_millis(),
+                tool_id: tool_id.map(String::from),
+                runtime: runtime.to_string(),
+                command_line: String::new(),
+                params,
+                stdin: None,
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: None,
+                status: models::ExecutionStatus::Success,
+                error: None,
+                duration_ms: 0,
+                source: source.to_string(),
+            },
+            settings: settings.execution.history.clone(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The task or execution ID the run is reported under.
+    pub fn id(&self) -> &str {
+        &self.record.id
+    }
+
+    pub fn set_params(&mut self, params: Value) {
+        self.record.params = params;
+    }
+
+    /// Keeps output that wasn't streamed, such as a webhook's response, as the run's stdout.
+    pub fn set_result_output(&mut self, output: &str) {
+        if self.record.stdout.is_empty() {
+            self.push_stdout(output);
+        }
+    }
+
+    pub fn set_command(&mut self, program: &str, args: &[String]) {
+        self.record.command_line = std::iter::once(program).chain(args.iter().map(String::as_str)).map(quote).collect::<Vec<_>>().join(" ");
+    }
+
+    pub fn set_stdin(&mut self, stdin: Option<&str>) {
+        let cap = self.settings.max_output_bytes;
+        self.record.stdin = stdin.map(|text| {
+            let mut capped = String::new();
+            append_capped(&mut capped, text, cap);
+            capped
+        });
+    }
+
+    pub fn push_stdout(&mut self, text: &str) {
+        append_capped(&mut self.record.stdout, text, self.settings.max_output_bytes);
+    }
+
+    pub fn push_stderr(&mut self, text: &str) {
+        append_capped(&mut self.record.stderr, text, self.settings.max_output_bytes);
+    }
+
+    pub fn set_exit_code(&mut self, code: Option<i32>) {
+        self.record.exit_code = code;
+    }
+
+    /// Stores the run with the outcome of `result`. Output that wasn't streamed, such as a
+    /// webhook's response, is taken from the result unless `set_result_output` already kept it.
+    pub fn finish(mut self, state: &AppState, result: &Result<String>) {
+        let (status, error) = match result {
+            Ok(output) => {
+                self.set_result_output(output);
+                (models::ExecutionStatus::Success, None)
+            }
+            Err(e @ AppError::Cancelled(_)) => (models::ExecutionStatus::Cancelled, Some(e.to_string())),
+            Err(e @ AppError::Timeout(_)) => (models::ExecutionStatus::Timeout, Some(e.to_string())),
+            Err(e) => (models::ExecutionStatus::Error, Some(e.to_string())),
+        };
+        self.finish_with_status(state, status, error);
+    }
+
+    pub fn finish_with_status(mut self, state: &AppState, status: models::ExecutionStatus, error: Option<String>) {
+        self.record.status = status;
+        self.record.error = error;
+        self.record.duration_ms = self.started.elapsed().as_millis() as i64;
+        // Failures are only logged: the log must never break the run it describes.
+        if let Err(e) = self.store(state) {
+            log::warn!("[Tool Service] Failed to record execution {}: {}", self.record.id, e);
+        }
+    }
+
+    fn store(&self, state: &AppState) -> Result<()> {
+        if !self.settings.enabled {
+            return Ok(());
+        }
+        let conn = state.db.lock().unwrap();
+        queries::insert_execution_record(&conn, &self.record)?;
+        let before = (self.settings.retention_days > 0)
+            .then(|| chrono::Utc::now().timestamp_millis() - self.settings.retention_days as i64 * 24 * 60 * 60 * 1000);
+        let keep = Some(self.settings.max_records).filter(|max| *max > 0);
+        queries::prune_execution_records(&conn, before, keep)?;
+        Ok(())
+    }
+}
+
+/// Quotes an argument the way a POSIX shell would need it, so the command line can be copied.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn append_capped(buffer: &mut String, text: &str, cap: usize) {
+    if buffer.len() >= cap {
+        if !text.is_empty() && !buffer.ends_with(TRUNCATED) {
+            buffer.push_str(TRUNCATED);
+        }
+        return;
+    }
+    let room = cap - buffer.len();
+    if text.len() <= room {
+        buffer.push_str(text);
+        return;
+    }
+    let mut end = room;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    buffer.push_str(&text[..end]);
+    buffer.push_str(TRUNCATED);
+}
+
+/// Starts a recorded run again: the same tool with the same params, or the same ad-hoc code.
+/// Returns the new task ID.
+pub async fn rerun(app: AppHandle, state: AppState, id: &str) -> Result<String> {
+    let record = queries::get_execution_record(&state.db.lock().unwrap(), id)?
+        .ok_or_else(|| AppError::Internal(format!("Execution {} not found", id)))?;
+    log::info!("[Tool Service] Re-running execution {}", id);
+
+    match record.tool_id {
+        Some(tool_id) => {
+            if super::params::is_redacted(&record.params) {
+                return Err(AppError::Config("This run used secret parameters, which aren't kept; run the tool again with them".to_string()));
+            }
+            let payload = json!({ "toolName": tool_id, "params": record.params, "source": record.source });
+            super::execute_tool_from_payload(app, &state, payload).await
+        }
+        None => {
+            let code = record.params["code"].as_str()
+                .ok_or_else(|| AppError::Parse(format!("Execution {} has no code to run", id)))?
+                .to_string();
+            let runtime: models::ToolRuntime = serde_json::from_value(json!(record.runtime))
+                .map_err(|_| AppError::Parse(format!("Execution {} has an unknown runtime: {}", id, record.runtime)))?;
+            super::execute_generic_code(app, state, runtime, code, record.source).await
+        }
+    }
+}
@@ -0,0 +1,737 @@
+// src-tauri/src/services/tools/mod.rs
+pub mod bundles;
+pub mod environments;
+pub mod header;
+pub mod history;
+pub mod params;
+pub mod processing;
+pub mod sandbox;
+pub mod webhook;
+
+use self::history::ExecutionLog;
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    knowledge_base,
+    services::{prompts, workflows},
+    state::{AppState, RunningProcess},
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+fn script_files(settings: &models::Settings) -> Vec<PathBuf> {
+    settings.knowledge_base.scripts_directories.iter()
+        .flat_map(|dir| {
+            log::info!("Searching for dynamic tools in directory: {}", dir);
+            WalkDir::new(dir).into_iter().filter_map(|e| e.ok())
+        })
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+pub async fn list_dynamic_tools(state: &AppState) -> Result<Vec<crate::commands::tools::DynamicTool>> {
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let mut tools = Vec::new();
+
+    if settings.knowledge_base.scripts_directories.is_empty() {
+        log::info!("No script directories configured. Skipping dynamic tool discovery.");
+        return Ok(tools);
+    }
+
+    for path in script_files(&settings) {
+        match header::parse_script_for_tool(&path) {
+            Ok(Some(tool)) => {
+                log::info!("Successfully parsed tool: '{}' from {:?}", tool.name, path);
+                tools.push(tool);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Failed to parse tool from {:?}: {}", path, e);
+            }
+        }
+    }
+    Ok(tools)
+}
+
+/// Lints the script at `path`, or every script with a metadata block in the scripts directories.
+pub async fn lint_tool_scripts(state: &AppState, path: Option<&str>) -> Result<Vec<header::HeaderIssue>> {
+    if let Some(path) = path {
+        let path = Path::new(path);
+        return Ok(header::read(path, &fs::read_to_string(path)?).1);
+    }
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let mut issues = Vec::new();
+    for path in script_files(&settings) {
+        // Binary files can't hold a metadata block.
+        if let Ok(content) = fs::read_to_string(&path) {
+            issues.extend(header::read(&path, &content).1);
+        }
+    }
+    Ok(issues)
+}
+
+/// Resolves a `dynamic::<path>` tool. Only scripts inside a scripts directory can be run this way.
+fn load_dynamic_tool(settings: &models::Settings, script_path: &str) -> Result<models::ConfiguredTool> {
+    let path = Path::new(script_path).canonicalize()
+        .map_err(|_| AppError::Internal(format!("Tool script {} not found", script_path)))?;
+    let in_scripts_dir = settings.knowledge_base.scripts_directories.iter()
+        .filter_map(|dir| Path::new(dir).canonicalize().ok())
+        .any(|dir| path.starts_with(dir));
+    if !in_scripts_dir {
+        return Err(AppError::Config(format!("{} is not in a scripts directory", script_path)));
+    }
+    let tool = header::parse_script_for_tool(&path)?
+        .ok_or_else(|| AppError::Config(format!("{} has no NEXUS-TOOL block", script_path)))?;
+    Ok(header::to_configured_tool(&tool))
+}
+
+/// Fails unless `program --version` reports a version within `requirement`.
+async fn check_interpreter_version(program: &str, requirement: &str) -> Result<()> {
+    let bounds = header::parse_requirement(requirement)?;
+    let output = Command::new(program).arg("--version").output().await
+        .map_err(|e| AppError::Config(format!("Could not run {} to check its version: {}", program, e)))?;
+    // Python 2 printed its version to stderr.
+    let reported = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let version = header::parse_version(&reported)
+        .ok_or_else(|| AppError::Config(format!("Could not read the version of {} from '{}'", program, reported.trim())))?;
+    if !header::satisfies(&version, &bounds) {
+        let version = version.iter().map(u64::to_string).collect::<Vec<_>>().join(".");
+        return Err(AppError::Config(format!("{} {} doesn't satisfy the required version {}", program, version, requirement)));
+    }
+    Ok(())
+}
+
+pub async fn execute_tool_from_payload(app: AppHandle, state: &AppState, payload: Value) -> Result<String> {
+    let tool_id = payload["toolName"]
+        .as_str()
+        .ok_or_else(|| AppError::Internal("Missing 'toolName' in payload".to_string()))?;
+    let params = payload["params"].clone();
+    let source = payload["source"].as_str().unwrap_or("copilot").to_string();
+    let task_id = Uuid::new_v4().to_string();
+
+    let state_clone = state.clone();
+    let tool_id_clone = tool_id.to_string();
+    let app_clone = app.clone();
+    let task_id_clone = task_id.clone();
+
+    tokio::spawn(async move {
+        let result = execute(&state_clone, &tool_id_clone, params, &task_id_clone, &app_clone, &source).await;
+        emit_task_result(&app, &task_id_clone, result);
+    });
+
+    Ok(task_id)
+}
+
+pub async fn execute(state: &AppState, tool_id: &str, params: Value, task_id: &str, app: &AppHandle, source: &str) -> Result<String> {
+    log::info!("Executing tool: {} with task ID: {}", tool_id, task_id);
+
+    if let Some(workflow_id) = tool_id.strip_prefix(workflows::TOOL_PREFIX) {
+        // Boxed, since workflow steps run tools through this function again.
+        return Box::pin(workflows::run_as_tool(app, state, workflow_id, params, task_id, source)).await;
+    }
+    if let Some(prompt_id) = tool_id.strip_prefix(prompts::TOOL_PREFIX) {
+        return prompts::run_as_tool(app, state, prompt_id, params).await;
+    }
+
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let tool = if tool_id.starts_with("built_in::") {
+        None
+    } else if let Some(script_path) = tool_id.strip_prefix("dynamic::") {
+        Some(load_dynamic_tool(&settings, script_path)?)
+    } else if let Some(tool) = queries::get_configured_tool_by_id(&state.db.lock().unwrap(), tool_id)? {
+        Some(tool)
+    } else {
+        return Err(AppError::Internal(format!("Tool with ID {} not found", tool_id)));
+    };
+
+    let runtime = tool.as_ref().map_or_else(|| "built_in".to_string(), |tool| runtime_name(&tool.runtime));
+    let logged_params = match &tool {
+        Some(tool) => params::redact(&tool.parameters, &params),
+        None => params.clone(),
+    };
+    // The log covers the whole run, from resolving the input to routing the output, so a
+    // failure in any step is recorded.
+    let mut history = ExecutionLog::start(&settings, task_id, source, Some(tool_id), &runtime, logged_params);
+    let result = match tool {
+        None => match tool_id {
+            "built_in::find_file" => find_file_in_indexed_dirs(state, params).await,
+            "built_in::save_to_kb" => save_to_kb(state, params).await,
+            _ => Err(AppError::Internal(format!("Unknown built-in tool: {}", tool_id))),
+        },
+        Some(tool) => run_configured_tool(state, &settings, tool, params, task_id, app, &mut history).await,
+    };
+    history.finish(state, &result);
+    result
+}
+
+/// Resolves the input, pre-processes it, runs the tool, post-processes and routes the output.
+async fn run_configured_tool(state: &AppState, settings: &models::Settings, tool: models::ConfiguredTool, params: Value, task_id: &str, app: &AppHandle, history: &mut ExecutionLog) -> Result<String> {
+    // The log keeps the params as given, before the model rewrote them, so a re-run repeats
+    // the whole run rather than feeding the rewritten input through pre-processing again.
+    let given = processing::resolve_input(app, &tool, params).await?;
+    history.set_params(params::redact(&tool.parameters, &given));
+    let input = given.get("stdin").and_then(Value::as_str).unwrap_or_default().to_string();
+    let params = params::prepare(&tool, processing::pre_process(state, &tool, given).await?)?;
+
+    let output = execute_configured_tool(state, settings, tool.clone(), params, app, history).await?;
+    history.set_result_output(&output);
+    let output = processing::post_process(state, &tool, &input, output).await?;
+    processing::route_output(app, state, &tool, task_id, &input, &output)?;
+    Ok(output)
+}
+
+async fn execute_configured_tool(state: &AppState, settings: &models::Settings, tool: models::ConfiguredTool, params: Value, app: &AppHandle, history: &mut ExecutionLog) -> Result<String> {
+    let mapped = params::map_params(tool.argument_mapping, &tool.parameters, &params)?;
+    // What the log shows, with secrets masked.
+    let logged = params::map_params(tool.argument_mapping, &tool.parameters, &params::redact(&tool.parameters, &params))?;
+    let timeout = execution_timeout(settings, Some(&tool));
+    history.set_stdin(logged.stdin.as_deref());
+
+    match tool.runtime {
+        models::ToolRuntime::Python | models::ToolRuntime::Node | models::ToolRuntime::Shell => {
+            let script_path = tool.script_path.clone().ok_or_else(|| AppError::Config(format!("Tool is {:?} runtime but has no script_path", tool.runtime)))?;
+            let policy = tool.sandbox.clone().unwrap_or_else(|| settings.execution.sandbox.clone());
+            let working_dir = working_dir(settings);
+
+            let (mut program, mut argv) = interpreter(settings, &tool.runtime);
+            if let Some(requirement) = tool.interpreter_version.as_deref().filter(|_| tool.runtime != models::ToolRuntime::Shell) {
+                check_interpreter_version(&program, requirement).await?;
+            }
+            let mut inputs = vec![PathBuf::from(&script_path)];
+            let mut env = mapped.env;
+            if !tool.dependencies.is_empty() && tool.runtime != models::ToolRuntime::Shell {
+                let environment = environments::prepare(app, state, settings, &tool.runtime, &program, &tool.dependencies, history.id()).await?;
+                program = environment.program.unwrap_or(program);
+                env.extend(environment.env);
+                // The venv's interpreter is a symlink to the base one, so its directory has to be mounted explicitly.
+                inputs.push(environment.dir);
+            }
+            argv.push(script_path.clone());
+            history.set_command(&program, &[argv.clone(), logged.args].concat());
+            argv.extend(mapped.args);
+            let spec = sandbox::SandboxSpec { policy: &policy, working_dir: working_dir.as_deref(), inputs };
+            let mut command = sandbox::command(&program, &argv, &spec).await;
+            command.envs(env);
+            run_command_async(state, command, mapped.stdin, app, &policy, timeout, history).await
+        },
+        models::ToolRuntime::Webhook => {
+            history.set_command(tool.webhook_method.as_deref().unwrap_or("POST"), &[tool.webhook_url.clone().unwrap_or_default()]);
+            webhook::execute(state, &tool, &params, history.id(), app, timeout).await
+        },
+    }
+}
+
+fn runtime_name(runtime: &models::ToolRuntime) -> String {
+    serde_json::to_value(runtime).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default()
+}
+
+/// How long a run may take: the tool's own timeout if it has one, else the global one.
+fn execution_timeout(settings: &models::Settings, tool: Option<&models::ConfiguredTool>) -> Option<Duration> {
+    let secs = tool.and_then(|t| t.timeout_secs).unwrap_or(settings.execution.timeout_secs);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Sends the outcome of a background run as `tool-complete`, `tool-cancelled`, `tool-timeout` or `tool-error`.
+fn emit_task_result(app: &AppHandle, task_id: &str, result: Result<String>) {
+    let (event_name, payload) = match result {
+        Ok(output) => ("tool-complete", output),
+        Err(e @ AppError::Cancelled(_)) => ("tool-cancelled", e.to_string()),
+        Err(e @ AppError::Timeout(_)) => ("tool-timeout", e.to_string()),
+        Err(e) => ("tool-error", e.to_string()),
+    };
+    app.emit_all(event_name, json!({ "taskId": task_id, "payload": payload })).ok();
+}
+
+/// Stops the tool, shell command or code run registered under `id`, along with everything it started.
+/// Returns false when nothing is running under `id`, e.g. because the run already ended.
+pub fn cancel(state: &AppState, id: &str) -> bool {
+    let process = state.running_processes.lock().unwrap().get(id).cloned();
+    match process {
+        Some(process) => {
+            process.cancelled.store(true, Ordering::SeqCst);
+            if let Some(pid) = process.pid {
+                kill_process_group(pid);
+            }
+            log::info!("[Tool Service] Cancelled execution {}", id);
+            true
+        }
+        None => {
+            log::warn!("[Tool Service] Could not find running execution to cancel: {}", id);
+            false
+        }
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(pid: u32) {
+    // Children are spawned with `process_group(0)`, so the PID is also the group ID.
+    // SAFETY: kill() has no memory-safety preconditions.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(pid: u32) {
+    if let Err(e) = std::process::Command::new("taskkill").args(["/T", "/F", "/PID", &pid.to_string()]).output() {
+        log::warn!("[Tool Service] Could not stop process tree {}: {}", pid, e);
+    }
+}
+
+/// Keeps a spawned process, or a run without a process of its own such as a workflow, in
+/// `running_processes` until dropped, so `cancel` can stop it.
+pub(crate) struct ProcessRegistration<'a> {
+    state: &'a AppState,
+    id: String,
+    process: Arc<RunningProcess>,
+}
+
+impl<'a> ProcessRegistration<'a> {
+    pub(crate) fn new(state: &'a AppState, id: &str, pid: Option<u32>) -> Self {
+        let process = Arc::new(RunningProcess { pid, cancelled: AtomicBool::new(false) });
+        state.running_processes.lock().unwrap().insert(id.to_string(), process.clone());
+        Self { state, id: id.to_string(), process }
+    }
+
+    pub(crate) fn cancelled(&self) -> bool {
+        self.process.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` was called for the run.
+    pub(crate) async fn until_cancelled(&self) {
+        while !self.cancelled() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    fn kill(&self) {
+        if let Some(pid) = self.process.pid {
+            kill_process_group(pid);
+        }
+    }
+}
+
+impl Drop for ProcessRegistration<'_> {
+    fn drop(&mut self) {
+        self.state.running_processes.lock().unwrap().remove(&self.id);
+    }
+}
+
+fn timeout_error(limit: Duration) -> AppError {
+    AppError::Timeout(format!("Stopped after the time limit of {}s", limit.as_secs()))
+}
+
+fn cancelled_error() -> AppError {
+    AppError::Cancelled("Stopped by the user".to_string())
+}
+
+/// The program that runs code of `runtime` and the arguments that precede the script or code.
+fn interpreter(settings: &models::Settings, runtime: &models::ToolRuntime) -> (String, Vec<String>) {
+    let configured = |path: &str, default: &str| if path.is_empty() { default.to_string() } else { path.to_string() };
+    match runtime {
+        models::ToolRuntime::Python => (configured(&settings.execution.python_path, "python"), vec![]),
+        models::ToolRuntime::Node => (configured(&settings.execution.node_path, "node"), vec![]),
+        _ if cfg!(target_os = "windows") => ("cmd".to_string(), vec!["/C".to_string()]),
+        _ => ("sh".to_string(), vec![]),
+    }
+}
+
+fn working_dir(settings: &models::Settings) -> Option<PathBuf> {
+    Some(&settings.execution.working_directory).filter(|dir| !dir.is_empty()).map(PathBuf::from)
+}
+
+async fn save_to_kb(state: &AppState, params: Value) -> Result<String> {
+    let content = params["stdin"].as_str().ok_or_else(|| AppError::Internal("Missing content for save_to_kb".to_string()))?;
+
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+
+    let root_dir = settings.knowledge_base.default_save_directory
+        .or_else(|| settings.knowledge_base.indexed_directories.get(0).cloned())
+        .ok_or_else(|| AppError::Config("No default save directory set and no indexed directories available.".to_string()))?;
+
+    let filename = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let new_path = Path::new(&root_dir).join(format!("{}.md", filename));
+
+    fs::write(&new_path, content)?;
+
+    let path_str = new_path.to_string_lossy().to_string();
+    knowledge_base::indexer::reindex_file(state.clone(), path_str.clone()).await?;
+
+    Ok(format!("Note saved to {}", path_str))
+}
+
+async fn find_file_in_indexed_dirs(state: &AppState, params: Value) -> Result<String> {
+    let file_name = params["stdin"].as_str().ok_or_else(|| AppError::Internal("Missing 'stdin' parameter for find_file".to_string()))?;
+    let settings = queries::get_settings(&state.db.lock().unwrap())?;
+    let indexed_dirs = settings.knowledge_base.indexed_directories;
+    let mut found_files = Vec::new();
+    for dir in indexed_dirs {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_name().to_string_lossy().to_lowercase().contains(&file_name.to_lowercase()) {
+                found_files.push(entry.path().to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(if found_files.is_empty() { format!("No files found matching '{}'.", file_name) } else { format!("Found files:\n{}", found_files.join("\n")) })
+}
+
+fn report_limit(app: &AppHandle, task_id: &str, violation: &sandbox::LimitViolation) {
+    log::warn!("[Sandbox] Task {} stopped: {}", task_id, violation.message);
+    app.emit_all("tool-limit-exceeded", json!({ "taskId": task_id, "limit": violation.limit, "message": violation.message })).ok();
+}
+
+async fn run_command_async(
+    state: &AppState,
+    mut command: Command,
+    stdin_data: Option<String>,
+    app: &AppHandle,
+    policy: &models::SandboxPolicy,
+    timeout: Option<Duration>,
+    history: &mut ExecutionLog,
+) -> Result<String> {
+    let task_id = history.id().to_string();
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let registration = ProcessRegistration::new(state, &task_id, child.id());
+
+    let run = collect_output(&mut child, &registration, stdin_data, app, policy, history);
+    let result = match timeout {
+        Some(limit) => match tokio::time::timeout(limit, run).await {
+            Ok(result) => result,
+            Err(_) => {
+                log::warn!("[Tool Service] Task {} timed out after {}s", task_id, limit.as_secs());
+                registration.kill();
+                if let Ok(status) = child.wait().await {
+                    history.set_exit_code(status.code());
+                }
+                Err(timeout_error(limit))
+            }
+        },
+        None => run.await,
+    };
+    if registration.cancelled() {
+        return Err(cancelled_error());
+    }
+    result
+}
+
+async fn collect_output(
+    child: &mut Child,
+    registration: &ProcessRegistration<'_>,
+    stdin_data: Option<String>,
+    app: &AppHandle,
+    policy: &models::SandboxPolicy,
+    history: &mut ExecutionLog,
+) -> Result<String> {
+    let task_id = history.id().to_string();
+    if let Some(data) = stdin_data {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(data.as_bytes()).await?;
+        }
+    }
+
+    // Both streams are read together, so a chatty stderr can't fill its pipe and stall the
+    // process, and both count toward the output limit.
+    let stdout = child.stdout.take().ok_or_else(|| AppError::Internal("Failed to capture stdout".to_string()))?;
+    let stderr = child.stderr.take().ok_or_else(|| AppError::Internal("Failed to capture stderr".to_string()))?;
+    let mut stdout_lines = BufReader::new(stdout).lines();
+    let mut stderr_lines = BufReader::new(stderr).lines();
+    let (mut stdout_open, mut stderr_open) = (true, true);
+    let mut full_output = String::new();
+    let mut err_output = String::new();
+    let over_limit = |bytes: usize| policy.enabled && policy.max_output_bytes > 0 && bytes > policy.max_output_bytes;
+
+    while stdout_open || stderr_open {
+        if over_limit(full_output.len() + err_output.len()) {
+            registration.kill();
+            child.kill().await.ok();
+            let violation = sandbox::output_violation(policy);
+            report_limit(app, &task_id, &violation);
+            return Err(AppError::Internal(format!("Script stopped by the sandbox: {}", violation.message)));
+        }
+        tokio::select! {
+            line = stdout_lines.next_line(), if stdout_open => match line? {
+                Some(line) => {
+                    let line_with_newline = format!("{}\n", line);
+                    app.emit_all("tool-output", json!({ "taskId": &task_id, "chunk": &line_with_newline })).ok();
+                    history.push_stdout(&line_with_newline);
+                    full_output.push_str(&line_with_newline);
+                }
+                None => stdout_open = false,
+            },
+            line = stderr_lines.next_line(), if stderr_open => match line? {
+                Some(line) => {
+                    let line_with_newline = format!("{}\n", line);
+                    history.push_stderr(&line_with_newline);
+                    err_output.push_str(&line_with_newline);
+                }
+                None => stderr_open = false,
+            },
+        }
+    }
+
+    let status = child.wait().await?;
+    history.set_exit_code(status.code());
+    if registration.cancelled() {
+        // Killed by `cancel`, which would otherwise read as a limit or a failure.
+        return Err(cancelled_error());
+    }
+    if status.success() {
+        Ok(full_output)
+    } else if let Some(violation) = sandbox::limit_violation(&status, policy) {
+        report_limit(app, &task_id, &violation);
+        Err(AppError::Internal(format!("Script stopped by the sandbox: {}", violation.message)))
+    } else {
+        Err(AppError::Internal(format!("Script failed: {}", err_output)))
+    }
+}
+
+pub async fn execute_python_code(app: AppHandle, state: AppState, execution_id: String, code: String, source: String) -> Result<()> {
+    tokio::spawn(async move {
+        let settings = match queries::get_settings(&state.db.lock().unwrap()) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = app.emit_all("code-execution-complete", json!({ "executionId": execution_id, "status": "error", "error": e.to_string() }));
+                return;
+            }
+        };
+
+        let (python_path, _) = interpreter(&settings, &models::ToolRuntime::Python);
+        let argv = vec!["-c".to_string(), code.clone()];
+        let mut history = ExecutionLog::start(&settings, &execution_id, &source, None, "python", json!({ "code": code }));
+        history.set_command(&python_path, &argv);
+        let policy = &settings.execution.sandbox;
+        let working_dir = working_dir(&settings);
+        let spec = sandbox::SandboxSpec { policy, working_dir: working_dir.as_deref(), inputs: vec![] };
+        let mut command = sandbox::command(&python_path, &argv, &spec).await;
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let mut child = match command.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true).spawn() {
+            Ok(c) => c,
+            Err(e) => {
+                history.finish_with_status(&state, models::ExecutionStatus::Error, Some(e.to_string()));
+                let _ = app.emit_all("code-execution-complete", json!({ "executionId": execution_id, "status": "error", "error": e.to_string() }));
+                return;
+            }
+        };
+        let registration = ProcessRegistration::new(&state, &execution_id, child.id());
+
+        let run = stream_code_output(&app, &execution_id, &mut child, &registration, policy, &mut history);
+        let completion = match execution_timeout(&settings, None) {
+            Some(limit) => match tokio::time::timeout(limit, run).await {
+                Ok(completion) => completion,
+                Err(_) => {
+                    registration.kill();
+                    if let Ok(status) = child.wait().await {
+                        history.set_exit_code(status.code());
+                    }
+                    json!({ "executionId": execution_id, "status": "timeout", "error": timeout_error(limit).to_string() })
+                }
+            },
+            None => run.await,
+        };
+        let completion = if registration.cancelled() {
+            json!({ "executionId": execution_id, "status": "cancelled", "error": cancelled_error().to_string() })
+        } else {
+            completion
+        };
+        let status = serde_json::from_value(completion["status"].clone()).unwrap_or(models::ExecutionStatus::Error);
+        history.finish_with_status(&state, status, completion["error"].as_str().map(String::from));
+        let _ = app.emit_all("code-execution-complete", completion);
+    });
+    Ok(())
+}
+
+/// Streams a code run's output until it exits and returns the `code-execution-complete` payload.
+async fn stream_code_output(
+    app: &AppHandle,
+    execution_id: &str,
+    child: &mut Child,
+    registration: &ProcessRegistration<'_>,
+    policy: &models::SandboxPolicy,
+    history: &mut ExecutionLog,
+) -> Value {
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+
+    let mut stdout_reader = BufReader::new(stdout).lines();
+    let mut stderr_reader = BufReader::new(stderr).lines();
+    let mut output_bytes = 0usize;
+    let over_limit = |bytes: usize| policy.enabled && policy.max_output_bytes > 0 && bytes > policy.max_output_bytes;
+
+    loop {
+        if over_limit(output_bytes) {
+            registration.kill();
+            let _ = child.kill().await;
+            let violation = sandbox::output_violation(policy);
+            return json!({ "executionId": execution_id, "status": "error", "error": violation.message, "limit": violation.limit });
+        }
+        tokio::select! {
+            line = stdout_reader.next_line() => {
+                match line {
+                    Ok(Some(line)) => {
+                        output_bytes += line.len() + 1;
+                        let chunk = format!("{}\n", line);
+                        history.push_stdout(&chunk);
+                        let _ = app.emit_all("code-execution-output", json!({ "executionId": execution_id, "chunk": chunk }));
+                    },
+                    Ok(None) => break, // stdout closed
+                    Err(e) => {
+                         let _ = app.emit_all("code-execution-output", json!({ "executionId": execution_id, "chunk": format!("[ERROR] Failed to read stdout: {}\n", e) }));
+                         break;
+                    }
+                }
+            },
+            line = stderr_reader.next_line() => {
+                 match line {
+                    Ok(Some(line)) => {
+                        output_bytes += line.len() + 1;
+                        history.push_stderr(&format!("{}\n", line));
+                        let _ = app.emit_all("code-execution-output", json!({ "executionId": execution_id, "chunk": format!("[STDERR] {}\n", line) }));
+                    },
+                    Ok(None) => {}, // stderr might close before stdout
+                    Err(e) => {
+                         let _ = app.emit_all("code-execution-output", json!({ "executionId": execution_id, "chunk": format!("[ERROR] Failed to read stderr: {}\n", e) }));
+                    }
+                }
+            }
+        }
+    }
+
+    let status = child.wait().await;
+    if let Ok(status) = &status {
+        history.set_exit_code(status.code());
+    }
+    match status {
+        Ok(_) if registration.cancelled() => json!({ "executionId": execution_id, "status": "cancelled", "error": cancelled_error().to_string() }),
+        Ok(status) if status.success() => json!({ "executionId": execution_id, "status": "success" }),
+        Ok(status) => match sandbox::limit_violation(&status, policy) {
+            Some(violation) => json!({ "executionId": execution_id, "status": "error", "error": violation.message, "limit": violation.limit }),
+            None => json!({ "executionId": execution_id, "status": "error", "error": format!("Process exited with status: {}", status) }),
+        },
+        Err(e) => json!({ "executionId": execution_id, "status": "error", "error": e.to_string() }),
+    }
+}
+
+pub async fn execute_shell_command_service(app: AppHandle, state: AppState, command: String, source: String) -> Result<String> {
+    let task_id = Uuid::new_v4().to_string();
+    let app_clone = app.clone();
+    let task_id_clone = task_id.clone();
+
+    tokio::spawn(async move {
+        let result = async {
+            let settings = queries::get_settings(&state.db.lock().unwrap())?;
+            let mut history = ExecutionLog::start(&settings, &task_id_clone, &source, None, "shell", json!({ "code": command }));
+            let (shell, mut argv) = interpreter(&settings, &models::ToolRuntime::Shell);
+            if !cfg!(target_os = "windows") {
+                argv.push("-c".to_string());
+            }
+            argv.push(command);
+            history.set_command(&shell, &argv);
+
+            let policy = &settings.execution.sandbox;
+            let working_dir = working_dir(&settings);
+            let spec = sandbox::SandboxSpec { policy, working_dir: working_dir.as_deref(), inputs: vec![] };
+            let timeout = execution_timeout(&settings, None);
+            let result = run_command_async(&state, sandbox::command(&shell, &argv, &spec).await, None, &app_clone, policy, timeout, &mut history).await;
+            history.finish(&state, &result);
+            result
+        }.await;
+        emit_task_result(&app, &task_id_clone, result);
+    });
+
+    Ok(task_id)
+}
+
+pub async fn execute_generic_code(
+    app: AppHandle,
+    state: AppState,
+    runtime: models::ToolRuntime,
+    code: String,
+    source: String,
+) -> Result<String> {
+    let task_id = Uuid::new_v4().to_string();
+    let app_clone = app.clone();
+    let task_id_clone = task_id.clone();
+    let state_clone = state.clone();
+
+    tokio::spawn(async move {
+        let result = async {
+            let settings = queries::get_settings(&state_clone.db.lock().unwrap())?;
+            let mut history = ExecutionLog::start(&settings, &task_id_clone, &source, None, &runtime_name(&runtime), json!({ "code": code }));
+            let working_dir = working_dir(&settings);
+            let (program, mut argv) = interpreter(&settings, &runtime);
+            let mut inputs = Vec::new();
+            let mut _cleaner = None;
+
+            match runtime {
+                models::ToolRuntime::Shell => {
+                    log::info!("[Tool Service] Executing generic shell command directly: {}", code);
+                    if !cfg!(target_os = "windows") {
+                        argv.push("-c".to_string());
+                    }
+                    argv.push(code.clone());
+                }
+                models::ToolRuntime::Python | models::ToolRuntime::Node => {
+                    log::info!("[Tool Service] Executing generic code via temp file for runtime: {:?}", runtime);
+
+                    let script_dir: std::path::PathBuf = working_dir.clone().unwrap_or_else(env::temp_dir);
+                    fs::create_dir_all(&script_dir)?;
+
+                    let extension = if runtime == models::ToolRuntime::Python { "py" } else { "js" };
+                    let script_path = script_dir.join(format!("{}.{}", task_id_clone, extension));
+                    fs::write(&script_path, &code)?;
+
+                    argv.push(script_path.to_string_lossy().to_string());
+                    inputs.push(script_path.clone());
+                    // Removed once the run is over, however it ends.
+                    _cleaner = Some(TempFileCleaner(script_path));
+                }
+                models::ToolRuntime::Webhook => return Err(AppError::Internal("Webhook runtime cannot be executed directly.".to_string())),
+            }
+
+            history.set_command(&program, &argv);
+            let policy = &settings.execution.sandbox;
+            let spec = sandbox::SandboxSpec { policy, working_dir: working_dir.as_deref(), inputs };
+            let timeout = execution_timeout(&settings, None);
+            let result = run_command_async(&state_clone, sandbox::command(&program, &argv, &spec).await, None, &app_clone, policy, timeout, &mut history).await;
+            history.finish(&state_clone, &result);
+            result
+        }.await;
+        emit_task_result(&app, &task_id_clone, result);
+    });
+
+    Ok(task_id)
+}
+
+// A helper struct to ensure temporary files are cleaned up even on panic.
+struct TempFileCleaner(std::path::PathBuf);
+impl Drop for TempFileCleaner {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.0) {
+            log::warn!("Failed to clean up temporary script file {:?}: {}", self.0, e);
+        }
+    }
+}
\ No newline at end of file
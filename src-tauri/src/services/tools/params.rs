@@ -0,0 +1,366 @@
+// src-tauri/src/services/tools/params.rs
+// Checks tool params against the tool's input schema and turns them into what a script
+// receives. A tool without an `input_schema` gets one derived from its declared parameters.
+// Validation covers the parts of JSON Schema tools use: `type`, `enum`, `const`, `required`,
+// `properties`, `additionalProperties`, `items`, the numeric, length and item-count bounds,
+// `pattern` and the `date` and `date-time` formats.
+use crate::{
+    database::models::{ConfiguredTool, ToolArgumentMapping, ToolParameter, ToolParameterType},
+    error::{AppError, FieldError, Result},
+};
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+const ENV_PREFIX: &str = "NEXUS_PARAM_";
+const MASK: &str = "••••••";
+
+/// The schema params are validated against, if the tool has one or declares parameters.
+pub fn input_schema(tool: &ConfiguredTool) -> Result<Option<Value>> {
+    match tool.input_schema.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(schema) => serde_json::from_str(schema)
+            .map(Some)
+            .map_err(|e| AppError::Config(format!("Input schema of tool '{}' is not valid JSON: {}", tool.name, e))),
+        None if tool.parameters.is_empty() => Ok(None),
+        None => Ok(Some(schema_from_parameters(&tool.parameters))),
+    }
+}
+
+fn schema_from_parameters(parameters: &[ToolParameter]) -> Value {
+    let properties: Map<String, Value> = parameters.iter()
+        .map(|p| {
+            let mut property = match p.param_type {
+                ToolParameterType::Number => json!({ "type": "number" }),
+                ToolParameterType::Boolean => json!({ "type": "boolean" }),
+                ToolParameterType::Select => json!({ "type": "string", "enum": p.options }),
+                ToolParameterType::Date => json!({ "type": "string", "format": "date" }),
+                ToolParameterType::Json => json!({}),
+                _ => json!({ "type": "string" }),
+            };
+            if !p.label.is_empty() {
+                property["title"] = json!(p.label);
+            }
+            (p.name.clone(), property)
+        })
+        .collect();
+    let required: Vec<&str> = parameters.iter().filter(|p| p.required).map(|p| p.name.as_str()).collect();
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+/// Fills in defaults for missing declared parameters and converts text from forms ("42",
+/// "true", "{...}") into the declared type, so the result can be validated and mapped.
+pub fn normalize(parameters: &[ToolParameter], params: Value) -> Value {
+    let mut map = match params {
+        Value::Object(map) => map,
+        Value::Null => Map::new(),
+        other => return other,
+    };
+    for p in parameters {
+        let missing = !matches!(map.get(&p.name), Some(v) if !v.is_null() && v.as_str() != Some(""));
+        if missing && !p.default_value.is_empty() {
+            map.insert(p.name.clone(), Value::String(p.default_value.clone()));
+        } else if missing {
+            map.remove(&p.name);
+            continue;
+        }
+        if let Some(Value::String(text)) = map.get(&p.name) {
+            let text = text.trim();
+            let converted = match p.param_type {
+                ToolParameterType::Number => text.parse::<f64>().ok().and_then(|n| {
+                    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 { Some(json!(n as i64)) } else { serde_json::Number::from_f64(n).map(Value::Number) }
+                }),
+                ToolParameterType::Boolean => match text.to_lowercase().as_str() {
+                    "true" | "yes" | "on" | "1" => Some(Value::Bool(true)),
+                    "false" | "no" | "off" | "0" => Some(Value::Bool(false)),
+                    _ => None,
+                },
+                ToolParameterType::Json => serde_json::from_str(text).ok(),
+                _ => None,
+            };
+            // Unconvertible text stays as it is and fails validation with a proper message.
+            if let Some(value) = converted {
+                map.insert(p.name.clone(), value);
+            }
+        }
+    }
+    Value::Object(map)
+}
+
+/// Normalizes `params` and validates them against the tool's schema. Every failing field is
+/// reported, not just the first.
+pub fn prepare(tool: &ConfiguredTool, params: Value) -> Result<Value> {
+    let params = normalize(&tool.parameters, params);
+    if let Some(schema) = input_schema(tool)? {
+        let errors = validate(&schema, &params);
+        if !errors.is_empty() {
+            return Err(AppError::InvalidParams(errors));
+        }
+    }
+    Ok(params)
+}
+
+pub fn validate(schema: &Value, value: &Value) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    check(schema, value, "", &mut errors);
+    errors
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected
+        || (expected == "number" && actual == "integer")
+        || (expected == "integer" && value.as_f64().is_some_and(|n| n.fract() == 0.0))
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let Some(schema) = schema.as_object() else { return };
+    let mut fail = |message: String| errors.push(FieldError { field: path.to_string(), message });
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+        fail(format!("expected {}, got {}", types.join(" or "), type_name(value)));
+        return;
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let options: Vec<String> = allowed.iter().map(|v| v.as_str().map_or_else(|| v.to_string(), String::from)).collect();
+            fail(format!("must be one of: {}", options.join(", ")));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            fail(format!("must be {}", expected));
+        }
+    }
+
+    match value {
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+            if let Some(min) = bound("minimum").filter(|min| n < *min) {
+                fail(format!("must be at least {}", min));
+            }
+            if let Some(max) = bound("maximum").filter(|max| n > *max) {
+                fail(format!("must be at most {}", max));
+            }
+            if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+                fail(format!("must be greater than {}", min));
+            }
+            if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+                fail(format!("must be less than {}", max));
+            }
+        }
+        Value::String(s) => {
+            let length = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64).filter(|min| length < *min) {
+                fail(format!("must be at least {} characters", min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64).filter(|max| length > *max) {
+                fail(format!("must be at most {} characters", max));
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                match Regex::new(pattern) {
+                    Ok(re) if !re.is_match(s) => fail(format!("must match the pattern {}", pattern)),
+                    Ok(_) => {}
+                    Err(e) => fail(format!("the schema's pattern is invalid: {}", e)),
+                }
+            }
+            match schema.get("format").and_then(Value::as_str) {
+                Some("date") if chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_err() => fail("must be a date as YYYY-MM-DD".to_string()),
+                Some("date-time") if chrono::DateTime::parse_from_rfc3339(s).is_err() => fail("must be an RFC 3339 date and time".to_string()),
+                _ => {}
+            }
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64).filter(|min| count < *min) {
+                fail(format!("must have at least {} items", min));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64).filter(|max| count > *max) {
+                fail(format!("must have at most {} items", max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{}/{}", path, i), errors);
+                }
+            }
+        }
+        Value::Object(map) => {
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !map.contains_key(name) {
+                    errors.push(FieldError { field: format!("{}/{}", path, name), message: "is required".to_string() });
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, item) in map {
+                let field = format!("{}/{}", path, name);
+                match (properties.and_then(|p| p.get(name)), schema.get("additionalProperties")) {
+                    (Some(property), _) => check(property, item, &field, errors),
+                    (None, Some(Value::Bool(false))) => errors.push(FieldError { field, message: "is not an accepted parameter".to_string() }),
+                    (None, Some(additional)) => check(additional, item, &field, errors),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Params as a script receives them, according to the tool's `argument_mapping`.
+#[derive(Debug, Default)]
+pub struct MappedParams {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub stdin: Option<String>,
+}
+
+/// Text form of a value for argv and env; `None` leaves the param out.
+fn format_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null | Value::Bool(false) => None,
+        Value::String(s) if s.is_empty() => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(true) => Some("true".to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Declared parameters in their declared order, then the rest by name.
+fn ordered<'a>(parameters: &[ToolParameter], map: &'a Map<String, Value>) -> Vec<(&'a String, &'a Value)> {
+    let position = |name: &str| parameters.iter().position(|p| p.name == name).unwrap_or(usize::MAX);
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(name, _)| position(name));
+    entries
+}
+
+pub fn env_var_name(name: &str) -> String {
+    let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    format!("{}{}", ENV_PREFIX, name)
+}
+
+pub fn map_params(mapping: ToolArgumentMapping, parameters: &[ToolParameter], params: &Value) -> Result<MappedParams> {
+    let empty = Map::new();
+    let map = params.as_object().unwrap_or(&empty);
+    let mut mapped = MappedParams::default();
+
+    if mapping == ToolArgumentMapping::JsonStdin {
+        mapped.stdin = Some(serde_json::to_string(params)?);
+        return Ok(mapped);
+    }
+    for (name, value) in ordered(parameters, map) {
+        if name == "stdin" {
+            mapped.stdin = format_value(value);
+            continue;
+        }
+        let Some(text) = format_value(value) else { continue };
+        let secret = parameters.iter().any(|p| p.name == *name && p.param_type == ToolParameterType::Secret);
+        match mapping {
+            ToolArgumentMapping::Env => mapped.env.push((env_var_name(name), text)),
+            _ if secret => mapped.env.push((env_var_name(name), text)),
+            _ => {
+                mapped.args.push(format!("--{}", name));
+                if *value != Value::Bool(true) {
+                    mapped.args.push(text);
+                }
+            }
+        }
+    }
+    Ok(mapped)
+}
+
+/// Whether `params` came from the execution log with secrets masked, and can't be run as they are.
+pub fn is_redacted(params: &Value) -> bool {
+    params.as_object().is_some_and(|map| map.values().any(|v| v.as_str() == Some(MASK)))
+}
+
+/// A copy of `params` with the values of secret parameters masked, for logs.
+pub fn redact(parameters: &[ToolParameter], params: &Value) -> Value {
+    let mut params = params.clone();
+    if let Some(map) = params.as_object_mut() {
+        for p in parameters.iter().filter(|p| p.param_type == ToolParameterType::Secret) {
+            if let Some(value) = map.get_mut(&p.name) {
+                *value = Value::String(MASK.to_string());
+            }
+        }
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> Vec<ToolParameter> {
+        serde_json::from_value(json!([
+            { "name": "query", "type": "text", "required": true },
+            { "name": "limit", "type": "number", "default": "10" },
+            { "name": "verbose", "type": "boolean" },
+            { "name": "token", "type": "secret" },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn normalize_fills_defaults_and_converts_form_text() {
+        let params = normalize(&parameters(), json!({ "query": "rust", "verbose": "yes", "token": "" }));
+        assert_eq!(params, json!({ "query": "rust", "limit": 10, "verbose": true }));
+    }
+
+    #[test]
+    fn validate_reports_every_failing_field() {
+        let schema = schema_from_parameters(&parameters());
+        let errors = validate(&schema, &json!({ "limit": "many", "verbose": true }));
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["/query", "/limit"]);
+    }
+
+    #[test]
+    fn validate_checks_bounds_patterns_and_formats() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "n": { "type": "integer", "minimum": 1 },
+                "code": { "type": "string", "pattern": "^[A-Z]+$" },
+                "day": { "type": "string", "format": "date" },
+                "tags": { "type": "array", "maxItems": 1, "items": { "type": "string" } },
+            },
+            "additionalProperties": false,
+        });
+        assert!(validate(&schema, &json!({ "n": 2, "code": "AB", "day": "2024-02-29", "tags": ["a"] })).is_empty());
+        let errors = validate(&schema, &json!({ "n": 0, "code": "ab", "day": "yesterday", "tags": ["a", 1], "extra": 1 }));
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["/code", "/day", "/extra", "/n", "/tags", "/tags/1"]);
+    }
+
+    #[test]
+    fn argv_mapping_keeps_secrets_out_of_args() {
+        let params = json!({ "verbose": true, "query": "rust", "token": "hunter2", "stdin": "input" });
+        let mapped = map_params(ToolArgumentMapping::Argv, &parameters(), &params).unwrap();
+        assert_eq!(mapped.args, ["--query", "rust", "--verbose"]);
+        assert_eq!(mapped.env, [("NEXUS_PARAM_TOKEN".to_string(), "hunter2".to_string())]);
+        assert_eq!(mapped.stdin.as_deref(), Some("input"));
+    }
+
+    #[test]
+    fn redact_masks_secrets_only() {
+        let redacted = redact(&parameters(), &json!({ "query": "rust", "token": "hunter2" }));
+        assert_eq!(redacted, json!({ "query": "rust", "token": MASK }));
+        assert!(is_redacted(&redacted));
+        assert!(!is_redacted(&json!({ "query": "rust" })));
+    }
+}
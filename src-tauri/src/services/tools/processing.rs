@@ -0,0 +1,181 @@
+// src-tauri/src/services/tools/processing.rs
+// What happens around a configured tool's run: where its input comes from (`input_source`),
+// the optional model passes before and after it, and where its output goes (`output_handling`).
+// The input travels as the `stdin` param; both model passes use the suggestion model.
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    services::chat::{llm_utils, templates},
+    state::AppState,
+    system::clipboard,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Params key the frontend puts the selected chat text under.
+const SELECTION_PARAM: &str = "selection";
+/// Pre-processing prompts that mention this variable extract params rather than rewrite the input.
+const PARAMS_VARIABLE: &str = "params";
+
+fn input_text(params: &Value) -> Option<&str> {
+    params.get("stdin").and_then(Value::as_str).filter(|s| !s.trim().is_empty())
+}
+
+/// `params` with `stdin` taken from the tool's input source. Input that was passed explicitly,
+/// as on a re-run, is kept.
+pub async fn resolve_input(app: &AppHandle, tool: &models::ConfiguredTool, params: Value) -> Result<Value> {
+    let mut map = match params {
+        Value::Object(map) => map,
+        Value::Null => Map::new(),
+        other => return Ok(other),
+    };
+    let selection = map.remove(SELECTION_PARAM);
+    if input_text(&Value::Object(map.clone())).is_some() {
+        return Ok(Value::Object(map));
+    }
+    let input = match tool.input_source {
+        models::ToolInputSource::UserInput => None,
+        models::ToolInputSource::Clipboard => match clipboard::read_clipboard(app).await? {
+            Some(payload) if payload.content_type == "text" => payload.content.as_str().map(String::from),
+            _ => return Err(AppError::Config(format!("'{}' takes its input from the clipboard, but there is no text on it", tool.name))),
+        },
+        models::ToolInputSource::ChatSelection => match selection.as_ref().and_then(Value::as_str).filter(|s| !s.trim().is_empty()) {
+            Some(selection) => Some(selection.to_string()),
+            None => return Err(AppError::Config(format!("'{}' takes the selected chat text, but nothing is selected", tool.name))),
+        },
+    };
+    if let Some(input) = input {
+        map.insert("stdin".to_string(), Value::String(input));
+    }
+    Ok(Value::Object(map))
+}
+
+/// Fills `{{name}}` from `values`; when the prompt doesn't mention `main`, its value is appended.
+fn build_prompt(prompt: &str, main: &str, values: &HashMap<String, String>) -> String {
+    let filled = templates::fill(prompt, values);
+    if templates::find_variables(prompt).iter().any(|name| name == main) {
+        filled
+    } else {
+        format!("{}\n\n{}", filled.trim_end(), values.get(main).map(String::as_str).unwrap_or_default())
+    }
+}
+
+/// Runs the pre-processing prompt over the input. The answer replaces the input, unless the
+/// prompt mentions `{{params}}`: it is then given the tool's input schema, and a JSON object in
+/// the answer is merged into the params instead.
+pub async fn pre_process(state: &AppState, tool: &models::ConfiguredTool, params: Value) -> Result<Value> {
+    if !tool.requires_ai_pre_processing || tool.pre_processing_prompt.trim().is_empty() {
+        return Ok(params);
+    }
+    let extracts_params = templates::find_variables(&tool.pre_processing_prompt).iter().any(|name| name == PARAMS_VARIABLE);
+    let input = input_text(&params).unwrap_or_default().to_string();
+    let mut values = HashMap::from([("input".to_string(), input)]);
+    if extracts_params {
+        let schema = super::params::input_schema(tool)?.unwrap_or_else(|| json!({ "type": "object" }));
+        values.insert(PARAMS_VARIABLE.to_string(), schema.to_string());
+    }
+    let prompt = build_prompt(&tool.pre_processing_prompt, "input", &values);
+    log::info!("[Tool Service] Pre-processing input of '{}'", tool.name);
+    let answer = llm_utils::complete_prompt(state, "tool_pre_processing", prompt).await?;
+    Ok(apply_pre_processing(params, &answer, extracts_params))
+}
+
+fn apply_pre_processing(mut params: Value, answer: &str, extracts_params: bool) -> Value {
+    let Some(map) = params.as_object_mut() else { return params };
+    match serde_json::from_str::<Value>(strip_code_fence(answer)) {
+        Ok(Value::Object(extracted)) if extracts_params => map.extend(extracted),
+        _ => {
+            map.insert("stdin".to_string(), Value::String(answer.trim().to_string()));
+        }
+    }
+    params
+}
+
+/// Models often wrap JSON in a ```json block.
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    text.strip_prefix("```json").or_else(|| text.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map_or(text, str::trim)
+}
+
+/// Runs the post-processing prompt over the output, with `{{output}}` and `{{input}}`.
+pub async fn post_process(state: &AppState, tool: &models::ConfiguredTool, input: &str, output: String) -> Result<String> {
+    if !tool.requires_ai_post_processing || tool.post_processing_prompt.trim().is_empty() {
+        return Ok(output);
+    }
+    let values = HashMap::from([("output".to_string(), output), ("input".to_string(), input.to_string())]);
+    let prompt = build_prompt(&tool.post_processing_prompt, "output", &values);
+    log::info!("[Tool Service] Post-processing output of '{}'", tool.name);
+    Ok(llm_utils::complete_prompt(state, "tool_post_processing", prompt).await?.trim().to_string())
+}
+
+/// Opens a conversation holding the input and the tool's output. Returns its ID.
+fn save_as_conversation(state: &AppState, tool: &models::ConfiguredTool, input: &str, output: &str) -> Result<String> {
+    let conversation_id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = state.db.lock().unwrap();
+    queries::ensure_conversation_exists(&conn, &conversation_id, &tool.name, "chat")?;
+    if !input.trim().is_empty() {
+        queries::save_message(&conn, &models::ChatMessage {
+            id: Uuid::new_v4().to_string(),
+            conversation_id: conversation_id.clone(),
+            role: "user".to_string(),
+            content: vec![models::ChatMessageContentPart::Text { text: input.to_string() }],
+            timestamp: now,
+            ..Default::default()
+        })?;
+    }
+    queries::save_message(&conn, &models::ChatMessage {
+        id: Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.clone(),
+        role: "ai".to_string(),
+        content: vec![models::ChatMessageContentPart::Text { text: output.to_string() }],
+        timestamp: now + 1,
+        ..Default::default()
+    })?;
+    Ok(conversation_id)
+}
+
+/// Sends the output where the tool's `output_handling` says and announces it as
+/// `tool-output-handled`, so the frontend can render Markdown or open the new chat.
+pub fn route_output(app: &AppHandle, state: &AppState, tool: &models::ConfiguredTool, task_id: &str, input: &str, output: &str) -> Result<()> {
+    let conversation_id = match tool.output_handling {
+        models::ToolOutputHandling::RawText | models::ToolOutputHandling::Markdown => None,
+        models::ToolOutputHandling::ToClipboard => {
+            clipboard::write_text(output)?;
+            None
+        }
+        models::ToolOutputHandling::NewChat => Some(save_as_conversation(state, tool, input, output)?),
+    };
+    app.emit_all("tool-output-handled", json!({
+        "taskId": task_id,
+        "toolId": tool.id,
+        "handling": tool.output_handling,
+        "conversationId": conversation_id,
+    })).ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pre_processing_answers_replace_the_input() {
+        let params = json!({ "stdin": "raw text", "lang": "en" });
+        let answer = r#"{ "summary": "short" }"#;
+        assert_eq!(apply_pre_processing(params.clone(), answer, false), json!({ "stdin": answer, "lang": "en" }));
+        assert_eq!(apply_pre_processing(params.clone(), " cleaned text\n", false), json!({ "stdin": "cleaned text", "lang": "en" }));
+    }
+
+    #[test]
+    fn extracted_params_are_merged_only_when_asked_for() {
+        let params = json!({ "stdin": "resize to 800", "width": 100 });
+        let answer = "```json\n{ \"width\": 800 }\n```";
+        assert_eq!(apply_pre_processing(params.clone(), answer, true), json!({ "stdin": "resize to 800", "width": 800 }));
+        assert_eq!(apply_pre_processing(params, "not json", true), json!({ "stdin": "not json", "width": 100 }));
+    }
+}
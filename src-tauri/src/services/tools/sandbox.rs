@@ -0,0 +1,244 @@
+// src-tauri/src/services/tools/sandbox.rs
+// Builds the processes for tool scripts and ad-hoc code according to a `SandboxPolicy`.
+// On Linux the program runs under bubblewrap in fresh namespaces: system directories are
+// mounted read-only, only the working directory and the policy's paths are visible, and the
+// network is cut unless allowed. Where bubblewrap is missing or can't create namespaces, the
+// program runs directly and only the environment filter and resource limits apply.
+use crate::database::models;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use tokio::process::Command;
+
+/// Mounted read-only so interpreters, shared libraries and certificates are available.
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc", "/opt", "/nix/store"];
+
+static BUBBLEWRAP: tokio::sync::OnceCell<std::result::Result<PathBuf, String>> = tokio::sync::OnceCell::const_new();
+
+/// bubblewrap, if it can sandbox processes here. Probed once, on first use.
+async fn bubblewrap() -> &'static std::result::Result<PathBuf, String> {
+    BUBBLEWRAP.get_or_init(probe_bubblewrap).await
+}
+
+async fn probe_bubblewrap() -> std::result::Result<PathBuf, String> {
+    if !cfg!(target_os = "linux") {
+        return Err("Process isolation is only available on Linux".to_string());
+    }
+    let path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).map(|dir| dir.join("bwrap")).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| "bubblewrap (bwrap) is not installed".to_string())?;
+
+    // Unprivileged user namespaces can be disabled by the distribution or a container runtime.
+    let output = Command::new(&path)
+        .args(["--unshare-all", "--ro-bind", "/", "/", "true"])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Could not run bubblewrap: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("bubblewrap can't create a sandbox here: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    log::info!("[Sandbox] Using bubblewrap at {}", path.display());
+    Ok(path)
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxStatus {
+    /// Whether processes are isolated, or only filtered and limited.
+    pub isolated: bool,
+    pub backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+pub async fn status() -> SandboxStatus {
+    match bubblewrap().await {
+        Ok(_) => SandboxStatus { isolated: true, backend: "bubblewrap".to_string(), reason: None },
+        Err(reason) => SandboxStatus { isolated: false, backend: "none".to_string(), reason: Some(reason.clone()) },
+    }
+}
+
+/// Where a sandboxed process runs and which files it needs besides the policy's paths.
+pub struct SandboxSpec<'a> {
+    pub policy: &'a models::SandboxPolicy,
+    pub working_dir: Option<&'a Path>,
+    /// Files the process must be able to read, e.g. the script itself.
+    pub inputs: Vec<PathBuf>,
+}
+
+fn env_allowed(policy: &models::SandboxPolicy, key: &str) -> bool {
+    policy.env_allowlist.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => pattern.eq_ignore_ascii_case(key),
+    })
+}
+
+/// A venv or toolchain interpreter given by absolute path needs its installation visible:
+/// `/home/me/.venv/bin/python` mounts `/home/me/.venv`.
+fn interpreter_root(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if !path.is_absolute() {
+        return None;
+    }
+    let resolved = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let dir = resolved.parent()?;
+    Some(if dir.file_name().is_some_and(|name| name == "bin") { dir.parent()?.to_path_buf() } else { dir.to_path_buf() })
+}
+
+fn bubblewrap_args(spec: &SandboxSpec, program: &str) -> Vec<String> {
+    let mut args: Vec<String> = ["--die-with-parent", "--new-session", "--unshare-all"].iter().map(|s| s.to_string()).collect();
+    if spec.policy.allow_network {
+        args.push("--share-net".to_string());
+    }
+    let mut bind = |flag: &str, path: &Path| {
+        let path = path.to_string_lossy().to_string();
+        args.extend([flag.to_string(), path.clone(), path]);
+    };
+    for dir in SYSTEM_DIRS {
+        bind("--ro-bind-try", Path::new(dir));
+    }
+    if let Some(root) = interpreter_root(program) {
+        bind("--ro-bind-try", &root);
+    }
+    for path in spec.policy.read_paths.iter().map(PathBuf::from).chain(spec.inputs.iter().cloned()) {
+        bind("--ro-bind-try", &path);
+    }
+    for path in &spec.policy.write_paths {
+        bind("--bind-try", Path::new(path));
+    }
+    args.extend(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"].iter().map(|s| s.to_string()));
+    // The working directory is bound last so it stays writable even inside a read-only path.
+    match spec.working_dir {
+        Some(dir) => {
+            let dir = dir.to_string_lossy().to_string();
+            args.extend(["--bind".to_string(), dir.clone(), dir.clone(), "--chdir".to_string(), dir]);
+        }
+        None => args.extend(["--chdir".to_string(), "/tmp".to_string()]),
+    }
+    args.push("--".to_string());
+    args.push(program.to_string());
+    args
+}
+
+/// The memory limit is on the data segment (RLIMIT_DATA), which counts what the process
+/// allocates but not address space it only reserves, as Node, the JVM and Go do at startup.
+#[cfg(unix)]
+fn apply_limits(command: &mut Command, policy: &models::SandboxPolicy) {
+    let (cpu_secs, memory_bytes) = (policy.cpu_time_secs, policy.memory_mb.saturating_mul(1024 * 1024));
+    // SAFETY: only async-signal-safe calls (setrlimit) run between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            if cpu_secs > 0 {
+                // The soft limit sends SIGXCPU, which `limit_violation` recognises; the hard one is a backstop.
+                let limit = libc::rlimit { rlim_cur: cpu_secs as libc::rlim_t, rlim_max: (cpu_secs + 5) as libc::rlim_t };
+                if libc::setrlimit(libc::RLIMIT_CPU, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if memory_bytes > 0 {
+                let limit = libc::rlimit { rlim_cur: memory_bytes as libc::rlim_t, rlim_max: memory_bytes as libc::rlim_t };
+                if libc::setrlimit(libc::RLIMIT_DATA, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_limits(_command: &mut Command, _policy: &models::SandboxPolicy) {}
+
+/// A command that runs `program` with `args` under `spec`. With the policy disabled this is a
+/// plain command in the working directory, as before sandboxing existed.
+pub async fn command(program: &str, args: &[String], spec: &SandboxSpec<'_>) -> Command {
+    let policy = spec.policy;
+    if !policy.enabled {
+        let mut command = Command::new(program);
+        command.args(args);
+        if let Some(dir) = spec.working_dir {
+            command.current_dir(dir);
+        }
+        return command;
+    }
+
+    let mut command = match bubblewrap().await {
+        Ok(bwrap) => {
+            let mut command = Command::new(bwrap);
+            command.args(bubblewrap_args(spec, program));
+            command
+        }
+        Err(reason) => {
+            log::warn!("[Sandbox] Running {} without isolation: {}", program, reason);
+            let mut command = Command::new(program);
+            if let Some(dir) = spec.working_dir {
+                command.current_dir(dir);
+            }
+            command
+        }
+    };
+    command.args(args);
+    command.env_clear();
+    command.envs(std::env::vars().filter(|(key, _)| env_allowed(policy, key)));
+    apply_limits(&mut command, policy);
+    command
+}
+
+/// Which policy limit stopped a process.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    CpuTime,
+    Memory,
+    Output,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitViolation {
+    pub limit: LimitKind,
+    pub message: String,
+}
+
+pub fn output_violation(policy: &models::SandboxPolicy) -> LimitViolation {
+    LimitViolation {
+        limit: LimitKind::Output,
+        message: format!("Output exceeded the limit of {} bytes; the process was stopped", policy.max_output_bytes),
+    }
+}
+
+/// Explains an abnormal exit in terms of the policy, if a limit is the likely cause.
+/// Shells and bubblewrap report a child killed by signal N as exit code 128 + N. SIGKILL isn't
+/// attributed to the policy: it comes from cancelling, the timeout or the OOM killer, and
+/// none of the limits send it by themselves.
+#[cfg(unix)]
+pub fn limit_violation(status: &ExitStatus, policy: &models::SandboxPolicy) -> Option<LimitViolation> {
+    use std::os::unix::process::ExitStatusExt;
+    if !policy.enabled {
+        return None;
+    }
+    let signal = status.signal().or_else(|| status.code().filter(|code| *code > 128).map(|code| code - 128))?;
+    if signal == libc::SIGXCPU && policy.cpu_time_secs > 0 {
+        return Some(LimitViolation {
+            limit: LimitKind::CpuTime,
+            message: format!("CPU time limit of {}s exceeded", policy.cpu_time_secs),
+        });
+    }
+    // A failed allocation usually ends in an abort or a segfault, but so does any other crash.
+    if matches!(signal, libc::SIGSEGV | libc::SIGABRT) && policy.memory_mb > 0 {
+        return Some(LimitViolation {
+            limit: LimitKind::Memory,
+            message: format!("Crashed with signal {}, possibly because it reached the memory limit of {} MB", signal, policy.memory_mb),
+        });
+    }
+    None
+}
+
+#[cfg(not(unix))]
+pub fn limit_violation(_status: &ExitStatus, _policy: &models::SandboxPolicy) -> Option<LimitViolation> {
+    None
+}
@@ -0,0 +1,373 @@
+// src-tauri/src/services/tools/webhook.rs
+// Runs `Webhook` tools. The URL, header values and body template are rendered from the params:
+// `{{name}}` (or `{{a.b}}` for a nested value) is replaced by the param, escaped for where it
+// appears — percent-encoded in the URL, as JSON in a JSON body — and `{{secret:NAME}}` by a
+// value from the secret store, for the secrets the tool is allowed and never in the URL's host.
+// Without a body template, params go in the query string for GET,
+// HEAD and DELETE and as a JSON body otherwise.
+use super::{cancelled_error, timeout_error, ProcessRegistration};
+use crate::{
+    database::models::{ApiKeyLocation, ConfiguredTool, SignatureEncoding, WebhookAuth},
+    error::{AppError, Result},
+    services::{chat::templates::{lookup, text_of, PATH_PLACEHOLDER}, secrets},
+    state::AppState,
+};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use serde_json_path::JsonPath;
+use sha2::Sha256;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Everything but RFC 3986 unreserved characters.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Response text kept in the error for a failed request.
+const ERROR_BODY_CHARS: usize = 500;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Url,
+    Header,
+    Json,
+    Form,
+    Text,
+}
+
+/// Whether the template position `at` lies inside a JSON string literal.
+fn in_json_string(template: &str, at: usize) -> bool {
+    let (mut inside, mut escaped) = (false, false);
+    for c in template[..at].chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if inside => escaped = true,
+            '"' => inside = !inside,
+            _ => {}
+        }
+    }
+    inside
+}
+
+fn render(template: &str, params: &Value, context: Context, allowed_secrets: &[String]) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut last = 0;
+    for captures in PATH_PLACEHOLDER.captures_iter(template) {
+        let whole = captures.get(0).unwrap();
+        rendered.push_str(&template[last..whole.start()]);
+        last = whole.end();
+
+        let value = match captures[1].strip_prefix("secret:") {
+            Some(name) => Value::String(secrets::get_allowed(name, allowed_secrets)?),
+            None => lookup(params, &captures[1]).cloned().unwrap_or(Value::Null),
+        };
+        let text = text_of(&value);
+        match context {
+            Context::Url => rendered.extend(utf8_percent_encode(&text, COMPONENT)),
+            Context::Form => rendered.extend(url::form_urlencoded::byte_serialize(text.as_bytes())),
+            Context::Header if text.contains(['\r', '\n']) => {
+                return Err(AppError::Config(format!("Value of '{}' can't be used in a header: it contains a line break", &captures[1])));
+            }
+            Context::Json if in_json_string(template, whole.start()) => {
+                let quoted = serde_json::to_string(&text)?;
+                rendered.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            Context::Json => rendered.push_str(&serde_json::to_string(&value)?),
+            Context::Header | Context::Text => rendered.push_str(&text),
+        }
+    }
+    rendered.push_str(&template[last..]);
+    Ok(rendered)
+}
+
+fn parse_headers(tool: &ConfiguredTool, params: &Value) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    let Some(raw) = tool.webhook_headers.as_deref().map(str::trim).filter(|s| !s.is_empty()) else { return Ok(headers) };
+    let declared: serde_json::Map<String, Value> = serde_json::from_str(raw)
+        .map_err(|e| AppError::Config(format!("Headers of tool '{}' must be a JSON object: {}", tool.name, e)))?;
+    for (name, value) in declared {
+        let value = render(&text_of(&value), params, Context::Header, &tool.allowed_secrets)?;
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| AppError::Config(format!("Invalid header name '{}': {}", name, e)))?;
+        let value = HeaderValue::from_str(&value).map_err(|e| AppError::Config(format!("Invalid value for header '{}': {}", name, e)))?;
+        headers.insert(name, value);
+    }
+    Ok(headers)
+}
+
+fn body_template(tool: &ConfiguredTool) -> Option<&str> {
+    tool.webhook_body_template.as_deref().filter(|t| !t.trim().is_empty())
+}
+
+fn body_context(headers: &HeaderMap, template: &str) -> Context {
+    match headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_lowercase) {
+        Some(content_type) if content_type.contains("json") => Context::Json,
+        Some(content_type) if content_type.contains("x-www-form-urlencoded") => Context::Form,
+        Some(_) => Context::Text,
+        None if template.trim_start().starts_with(['{', '[']) => Context::Json,
+        None => Context::Text,
+    }
+}
+
+/// The body to send, setting a content type if the headers don't have one.
+fn build_body(tool: &ConfiguredTool, method: &Method, params: &Value, headers: &mut HeaderMap) -> Result<Option<Vec<u8>>> {
+    match body_template(tool) {
+        Some(template) => {
+            let context = body_context(headers, template);
+            let body = render(template, params, context, &tool.allowed_secrets)?;
+            if context == Context::Json {
+                serde_json::from_str::<Value>(&body)
+                    .map_err(|e| AppError::Config(format!("Body template of tool '{}' doesn't render to valid JSON: {}", tool.name, e)))?;
+            }
+            if !headers.contains_key(CONTENT_TYPE) {
+                let content_type = if context == Context::Json { "application/json" } else { "text/plain; charset=utf-8" };
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            }
+            Ok(Some(body.into_bytes()))
+        }
+        None if matches!(*method, Method::GET | Method::HEAD | Method::DELETE) => Ok(None),
+        None => {
+            headers.entry(CONTENT_TYPE).or_insert(HeaderValue::from_static("application/json"));
+            Ok(Some(serde_json::to_vec(params)?))
+        }
+    }
+}
+
+/// The user info and host part of a URL template, where a placeholder would decide, or
+/// reach, the server the request goes to.
+pub(super) fn url_authority(template: &str) -> &str {
+    let start = template.find("://").map_or(0, |i| i + 3);
+    let end = template[start..].find(['/', '?', '#']).map_or(template.len(), |i| start + i);
+    &template[start..end]
+}
+
+fn build_url(tool: &ConfiguredTool, template: &str, method: &Method, params: &Value) -> Result<url::Url> {
+    let placeholders: Vec<String> = PATH_PLACEHOLDER.captures_iter(url_authority(template)).map(|c| c[1].to_string()).collect();
+    if placeholders.iter().any(|p| p.starts_with("secret:")) {
+        return Err(AppError::Config(format!("Webhook URL of tool '{}' can't use a secret in its host; put it in the path, query or a header", tool.name)));
+    }
+    if !placeholders.is_empty() && !tool.allowed_secrets.is_empty() {
+        return Err(AppError::Config(format!("Tool '{}' uses secrets, so its webhook host can't come from the params", tool.name)));
+    }
+    let rendered = render(template, params, Context::Url, &tool.allowed_secrets)?;
+    let mut url = url::Url::parse(&rendered).map_err(|e| AppError::Config(format!("Webhook URL of tool '{}' is invalid: {}", tool.name, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::Config(format!("Webhook URL of tool '{}' must use http or https", tool.name)));
+    }
+    if body_template(tool).is_none() && matches!(*method, Method::GET | Method::HEAD | Method::DELETE) {
+        // Params already placed in the URL aren't repeated in the query string.
+        let in_template: Vec<&str> = PATH_PLACEHOLDER.captures_iter(template).filter_map(|c| c.get(1)).map(|m| m.as_str().split('.').next().unwrap_or_default()).collect();
+        if let Some(map) = params.as_object() {
+            let mut query = url.query_pairs_mut();
+            for (name, value) in map.iter().filter(|(name, v)| !v.is_null() && !in_template.contains(&name.as_str())) {
+                query.append_pair(name, &text_of(value));
+            }
+        }
+        // An empty `?` is left behind when there were no params.
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+    }
+    Ok(url)
+}
+
+fn sign(secret: &str, message: &[u8], encoding: SignatureEncoding) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| AppError::Internal(e.to_string()))?;
+    mac.update(message);
+    let signature = mac.finalize().into_bytes();
+    Ok(match encoding {
+        SignatureEncoding::Hex => signature.iter().map(|b| format!("{:02x}", b)).collect(),
+        SignatureEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(signature),
+    })
+}
+
+fn header_value(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| AppError::Config(format!("Invalid header name '{}': {}", name, e)))?;
+    let value = HeaderValue::from_str(value).map_err(|e| AppError::Config(format!("Invalid value for header '{}': {}", name, e)))?;
+    Ok((name, value))
+}
+
+/// Adds the credentials of `auth` to the request. HMAC signs the final body, so it comes last.
+fn apply_auth(auth: &WebhookAuth, allowed_secrets: &[String], url: &mut url::Url, headers: &mut HeaderMap, body: &[u8]) -> Result<()> {
+    let resolve = |text: &str| secrets::resolve(text, allowed_secrets);
+    match auth {
+        WebhookAuth::Bearer { token } => {
+            let (name, value) = header_value("Authorization", &format!("Bearer {}", resolve(token)?))?;
+            headers.insert(name, value);
+        }
+        WebhookAuth::Basic { username, password } => {
+            let credentials = format!("{}:{}", resolve(username)?, resolve(password)?);
+            let (name, value) = header_value("Authorization", &format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials)))?;
+            headers.insert(name, value);
+        }
+        WebhookAuth::ApiKey { name, value, location: ApiKeyLocation::Header } => {
+            let (name, value) = header_value(name, &resolve(value)?)?;
+            headers.insert(name, value);
+        }
+        WebhookAuth::ApiKey { name, value, location: ApiKeyLocation::Query } => {
+            url.query_pairs_mut().append_pair(name, &resolve(value)?);
+        }
+        WebhookAuth::Hmac { secret, header, prefix, encoding, timestamp_header } => {
+            let secret = resolve(secret)?;
+            let signature = match timestamp_header {
+                Some(timestamp_header) => {
+                    let timestamp = chrono::Utc::now().timestamp().to_string();
+                    let (name, value) = header_value(timestamp_header, &timestamp)?;
+                    headers.insert(name, value);
+                    sign(&secret, &[timestamp.as_bytes(), b".", body].concat(), *encoding)?
+                }
+                None => sign(&secret, body, *encoding)?,
+            };
+            let (name, value) = header_value(header, &format!("{}{}", prefix, signature))?;
+            headers.insert(name, value);
+        }
+    }
+    Ok(())
+}
+
+/// The tool's output: what `path` selects from a JSON response, or the response as text.
+fn extract_output(tool: &ConfiguredTool, body: &str) -> Result<String> {
+    let parsed = serde_json::from_str::<Value>(body).ok();
+    let Some(path) = tool.webhook_response_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) else {
+        return Ok(match parsed {
+            Some(value) => serde_json::to_string_pretty(&value)?,
+            None => body.to_string(),
+        });
+    };
+    let value = parsed.ok_or_else(|| AppError::Parse(format!("Response of tool '{}' is not JSON, so '{}' can't be applied", tool.name, path)))?;
+    let path = JsonPath::parse(path).map_err(|e| AppError::Config(format!("Invalid response path of tool '{}': {}", tool.name, e)))?;
+    match path.query(&value).all().as_slice() {
+        [] => Err(AppError::Parse(format!("The response has nothing at {}", path))),
+        [Value::String(text)] => Ok(text.clone()),
+        [single] => Ok(serde_json::to_string_pretty(single)?),
+        many => Ok(serde_json::to_string_pretty(many)?),
+    }
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || matches!(status.as_u16(), 500 | 502 | 503 | 504)
+}
+
+/// Exponential backoff from 500ms, or the server's `Retry-After` in seconds.
+fn retry_delay(attempt: u32, retry_after: Option<&HeaderValue>) -> Duration {
+    retry_after
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| Duration::from_millis(500 * 2u64.saturating_pow(attempt)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// The error without the request URL, which can hold secrets from the auth or the templates.
+fn request_error(e: reqwest::Error) -> AppError {
+    e.without_url().into()
+}
+
+async fn send_with_retries(state: &AppState, tool: &ConfiguredTool, request: reqwest::Request, registration: &ProcessRegistration<'_>) -> Result<String> {
+    let mut attempt = 0;
+    loop {
+        let request = request.try_clone().ok_or_else(|| AppError::Internal("Webhook request can't be repeated".to_string()))?;
+        let retries_left = attempt < tool.webhook_retries;
+        let delay = match state.http_client.execute(request).await {
+            Ok(response) if response.status().is_success() => return response.text().await.map_err(request_error),
+            Ok(response) if retryable(response.status()) && retries_left => {
+                log::warn!("[Tool Service] Webhook of '{}' answered {}, retrying", tool.name, response.status());
+                retry_delay(attempt, response.headers().get(RETRY_AFTER))
+            }
+            Ok(response) => {
+                let status = response.status();
+                let text: String = response.text().await.unwrap_or_default().chars().take(ERROR_BODY_CHARS).collect();
+                return Err(AppError::ApiClient(format!("Webhook answered {}: {}", status, text)));
+            }
+            Err(e) if (e.is_connect() || e.is_timeout() || e.is_request()) && retries_left => {
+                log::warn!("[Tool Service] Webhook of '{}' failed ({}), retrying", tool.name, e.without_url());
+                retry_delay(attempt, None)
+            }
+            Err(e) => return Err(request_error(e)),
+        };
+        attempt += 1;
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = registration.until_cancelled() => return Err(cancelled_error()),
+        }
+    }
+}
+
+pub async fn execute(state: &AppState, tool: &ConfiguredTool, params: &Value, task_id: &str, app: &AppHandle, timeout: Option<Duration>) -> Result<String> {
+    let url_template = tool.webhook_url.as_deref().ok_or_else(|| AppError::Config("Tool is Webhook runtime but has no webhook_url".to_string()))?;
+    let method_name = tool.webhook_method.as_deref().map(str::trim).filter(|m| !m.is_empty()).unwrap_or("POST").to_uppercase();
+    let method = Method::from_bytes(method_name.as_bytes()).map_err(|_| AppError::Config(format!("Invalid HTTP method '{}'", method_name)))?;
+
+    let mut url = build_url(tool, url_template, &method, params)?;
+    let mut headers = parse_headers(tool, params)?;
+    let body = build_body(tool, &method, params, &mut headers)?;
+    if let Some(auth) = &tool.webhook_auth {
+        apply_auth(auth, &tool.allowed_secrets, &mut url, &mut headers, body.as_deref().unwrap_or_default())?;
+    }
+
+    let mut builder = state.http_client.request(method, url).headers(headers);
+    if let Some(body) = body {
+        builder = builder.body(body);
+    }
+    let request = builder.build().map_err(request_error)?;
+
+    let registration = ProcessRegistration::new(state, task_id, None);
+    let sending = async {
+        tokio::select! {
+            result = send_with_retries(state, tool, request, &registration) => result,
+            _ = registration.until_cancelled() => Err(cancelled_error()),
+        }
+    };
+    let body = match timeout {
+        Some(limit) => tokio::time::timeout(limit, sending).await.map_err(|_| timeout_error(limit))??,
+        None => sending.await?,
+    };
+
+    let output = extract_output(tool, &body)?;
+    // The response isn't streamed, so it goes out as a single chunk.
+    app.emit_all("tool-output", json!({ "taskId": task_id, "chunk": &output })).ok();
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_escapes_for_the_context() {
+        let params = json!({ "q": "a b&c", "user": { "name": "Ann \"A\"" }, "ids": [1, 2] });
+        assert_eq!(render("/search?q={{q}}", &params, Context::Url, &[]).unwrap(), "/search?q=a%20b%26c");
+        assert_eq!(render("q={{ q }}", &params, Context::Form, &[]).unwrap(), "q=a+b%26c");
+        assert_eq!(render("{{user.name}}", &params, Context::Text, &[]).unwrap(), "Ann \"A\"");
+        assert_eq!(
+            render(r#"{"name": "{{user.name}}", "ids": {{ids}}, "first": {{ids.0}}}"#, &params, Context::Json, &[]).unwrap(),
+            r#"{"name": "Ann \"A\"", "ids": [1,2], "first": 1}"#,
+        );
+    }
+
+    #[test]
+    fn render_leaves_missing_params_empty() {
+        assert_eq!(render("a{{missing}}b", &json!({}), Context::Text, &[]).unwrap(), "ab");
+        assert_eq!(render(r#"{"x": {{missing}}}"#, &json!({}), Context::Json, &[]).unwrap(), r#"{"x": null}"#);
+    }
+
+    #[test]
+    fn render_rejects_line_breaks_in_headers() {
+        assert!(render("Bearer {{token}}", &json!({ "token": "x\r\nHost: evil" }), Context::Header, &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn request_errors_leave_out_the_url() {
+        let error = reqwest::Client::new().get("http://127.0.0.1:1/hook?api_key=topsecret").send().await.unwrap_err();
+        assert!(error.to_string().contains("topsecret"));
+        assert!(!request_error(error).to_string().contains("topsecret"));
+    }
+
+    #[test]
+    fn render_refuses_secrets_the_tool_is_not_allowed() {
+        assert!(render("{{secret:API_TOKEN}}", &json!({}), Context::Header, &["OTHER".to_string()]).is_err());
+    }
+}
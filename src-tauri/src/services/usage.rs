@@ -0,0 +1,170 @@
+// src-tauri/src/services/usage.rs
+use crate::{
+    database::{models, queries},
+    error::Result,
+    services::proxy_types::ProxyUsage,
+    state::AppState,
+};
+use chrono::{Datelike, Local};
+use serde_json::json;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Token counts of a finished call.
+#[derive(Clone, Copy)]
+pub struct CallUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub images: u32,
+    pub estimated: bool,
+}
+
+impl CallUsage {
+    pub fn estimated(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self { prompt_tokens, completion_tokens, images: 0, estimated: true }
+    }
+
+    /// Uses the counts reported by the provider, falling back to `estimate()` as (prompt, completion).
+    pub fn reported_or(reported: Option<&ProxyUsage>, estimate: impl FnOnce() -> (u32, u32)) -> Self {
+        match reported.filter(|u| u.prompt_tokens > 0 || u.completion_tokens > 0) {
+            Some(usage) => Self { prompt_tokens: usage.prompt_tokens, completion_tokens: usage.completion_tokens, images: 0, estimated: false },
+            None => {
+                let (prompt_tokens, completion_tokens) = estimate();
+                Self::estimated(prompt_tokens, completion_tokens)
+            }
+        }
+    }
+}
+
+/// A model call being timed. Start it right before the request and `finish` it once the
+/// response (or error) is in.
+pub struct UsageCall {
+    provider_id: String,
+    provider_name: String,
+    model: String,
+    kind: &'static str,
+    purpose: String,
+    started: Instant,
+}
+
+impl UsageCall {
+    pub fn start(provider: &models::ApiProvider, model: &str, kind: &'static str, purpose: &str) -> Self {
+        Self {
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            model: model.to_string(),
+            kind,
+            purpose: purpose.to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Stores the call. Failures are only logged: accounting must never break the feature that made the call.
+    pub fn finish(self, state: &AppState, usage: CallUsage, error: Option<String>) {
+        if let Err(e) = self.store(state, usage, error) {
+            log::warn!("[Usage] Failed to record {} call to {}: {}", self.purpose, self.model, e);
+        }
+    }
+
+    fn store(&self, state: &AppState, usage: CallUsage, error: Option<String>) -> Result<()> {
+        let conn = state.db.lock().unwrap();
+        let settings = queries::get_settings(&conn)?.usage;
+        if !settings.enabled {
+            return Ok(());
+        }
+        let cost = price_for(&settings.prices, &self.provider_id, &self.model).map_or(0.0, |price| {
+            usage.prompt_tokens as f64 * price.input_per_million / 1_000_000.0
+                + usage.completion_tokens as f64 * price.output_per_million / 1_000_000.0
+                + usage.images as f64 * price.per_image
+        });
+        let record = models::UsageRecord {
+            id: Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            provider_id: self.provider_id.clone(),
+            provider_name: self.provider_name.clone(),
+            model: self.model.clone(),
+            kind: self.kind.to_string(),
+            purpose: self.purpose.clone(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            images: usage.images,
+            estimated: usage.estimated,
+            latency_ms: self.started.elapsed().as_millis() as i64,
+            success: error.is_none(),
+            error,
+            cost,
+        };
+        queries::insert_usage_record(&conn, &record)
+    }
+}
+
+/// Finds the price entry for a model. An exact name wins over a prefix match (a dated or
+/// vendor-prefixed variant such as "openai/gpt-4o-2024-08-06"); among equal matches, a
+/// provider-specific entry wins over a generic one.
+pub fn price_for<'a>(prices: &'a [models::ModelPrice], provider_id: &str, model: &str) -> Option<&'a models::ModelPrice> {
+    let model = model.to_lowercase();
+    let base = model.rsplit('/').next().unwrap_or(&model);
+    prices.iter()
+        .filter(|p| p.provider_id.is_empty() || p.provider_id == provider_id)
+        .filter_map(|p| {
+            let name = p.model.to_lowercase();
+            let rank = if name == model || name == base {
+                usize::MAX
+            } else if base.starts_with(&format!("{}-", name)) {
+                name.len()
+            } else {
+                return None;
+            };
+            Some(((rank, !p.provider_id.is_empty()), p))
+        })
+        .max_by_key(|(key, _)| *key)
+        .map(|(_, p)| p)
+}
+
+fn month_start() -> (String, i64) {
+    let now = Local::now();
+    let start = now.date_naive().with_day(1).and_then(|d| d.and_hms_opt(0, 0, 0))
+        .and_then(|dt| dt.and_local_timezone(Local).earliest())
+        .map_or(0, |dt| dt.timestamp_millis());
+    (now.format("%Y-%m").to_string(), start)
+}
+
+pub fn budget_status(conn: &rusqlite::Connection, settings: &models::UsageSettings) -> Result<models::UsageBudgetStatus> {
+    let (month, since) = month_start();
+    Ok(models::UsageBudgetStatus {
+        month,
+        spent: queries::total_usage_cost_since(conn, since)?,
+        budget: settings.monthly_budget.filter(|b| *b > 0.0),
+    })
+}
+
+/// Emits `usage-budget-alert` the first time this month's spending reaches the alert threshold,
+/// and again when it exceeds the budget.
+pub fn check_budget(app: &AppHandle, state: &AppState) -> Result<()> {
+    let (status, level) = {
+        let conn = state.db.lock().unwrap();
+        let settings = queries::get_settings(&conn)?.usage;
+        let status = budget_status(&conn, &settings)?;
+        let Some(budget) = status.budget else { return Ok(()) };
+        let level = if status.spent >= budget {
+            "exceeded"
+        } else if status.spent >= budget * settings.alert_threshold {
+            "warning"
+        } else {
+            return Ok(());
+        };
+        if !queries::record_budget_alert(&conn, &status.month, level)? {
+            return Ok(());
+        }
+        (status, level)
+    };
+    log::warn!("[Usage] Monthly budget {}: spent ${:.2} of ${:.2}", level, status.spent, status.budget.unwrap_or_default());
+    app.emit_all("usage-budget-alert", json!({
+        "level": level,
+        "month": status.month,
+        "spent": status.spent,
+        "budget": status.budget,
+    }))?;
+    Ok(())
+}
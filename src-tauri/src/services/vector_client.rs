@@ -6,7 +6,7 @@ use crate::{
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-const VECTOR_DB_NAME: &str = "nexus_db";
+pub const VECTOR_DB_NAME: &str = "nexus_db";
 const VECTOR_COLLECTION_NAME: &str = "knowledge_base";
 
 #[derive(Serialize)]
@@ -104,11 +104,16 @@ async fn post<T: Serialize>(state: &AppState, url: &str, body: &T) -> Result<()>
 }
 
 pub async fn ensure_collection(state: &AppState, backend_url: &str) -> Result<()> {
+    ensure_named_collection(state, backend_url, VECTOR_COLLECTION_NAME).await
+}
+
+/// Like `ensure_collection`, for collections other than the knowledge base (e.g. conversation memory).
+pub async fn ensure_named_collection(state: &AppState, backend_url: &str, collection: &str) -> Result<()> {
     let url = format!("{}/api/v1/vector/ensure-collection", backend_url);
     let payload = EnsureCollectionPayload {
         base: VectorBase {
             database: VECTOR_DB_NAME,
-            collection: VECTOR_COLLECTION_NAME,
+            collection,
         },
     };
     post(state, &url, &payload).await
@@ -162,12 +167,20 @@ pub async fn update_metadata(
 pub async fn clear_collection(
     state: &AppState,
     backend_url: &str,
+) -> Result<()> {
+    clear_named_collection(state, backend_url, VECTOR_COLLECTION_NAME).await
+}
+
+pub async fn clear_named_collection(
+    state: &AppState,
+    backend_url: &str,
+    collection: &str,
 ) -> Result<()> {
     let url = format!("{}/api/v1/vector/clear-collection", backend_url);
     let payload = ClearCollectionPayload {
         base: VectorBase {
             database: VECTOR_DB_NAME,
-            collection: VECTOR_COLLECTION_NAME,
+            collection,
         },
     };
     post(state, &url, &payload).await
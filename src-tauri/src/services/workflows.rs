@@ -1,3 +1,362 @@
 // src-tauri/src/services/workflows.rs
-// This module is deprecated and its functionality has been replaced by the dynamic tools system.
-// The code has been removed to eliminate dead_code warnings and keep the codebase clean.
\ No newline at end of file
+// Runs saved workflows: steps in dependency order, each step's templates rendered from the run
+// input and the outputs of earlier steps. Progress goes out as `workflow-progress` events and the
+// outcome as `workflow-complete`; every run is stored with the result of each step.
+use crate::{
+    database::{models, queries},
+    error::{AppError, Result},
+    knowledge_base::searcher,
+    services::{
+        chat::{llm_utils, templates::{lookup, text_of, PATH_PLACEHOLDER}},
+        tools::{self, ProcessRegistration},
+    },
+    state::AppState,
+    system::clipboard,
+};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+/// Tool ID prefix under which workflows run.
+pub const TOOL_PREFIX: &str = "workflow::";
+
+/// Operators of step conditions, checked in this order.
+const OPERATORS: [&str; 4] = [" != ", " == ", " contains ", " matches "];
+
+/// Steps that must finish before each step, by index, with the implicit "previous step" resolved.
+fn dependencies(workflow: &models::Workflow) -> Result<Vec<Vec<usize>>> {
+    let index: HashMap<&str, usize> = workflow.steps.iter().enumerate().map(|(i, step)| (step.id.as_str(), i)).collect();
+    workflow.steps.iter().enumerate()
+        .map(|(i, step)| match &step.depends_on {
+            None => Ok(if i == 0 { vec![] } else { vec![i - 1] }),
+            Some(ids) => ids.iter()
+                .map(|id| index.get(id.as_str()).copied().ok_or_else(|| AppError::Config(format!("Step '{}' depends on unknown step '{}'", step.id, id))))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Step indices in an order that respects dependencies, keeping list order where it can.
+fn execution_order(workflow: &models::Workflow, dependencies: &[Vec<usize>]) -> Result<Vec<usize>> {
+    let mut done = vec![false; workflow.steps.len()];
+    let mut order = Vec::with_capacity(workflow.steps.len());
+    while order.len() < workflow.steps.len() {
+        let next = (0..workflow.steps.len()).find(|&i| !done[i] && dependencies[i].iter().all(|&d| done[d]));
+        match next {
+            Some(i) => {
+                done[i] = true;
+                order.push(i);
+            }
+            None => {
+                let stuck: Vec<&str> = (0..workflow.steps.len()).filter(|&i| !done[i]).map(|i| workflow.steps[i].id.as_str()).collect();
+                return Err(AppError::Config(format!("Steps depend on each other in a cycle: {}", stuck.join(", "))));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Checks that the workflow can run: named, unique step IDs, known and acyclic dependencies.
+pub fn validate(workflow: &models::Workflow) -> Result<()> {
+    if workflow.name.trim().is_empty() {
+        return Err(AppError::Config("Workflow name cannot be empty".to_string()));
+    }
+    if workflow.steps.is_empty() {
+        return Err(AppError::Config("A workflow needs at least one step".to_string()));
+    }
+    let mut seen = HashSet::new();
+    for step in &workflow.steps {
+        if step.id.is_empty() || !step.id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) {
+            return Err(AppError::Config(format!("Invalid step ID '{}': use letters, digits, '_' and '-'", step.id)));
+        }
+        if !seen.insert(step.id.as_str()) {
+            return Err(AppError::Config(format!("Step ID '{}' is used twice", step.id)));
+        }
+        if let models::WorkflowAction::Tool { tool_id, .. } = &step.action {
+            if tool_id.starts_with(TOOL_PREFIX) {
+                return Err(AppError::Config(format!("Step '{}' runs a workflow; workflows can't be nested", step.id)));
+            }
+        }
+    }
+    execution_order(workflow, &dependencies(workflow)?).map(|_| ())
+}
+
+/// Fills `{{name}}` placeholders from the run's variables; unknown ones become empty.
+fn render(template: &str, vars: &Value) -> String {
+    PATH_PLACEHOLDER.replace_all(template, |caps: &regex::Captures| lookup(vars, &caps[1]).map(text_of).unwrap_or_default()).into_owned()
+}
+
+/// Renders every string in `params`. A string that is a single placeholder takes the value
+/// as it is, so numbers, lists and objects keep their type.
+fn render_value(params: &Value, vars: &Value) -> Value {
+    match params {
+        Value::String(template) => match PATH_PLACEHOLDER.captures(template.trim()) {
+            Some(caps) if caps[0].len() == template.trim().len() => lookup(vars, &caps[1]).cloned().unwrap_or(Value::Null),
+            _ => Value::String(render(template, vars)),
+        },
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_value(item, vars)).collect()),
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), render_value(v, vars))).collect()),
+        other => other.clone(),
+    }
+}
+
+fn truthy(text: &str) -> bool {
+    !matches!(text.trim().to_lowercase().as_str(), "" | "false" | "0" | "no" | "null")
+}
+
+/// Evaluates a step condition. Both sides are rendered after splitting on the operator, so
+/// step outputs can't change how the condition is read.
+fn condition_holds(condition: &str, vars: &Value) -> Result<bool> {
+    let Some((operator, left, right)) = OPERATORS.iter().find_map(|op| condition.split_once(op).map(|(l, r)| (op.trim(), l, r))) else {
+        return Ok(truthy(&render(condition, vars)));
+    };
+    let (left, right) = (render(left, vars).trim().to_string(), render(right, vars).trim().to_string());
+    Ok(match operator {
+        "==" => left == right,
+        "!=" => left != right,
+        "contains" => left.to_lowercase().contains(&right.to_lowercase()),
+        _ => Regex::new(&right).map_err(|e| AppError::Config(format!("Invalid pattern in condition '{}': {}", condition, e)))?.is_match(&left),
+    })
+}
+
+/// Everything a step's templates can refer to.
+fn initial_vars(params: &Value) -> Value {
+    let input = ["input", "stdin"].iter().find_map(|key| params.get(key)).map(text_of).unwrap_or_default();
+    json!({ "input": input, "params": params, "previous": "", "steps": {} })
+}
+
+fn record_step(vars: &mut Value, result: &models::WorkflowStepResult) {
+    let mut step = json!({ "output": result.output, "status": result.status, "error": result.error });
+    if let Ok(parsed) = serde_json::from_str::<Value>(&result.output) {
+        step["json"] = parsed;
+    }
+    vars["steps"][&result.step_id] = step;
+    if result.status == models::WorkflowStepStatus::Success {
+        vars["previous"] = Value::String(result.output.clone());
+    }
+}
+
+async fn execute_step(app: &AppHandle, state: &AppState, action: &models::WorkflowAction, vars: &Value, task_id: &str, source: &str) -> Result<String> {
+    match action {
+        models::WorkflowAction::Tool { tool_id, params } => {
+            tools::execute(state, tool_id, render_value(params, vars), task_id, app, source).await
+        }
+        models::WorkflowAction::Prompt { prompt } => llm_utils::complete_prompt(state, "workflow", render(prompt, vars)).await,
+        models::WorkflowAction::KbSearch { query, top_k } => {
+            let settings = queries::get_settings(&state.db.lock().unwrap())?.knowledge_base;
+            let sources = searcher::search(state, render(query, vars), None, top_k.unwrap_or(settings.top_k), settings.score_threshold).await?;
+            Ok(sources.iter()
+                .map(|source| format!("{} ({}):\n{}", source.source_name, source.file_path, source.content_snippet))
+                .collect::<Vec<_>>()
+                .join("\n\n"))
+        }
+        models::WorkflowAction::ClipboardWrite { text } => {
+            let text = render(text, vars);
+            clipboard::write_text(&text)?;
+            Ok(text)
+        }
+        models::WorkflowAction::SaveToKb { content } => {
+            tools::execute(state, "built_in::save_to_kb", json!({ "stdin": render(content, vars) }), task_id, app, source).await
+        }
+    }
+}
+
+fn emit_progress(app: &AppHandle, run: &models::WorkflowRun, step_id: &str, stage: &str, result: Option<&models::WorkflowStepResult>) {
+    app.emit_all("workflow-progress", json!({
+        "runId": run.id,
+        "workflowId": run.workflow_id,
+        "stepId": step_id,
+        "stage": stage,
+        "result": result,
+    })).ok();
+}
+
+/// Runs a step, with its retries, until it succeeds, runs out of attempts or the run is cancelled.
+async fn run_step(app: &AppHandle, state: &AppState, registration: &ProcessRegistration<'_>, step: &models::WorkflowStep, vars: &Value, run_id: &str, source: &str) -> models::WorkflowStepResult {
+    let started = Instant::now();
+    let task_id = format!("{}:{}", run_id, step.id);
+    let mut attempts = 0;
+    let outcome = loop {
+        attempts += 1;
+        let execution = execute_step(app, state, &step.action, vars, &task_id, source);
+        tokio::pin!(execution);
+        let result = tokio::select! {
+            result = &mut execution => result,
+            // The step is still alive here, so a running tool is found and stopped with its children.
+            _ = registration.until_cancelled() => {
+                tools::cancel(state, &task_id);
+                Err(AppError::Cancelled("Stopped by the user".to_string()))
+            }
+        };
+        match result {
+            Err(e) if attempts <= step.retries && !registration.cancelled() && !matches!(e, AppError::Cancelled(_)) => {
+                log::warn!("[Workflows] Step {} failed (attempt {}): {}, retrying", step.id, attempts, e);
+            }
+            result => break result,
+        }
+    };
+    let (status, output, error) = match outcome {
+        Ok(output) => (models::WorkflowStepStatus::Success, output, None),
+        Err(e) => (models::WorkflowStepStatus::Failed, String::new(), Some(e.to_string())),
+    };
+    models::WorkflowStepResult { step_id: step.id.clone(), status, output, error, attempts, duration_ms: started.elapsed().as_millis() as i64 }
+}
+
+fn skipped(step: &models::WorkflowStep) -> models::WorkflowStepResult {
+    models::WorkflowStepResult { step_id: step.id.clone(), status: models::WorkflowStepStatus::Skipped, output: String::new(), error: None, attempts: 0, duration_ms: 0 }
+}
+
+/// Runs the workflow with `params` (`input` or `stdin` become `{{input}}`) under `run_id`.
+pub async fn run(app: &AppHandle, state: &AppState, workflow_id: &str, params: Value, run_id: &str, source: &str) -> Result<models::WorkflowRun> {
+    let (workflow, history_settings) = {
+        let conn = state.db.lock().unwrap();
+        let workflow = queries::get_workflow_by_id(&conn, workflow_id)?.ok_or_else(|| AppError::Internal(format!("Workflow with ID {} not found", workflow_id)))?;
+        (workflow, queries::get_settings(&conn)?.execution.history)
+    };
+    let dependencies = dependencies(&workflow)?;
+    let order = execution_order(&workflow, &dependencies)?;
+    log::info!("[Workflows] Running '{}' as {}", workflow.name, run_id);
+
+    let started = Instant::now();
+    let registration = ProcessRegistration::new(state, run_id, None);
+    let mut vars = initial_vars(&params);
+    let mut run = models::WorkflowRun {
+        id: run_id.to_string(),
+        workflow_id: workflow.id.clone(),
+        started_at: chrono::Utc::now().timestamp_millis(),
+        status: models::WorkflowRunStatus::Running,
+        input: params,
+        steps: Vec::with_capacity(order.len()),
+        output: String::new(),
+        error: None,
+        duration_ms: 0,
+        source: source.to_string(),
+    };
+    let mut statuses: HashMap<usize, models::WorkflowStepStatus> = HashMap::new();
+
+    for index in order {
+        if registration.cancelled() {
+            run.status = models::WorkflowRunStatus::Cancelled;
+            break;
+        }
+        let step = &workflow.steps[index];
+        let blocked = dependencies[index].iter().any(|d| statuses.get(d) == Some(&models::WorkflowStepStatus::Skipped));
+        let runs = match &step.condition {
+            Some(condition) if !blocked => condition_holds(condition, &vars),
+            _ => Ok(!blocked),
+        };
+        let result = match runs {
+            Ok(true) => {
+                emit_progress(app, &run, &step.id, "started", None);
+                run_step(app, state, &registration, step, &vars, run_id, source).await
+            }
+            Ok(false) => skipped(step),
+            // A condition that can't be evaluated fails the step, so `on_error` decides what happens.
+            Err(e) => models::WorkflowStepResult { error: Some(e.to_string()), status: models::WorkflowStepStatus::Failed, ..skipped(step) },
+        };
+        emit_progress(app, &run, &step.id, "finished", Some(&result));
+        record_step(&mut vars, &result);
+        statuses.insert(index, result.status);
+
+        let failed = result.status == models::WorkflowStepStatus::Failed;
+        let error = result.error.clone();
+        if result.status == models::WorkflowStepStatus::Success {
+            run.output = result.output.clone();
+        }
+        run.steps.push(result);
+        if registration.cancelled() {
+            run.status = models::WorkflowRunStatus::Cancelled;
+            break;
+        }
+        if failed && step.on_error == models::StepErrorPolicy::Stop {
+            run.status = models::WorkflowRunStatus::Failed;
+            run.error = Some(format!("Step '{}' failed: {}", step.id, error.unwrap_or_default()));
+            break;
+        }
+    }
+    if run.status == models::WorkflowRunStatus::Running {
+        run.status = models::WorkflowRunStatus::Success;
+    }
+    run.duration_ms = started.elapsed().as_millis() as i64;
+
+    if history_settings.enabled {
+        if let Err(e) = store(state, &run, &history_settings) {
+            log::warn!("[Workflows] Failed to record run {}: {}", run.id, e);
+        }
+    }
+    app.emit_all("workflow-complete", json!({
+        "runId": run.id,
+        "workflowId": run.workflow_id,
+        "status": run.status,
+        "output": run.output,
+        "error": run.error,
+    })).ok();
+    Ok(run)
+}
+
+fn store(state: &AppState, run: &models::WorkflowRun, settings: &models::ExecutionHistorySettings) -> Result<()> {
+    let conn = state.db.lock().unwrap();
+    queries::insert_workflow_run(&conn, run)?;
+    let before = (settings.retention_days > 0)
+        .then(|| chrono::Utc::now().timestamp_millis() - settings.retention_days as i64 * 24 * 60 * 60 * 1000);
+    let keep = Some(settings.max_records).filter(|max| *max > 0);
+    queries::prune_workflow_runs(&conn, before, keep)?;
+    Ok(())
+}
+
+/// Runs a workflow the way a tool runs: its output on success, the failure as an error.
+pub async fn run_as_tool(app: &AppHandle, state: &AppState, workflow_id: &str, params: Value, task_id: &str, source: &str) -> Result<String> {
+    let run = run(app, state, workflow_id, params, task_id, source).await?;
+    match run.status {
+        models::WorkflowRunStatus::Success => Ok(run.output),
+        models::WorkflowRunStatus::Cancelled => Err(AppError::Cancelled("Stopped by the user".to_string())),
+        _ => Err(AppError::Internal(run.error.unwrap_or_else(|| "Workflow failed".to_string()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, depends_on: Option<&[&str]>) -> models::WorkflowStep {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": "prompt",
+            "prompt": "",
+            "dependsOn": depends_on,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn conditions_compare_rendered_sides() {
+        let vars = json!({ "previous": "Status: OK", "steps": { "a": { "output": "3" } } });
+        assert!(condition_holds("{{steps.a.output}} == 3", &vars).unwrap());
+        assert!(condition_holds("{{previous}} contains ok", &vars).unwrap());
+        assert!(condition_holds("{{previous}} matches ^Status: \\w+$", &vars).unwrap());
+        assert!(!condition_holds("{{steps.b.output}}", &vars).unwrap());
+        assert!(condition_holds("{{previous}} matches (", &vars).is_err());
+    }
+
+    #[test]
+    fn single_placeholders_keep_their_type() {
+        let vars = json!({ "steps": { "a": { "json": { "n": 2, "tags": ["x"] } } }, "input": "hi" });
+        let params = json!({ "n": "{{steps.a.json.n}}", "tags": "{{ steps.a.json.tags }}", "text": "say {{input}}" });
+        assert_eq!(render_value(&params, &vars), json!({ "n": 2, "tags": ["x"], "text": "say hi" }));
+    }
+
+    #[test]
+    fn steps_run_after_their_dependencies() {
+        let workflow = models::Workflow {
+            steps: vec![step("c", Some(&["b"])), step("a", Some(&[])), step("b", None)],
+            ..serde_json::from_value(json!({ "id": "w", "name": "W", "steps": [] })).unwrap()
+        };
+        let dependencies = dependencies(&workflow).unwrap();
+        assert_eq!(execution_order(&workflow, &dependencies).unwrap(), [1, 2, 0]);
+
+        let cyclic = models::Workflow { steps: vec![step("a", Some(&["b"])), step("b", Some(&["a"]))], ..workflow };
+        assert!(validate(&cyclic).is_err());
+    }
+}
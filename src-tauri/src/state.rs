@@ -11,11 +11,20 @@ use std::sync::{atomic::AtomicBool, Arc, Mutex};
 
 pub type AppState = Arc<AppStateInner>;
 
+/// A tool, shell command or code run in progress. The process leads its own process group,
+/// so cancelling it also stops everything it started.
+pub struct RunningProcess {
+    pub pid: Option<u32>,
+    pub cancelled: AtomicBool,
+}
+
 pub struct AppStateInner {
     pub db: Mutex<Connection>,
     pub http_client: Client,
     pub context: AppContext,
     pub running_chat_tasks: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// Keyed by task or execution ID.
+    pub running_processes: Mutex<HashMap<String, Arc<RunningProcess>>>,
     pub current_task_working_dir: Mutex<Option<PathBuf>>,
 }
 
@@ -47,6 +56,7 @@ impl AppStateInner {
             http_client: Client::new(),
             context,
             running_chat_tasks: Arc::new(Mutex::new(HashMap::new())),
+            running_processes: Mutex::new(HashMap::new()),
             current_task_working_dir: Mutex::new(None),
         })
     }
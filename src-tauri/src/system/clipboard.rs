@@ -103,6 +103,11 @@ pub async fn read_clipboard(_app_handle: &AppHandle) -> Result<Option<ClipboardP
     Ok(None)
 }
 
+pub fn write_text(text: &str) -> Result<()> {
+    let mut clipboard = Clipboard::new().map_err(|e| AppError::Internal(e.to_string()))?;
+    clipboard.set_text(text).map_err(|e| AppError::Internal(format!("Failed to write to the clipboard: {}", e)))
+}
+
 pub async fn start_clipboard_monitor(app: AppHandle) {
     tokio::spawn(async move {
         let mut last_content_json = json!(null);